@@ -0,0 +1,69 @@
+//! Count the heap allocations made on the Rust side when sending a two-frame message built from
+//! an array rather than a `Vec`. Run with `cargo bench --bench send_array`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use async_zmq::{context::Context, publish, Result, SinkExt};
+
+const ROUNDS: usize = 100_000;
+
+/// System allocator counting the allocations it makes.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn report(name: &str, elapsed: Duration, allocations: usize) {
+    println!(
+        "{:<6} {:>8.2} µs/message {:>6.2} allocations/message",
+        name,
+        elapsed.as_secs_f64() * 1e6 / ROUNDS as f64,
+        allocations as f64 / ROUNDS as f64
+    );
+}
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let context = Context::new();
+
+    // Without subscribers, PUB drops messages right away, so only the send path is measured.
+    // Frames are copied into buffers allocated by ØMQ itself, which are not counted.
+    let mut array = publish::<std::array::IntoIter<&str, 2>, &str>("inproc://bench-array")?
+        .with_context(&context)
+        .bind()?;
+    let started = Instant::now();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ROUNDS {
+        array.send(["topic", "body"].into()).await?;
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    report("array", started.elapsed(), allocations);
+
+    let mut vec = publish::<std::vec::IntoIter<&str>, &str>("inproc://bench-vec")?
+        .with_context(&context)
+        .bind()?;
+    let started = Instant::now();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ROUNDS {
+        vec.send(vec!["topic", "body"].into()).await?;
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    report("vec", started.elapsed(), allocations);
+
+    Ok(())
+}
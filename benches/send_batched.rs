@@ -0,0 +1,54 @@
+//! Compare sending messages with `send_all_batched` against sending them one by one through the
+//! `Sink`. Run with `cargo bench --bench send_batched`.
+
+use std::time::{Duration, Instant};
+use std::vec::IntoIter;
+
+use async_zmq::{context::Context, publish, subscribe, Message, Result, SinkExt, StreamExt};
+
+const ROUNDS: usize = 100_000;
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:<10} {:>8.2} µs/message",
+        name,
+        elapsed.as_secs_f64() * 1e6 / ROUNDS as f64
+    );
+}
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let context = Context::new();
+    // No high water mark, so that every message is delivered and received.
+    let mut publish = publish::<IntoIter<Message>, Message>("inproc://bench")?
+        .with_context(&context)
+        .with_send_hwm(0)
+        .bind()?;
+    let mut subscribe = subscribe("inproc://bench")?
+        .with_context(&context)
+        .with_receive_hwm(0)
+        .connect()?;
+    subscribe.set_subscribe("")?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let started = Instant::now();
+    let mut batch = (0..ROUNDS).map(|idx| vec![Message::from(&idx.to_be_bytes()[..])].into());
+    publish.send_all_batched(&mut batch).await?;
+    for _ in 0..ROUNDS {
+        subscribe.next().await.unwrap()?;
+    }
+    report("batched", started.elapsed());
+
+    let started = Instant::now();
+    for idx in 0..ROUNDS {
+        publish
+            .send(vec![Message::from(&idx.to_be_bytes()[..])].into())
+            .await?;
+    }
+    for _ in 0..ROUNDS {
+        subscribe.next().await.unwrap()?;
+    }
+    report("sink", started.elapsed());
+
+    Ok(())
+}
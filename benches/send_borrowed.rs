@@ -0,0 +1,46 @@
+//! Compare sending borrowed frames with `send_borrowed` against copying them into owned
+//! `Vec<u8>` frames first. Run with `cargo bench --bench send_borrowed`.
+
+use std::time::{Duration, Instant};
+use std::vec::IntoIter;
+
+use async_zmq::{context::Context, publish, Result, SinkExt};
+
+const ROUNDS: usize = 10_000;
+const FRAME_LEN: usize = 64 * 1024;
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:<10} {:>8.2} µs/message",
+        name,
+        elapsed.as_secs_f64() * 1e6 / ROUNDS as f64
+    );
+}
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let context = Context::new();
+    let buffer = vec![42u8; 2 * FRAME_LEN];
+    let (header, body) = buffer.split_at(FRAME_LEN);
+
+    // Without subscribers, PUB drops messages right away, so only the send path is measured.
+    let mut publish = publish::<IntoIter<Vec<u8>>, Vec<u8>>("inproc://bench")?
+        .with_context(&context)
+        .bind()?;
+
+    let started = Instant::now();
+    for _ in 0..ROUNDS {
+        publish.send_borrowed(&[header, body]).await?;
+    }
+    report("borrowed", started.elapsed());
+
+    let started = Instant::now();
+    for _ in 0..ROUNDS {
+        publish
+            .send(vec![header.to_vec(), body.to_vec()].into())
+            .await?;
+    }
+    report("owned", started.elapsed());
+
+    Ok(())
+}
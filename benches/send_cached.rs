@@ -0,0 +1,44 @@
+//! Compare sending the same payload with `send_cached` against building a new message each
+//! time. Run with `cargo bench --bench send_cached`.
+
+use std::time::{Duration, Instant};
+use std::vec::IntoIter;
+
+use async_zmq::{context::Context, publish, Result, SinkExt};
+
+const ROUNDS: usize = 100_000;
+const FRAME_LEN: usize = 4 * 1024;
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:<10} {:>8.2} µs/message",
+        name,
+        elapsed.as_secs_f64() * 1e6 / ROUNDS as f64
+    );
+}
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let context = Context::new();
+    let heartbeat = vec![42u8; FRAME_LEN];
+
+    // Without subscribers, PUB drops messages right away, so only the send path is measured.
+    let mut publish = publish::<IntoIter<Vec<u8>>, Vec<u8>>("inproc://bench")?
+        .with_context(&context)
+        .bind()?;
+    publish.with_cached_frame("heartbeat", heartbeat.clone());
+
+    let started = Instant::now();
+    for _ in 0..ROUNDS {
+        publish.send_cached("heartbeat").await?;
+    }
+    report("cached", started.elapsed());
+
+    let started = Instant::now();
+    for _ in 0..ROUNDS {
+        publish.send(vec![heartbeat.clone()].into()).await?;
+    }
+    report("uncached", started.elapsed());
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-//! cargo run --example publish_hwm --features="rt-async-std"
+//! cargo run --example publish_hwm
 
 use async_zmq::{Result, SinkExt};
 use std::time::Duration;
@@ -6,9 +6,8 @@ use std::time::Duration;
 #[async_std::main]
 async fn main() -> Result<()> {
     // Create a publisher with a send high water mark of 1000 messages
-    let publisher = async_zmq::publish("tcp://127.0.0.1:5555")?
-        .bind()?
-        .set_send_hwm(1000)?;
+    let mut publisher = async_zmq::publish("tcp://127.0.0.1:5555")?.bind()?;
+    publisher.set_send_hwm(1000)?;
 
     println!("Publisher running with HWM: {}", publisher.get_send_hwm()?);
     println!("Press Ctrl+C to stop");
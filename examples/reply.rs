@@ -1,4 +1,4 @@
-//! cargo run --example reply --features="rt-tokio" --no-default-features
+//! cargo run --example reply
 
 use async_zmq::Result;
 use futures::StreamExt;
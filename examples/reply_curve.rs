@@ -11,7 +11,7 @@ async fn main() -> Result<()> {
     
     // Create a replier socket
     println!("Creating replier socket...");
-    let replier = async_zmq::reply("tcp://127.0.0.1:5555")?.bind()?;
+    let mut replier = async_zmq::reply("tcp://127.0.0.1:5555")?.bind()?;
     println!("Replier socket created and bound");
 
     // Set CURVE options for the replier
@@ -1,4 +1,4 @@
-//! cargo run --example request --features="rt-tokio" --no-default-features
+//! cargo run --example request
 
 use async_zmq::Result;
 use std::ops::Deref;
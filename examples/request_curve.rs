@@ -11,7 +11,7 @@ async fn main() -> Result<()> {
     
     // Create a requester socket
     println!("Creating requester socket...");
-    let requester = async_zmq::request("tcp://127.0.0.1:5555")?.connect()?;
+    let mut requester = async_zmq::request("tcp://127.0.0.1:5555")?.connect()?;
     println!("Requester socket created and connected");
 
     // Set CURVE options for the requester
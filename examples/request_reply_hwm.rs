@@ -1,10 +1,11 @@
-//! cargo run --example request_reply_hwm --features="rt-async-std"
+//! cargo run --example request_reply_hwm
 
 use async_zmq::Result;
 use async_std::task;
+use futures::future::{self, Either};
 
 async fn run_server() -> Result<()> {
-    let server = async_zmq::reply("tcp://127.0.0.1:5555")?.bind()?;
+    let mut server = async_zmq::reply("tcp://127.0.0.1:5555")?.bind()?;
 
     server
         .set_receive_hwm(1000)?
@@ -24,11 +25,12 @@ async fn run_server() -> Result<()> {
 }
 
 async fn run_client() -> Result<()> {
-    let client = async_zmq::request("tcp://127.0.0.1:5555")?.connect()?;
-
-    client
-        .set_receive_hwm(1000)?
-        .set_send_hwm(1000)?;
+    // High water marks only apply to connections established afterwards, so set them before
+    // connecting
+    let client = async_zmq::request("tcp://127.0.0.1:5555")?
+        .configure(|socket| socket.set_rcvhwm(1000))
+        .configure(|socket| socket.set_sndhwm(1000))
+        .connect()?;
 
     println!("Client running with HWM - Receive: {}, Send: {}", 
         client.get_receive_hwm()?, 
@@ -50,12 +52,15 @@ async fn run_client() -> Result<()> {
 
 #[async_std::main]
 async fn main() -> Result<()> {
-    // Run server in a separate task
-    task::spawn(run_server());
-    
-    // Wait a bit for server to start
-    task::sleep(std::time::Duration::from_secs(1)).await;
-    
-    // Run client
-    run_client().await
+    // Sockets are not `Sync`, so drive the server alongside the client on this task
+    let server = Box::pin(run_server());
+    let client = Box::pin(async {
+        // Wait a bit for server to start
+        task::sleep(std::time::Duration::from_secs(1)).await;
+        run_client().await
+    });
+
+    match future::select(server, client).await {
+        Either::Left((result, _)) | Either::Right((result, _)) => result,
+    }
 } 
\ No newline at end of file
@@ -1,13 +1,14 @@
-//! cargo run --example subscribe_hwm --features="rt-async-std"
+//! cargo run --example subscribe_hwm
 
 use async_zmq::{Result, StreamExt};
 
 #[async_std::main]
 async fn main() -> Result<()> {
-    // Create a subscriber with a receive high water mark of 1000 messages
-    let socket = async_zmq::subscribe("tcp://127.0.0.1:5555")?;
+    // Create a subscriber with a receive high water mark of 1000 messages. It is set before
+    // connecting, since it only applies to connections established afterwards.
+    let socket = async_zmq::subscribe("tcp://127.0.0.1:5555")?
+        .configure(|socket| socket.set_rcvhwm(1000));
     let mut subscriber = socket.connect()?;
-    subscriber.set_receive_hwm(1000)?;
 
     // Subscribe to a topic
     subscriber.set_subscribe("topic")?;
@@ -0,0 +1,36 @@
+//! Body frame compression used by `Publish::send_compressed`.
+//!
+//! A compressed frame starts with the 4-byte marker `\0LZ4`, followed by the uncompressed size
+//! as a little-endian `u32` and the LZ4 block of the body.
+
+use zmq::Message;
+
+/// Prefix of compressed frames.
+const MARKER: &[u8] = b"\0LZ4";
+
+/// LZ4 cannot shrink data by more than this ratio, anything above is a forged size.
+const MAX_RATIO: usize = 255;
+
+/// Build the compressed frame of `body`.
+pub(crate) fn compress(body: &[u8]) -> Message {
+    let mut frame = MARKER.to_vec();
+    frame.extend_from_slice(&lz4_flex::compress_prepend_size(body));
+    Message::from(frame)
+}
+
+/// Check whether `frame` was built by `compress`.
+pub(crate) fn is_compressed(frame: &[u8]) -> bool {
+    frame.starts_with(MARKER)
+}
+
+/// Get back the body of a compressed frame. Returns `None` for a malformed frame.
+pub(crate) fn decompress(frame: &[u8]) -> Option<Message> {
+    let block = frame.strip_prefix(MARKER)?;
+    let size = u32::from_le_bytes(block.get(..4)?.try_into().ok()?) as usize;
+    if size > block.len().saturating_mul(MAX_RATIO) {
+        return None;
+    }
+    lz4_flex::decompress_size_prepended(block)
+        .ok()
+        .map(Message::from)
+}
@@ -0,0 +1,364 @@
+//! ZMQ context shared between sockets
+//!
+//! [`Context`] is a ØMQ context, created by this crate or converted from a context of the zmq
+//! crate. On top of the ØMQ options, it keeps track of the `inproc://` endpoints bound by sockets
+//! built with it, so that binding the same name twice fails with
+//! [`SocketError::InprocNameTaken`] instead of an opaque ØMQ error.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{context::Context, Result};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let context = Context::new();
+//!     let pull = async_zmq::pull_in(&context, "inproc://events")?.bind()?;
+//!
+//!     assert_eq!(context.inproc_endpoints(), vec!["events".to_string()]);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Options shared by every socket of an application, such as the linger period, can be set once
+//! on the context with [`Context::set_default_socket_options`]. Options set on a
+//! [`SocketBuilder`], for example through [`configure`], override them.
+//!
+//! Sockets built without [`with_context`] share the process-wide default context returned by
+//! [`Context::default`], so `inproc://` endpoints work between them too.
+//!
+//! [`Context`]: struct.Context.html
+//! [`Context::set_default_socket_options`]: struct.Context.html#method.set_default_socket_options
+//! [`SocketBuilder`]: ../struct.SocketBuilder.html
+//! [`configure`]: ../struct.SocketBuilder.html#method.configure
+//! [`with_context`]: ../struct.SocketBuilder.html#method.with_context
+//! [`Context::default`]: struct.Context.html#impl-Default-for-Context
+//! [`SocketError::InprocNameTaken`]: ../enum.SocketError.html#variant.InprocNameTaken
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{sys::RawContext, SocketError};
+
+/// Context of the sockets built without `with_context`, created on first use.
+static DEFAULT: Lazy<Context> = Lazy::new(Context::new);
+
+/// The process-wide default context, borrowed for as long as the process runs.
+pub(crate) fn default_context() -> &'static Context {
+    &DEFAULT
+}
+
+/// A ØMQ context tracking the inproc endpoints bound through it.
+///
+/// Cloning the context is cheap and all clones share the same ØMQ context and registry.
+#[derive(Clone)]
+pub struct Context {
+    inner: Arc<Handle>,
+    inproc: Arc<Mutex<BTreeSet<String>>>,
+    open_sockets: Arc<AtomicUsize>,
+    default_options: Arc<Mutex<SocketOptions>>,
+}
+
+/// The ØMQ context itself.
+enum Handle {
+    /// Created by this crate, so that every option of the context can be reached.
+    Raw(RawContext),
+    /// Converted from the zmq crate, which doesn't give access to the context options but the
+    /// I/O threads.
+    Zmq(zmq::Context),
+}
+
+/// Socket options applied to every socket built from a [`Context`], see
+/// [`Context::set_default_socket_options`].
+///
+/// Options left to `None` keep the ØMQ default.
+///
+/// [`Context`]: struct.Context.html
+/// [`Context::set_default_socket_options`]: struct.Context.html#method.set_default_socket_options
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Linger period in milliseconds, `ZMQ_LINGER`.
+    pub linger: Option<i32>,
+    /// Whether to use IPv6 on top of IPv4, `ZMQ_IPV6`.
+    pub ipv6: Option<bool>,
+    /// Send high water mark, `ZMQ_SNDHWM`.
+    pub send_hwm: Option<i32>,
+    /// Receive high water mark, `ZMQ_RCVHWM`.
+    pub receive_hwm: Option<i32>,
+}
+
+impl SocketOptions {
+    /// Set the options which are not `None` on `socket`.
+    pub(crate) fn apply(&self, socket: &zmq::Socket) -> Result<(), zmq::Error> {
+        if let Some(linger) = self.linger {
+            socket.set_linger(linger)?;
+        }
+        if let Some(ipv6) = self.ipv6 {
+            socket.set_ipv6(ipv6)?;
+        }
+        if let Some(hwm) = self.send_hwm {
+            socket.set_sndhwm(hwm)?;
+        }
+        if let Some(hwm) = self.receive_hwm {
+            socket.set_rcvhwm(hwm)?;
+        }
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Create a new ØMQ context, separate from every other one including the default context.
+    pub fn new() -> Self {
+        Self::with_handle(Handle::Raw(RawContext::new()))
+    }
+
+    fn with_handle(handle: Handle) -> Self {
+        Self {
+            inner: Arc::new(handle),
+            inproc: Default::default(),
+            open_sockets: Default::default(),
+            default_options: Default::default(),
+        }
+    }
+
+    /// Create a socket of type `socket_type` in this context, without any of the tracking of a
+    /// [`SocketBuilder`], for example to answer ZAP requests.
+    ///
+    /// The socket doesn't keep a context created by [`Context::new`] alive: close it before the
+    /// last clone of the context is dropped, which otherwise waits for it to be closed.
+    ///
+    /// [`SocketBuilder`]: ../struct.SocketBuilder.html
+    /// [`Context::new`]: #method.new
+    pub fn socket(&self, socket_type: zmq::SocketType) -> Result<zmq::Socket, zmq::Error> {
+        match &*self.inner {
+            Handle::Raw(context) => context.socket(socket_type),
+            Handle::Zmq(context) => context.socket(socket_type),
+        }
+    }
+
+    /// Shut the context down without terminating it: blocking operations on its sockets return
+    /// `ETERM`, and so does any later operation except closing the socket.
+    ///
+    /// Fails with `ENOTSUP` for a context converted from a `zmq::Context`, which the zmq crate
+    /// can only terminate.
+    pub fn shutdown(&self) -> Result<(), zmq::Error> {
+        match &*self.inner {
+            Handle::Raw(context) => context.shutdown(),
+            Handle::Zmq(_) => Err(zmq::Error::ENOTSUP),
+        }
+    }
+
+    /// Get the size of the ØMQ thread pool handling I/O operations.
+    pub fn get_io_threads(&self) -> Result<i32, zmq::Error> {
+        match &*self.inner {
+            Handle::Raw(context) => context.get(zmq_sys::ZMQ_IO_THREADS),
+            Handle::Zmq(context) => context.get_io_threads(),
+        }
+    }
+
+    /// Set the size of the ØMQ thread pool handling I/O operations, before creating any socket.
+    pub fn set_io_threads(&self, value: i32) -> Result<(), zmq::Error> {
+        match &*self.inner {
+            Handle::Raw(context) => context.set(zmq_sys::ZMQ_IO_THREADS, value),
+            Handle::Zmq(context) => context.set_io_threads(value),
+        }
+    }
+
+    /// Get the maximum number of sockets of the context, `ZMQ_MAX_SOCKETS`.
+    ///
+    /// The limit of a context converted from a `zmq::Context`, which can't be changed, is the ØMQ
+    /// default.
+    pub fn get_max_sockets(&self) -> Result<i32, zmq::Error> {
+        match &*self.inner {
+            Handle::Raw(context) => context.get(zmq_sys::ZMQ_MAX_SOCKETS),
+            Handle::Zmq(_) => Ok(zmq_sys::ZMQ_MAX_SOCKETS_DFLT as i32),
+        }
+    }
+
+    /// Set the maximum number of sockets of the context, `ZMQ_MAX_SOCKETS`, before creating any
+    /// socket.
+    ///
+    /// Fails with `ENOTSUP` for a context converted from a `zmq::Context`, as the zmq crate
+    /// doesn't give access to the option.
+    pub fn set_max_sockets(&self, value: i32) -> Result<(), zmq::Error> {
+        match &*self.inner {
+            Handle::Raw(context) => context.set(zmq_sys::ZMQ_MAX_SOCKETS, value),
+            Handle::Zmq(_) => Err(zmq::Error::ENOTSUP),
+        }
+    }
+
+    /// List the names of the `inproc://` endpoints currently bound by sockets built with this
+    /// context, without the `inproc://` prefix.
+    pub fn inproc_endpoints(&self) -> Vec<String> {
+        self.inproc.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Apply `options` to every socket built from this context or its clones from now on.
+    ///
+    /// The options are set right after the socket is created, before the options of its
+    /// [`SocketBuilder`], which therefore take precedence. Sockets built earlier are not affected.
+    ///
+    /// [`SocketBuilder`]: ../struct.SocketBuilder.html
+    pub fn set_default_socket_options(&self, options: SocketOptions) {
+        *self.default_options.lock().unwrap() = options;
+    }
+
+    /// The options applied to every socket built from this context.
+    pub fn default_socket_options(&self) -> SocketOptions {
+        self.default_options.lock().unwrap().clone()
+    }
+
+    /// Compute the `ZMQ_AFFINITY` mask of socket number `socket_index` out of `total_sockets`,
+    /// so that the sockets are spread evenly over the I/O threads of the context.
+    ///
+    /// With at least as many sockets as I/O threads, each socket gets a single thread, in turn.
+    /// With fewer sockets, each one gets its own share of the threads. The mask has to be set
+    /// before the socket binds or connects:
+    ///
+    /// ```no_run
+    /// use async_zmq::{context::Context, Result};
+    ///
+    /// # fn main() -> Result<()> {
+    /// let context = Context::new();
+    /// context.set_io_threads(4)?;
+    /// for index in 0..8 {
+    ///     let mask = context.balanced_affinity(index, 8);
+    ///     let pull = async_zmq::pull_in(&context, format!("tcp://127.0.0.1:{}", 5555 + index))?
+    ///         .configure(move |socket| socket.set_affinity(mask))
+    ///         .bind()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Only the first 64 I/O threads can be addressed by a mask.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `socket_index` is not less than `total_sockets`.
+    pub fn balanced_affinity(&self, socket_index: usize, total_sockets: usize) -> u64 {
+        assert!(
+            socket_index < total_sockets,
+            "socket_index must be less than total_sockets"
+        );
+        let threads = self.get_io_threads().unwrap_or(1).clamp(1, 64) as usize;
+        if total_sockets >= threads {
+            return 1 << (socket_index % threads);
+        }
+        let first = socket_index * threads / total_sockets;
+        let last = (socket_index + 1) * threads / total_sockets;
+        (first..last).fold(0, |mask, thread| mask | 1 << thread)
+    }
+
+    /// Number of sockets that can still be built before reaching the `max_sockets` limit of the
+    /// context.
+    ///
+    /// Only the sockets built through [`SocketBuilder`] with this context are counted, along with
+    /// the two PAIR sockets monitoring each of them once monitored, sockets created directly with
+    /// [`socket`](#method.socket) are not. A server creating many sockets can
+    /// use this to shed new work before socket creation starts failing with
+    /// `SocketError::SocketLimitReached`.
+    ///
+    /// [`SocketBuilder`]: ../struct.SocketBuilder.html
+    pub fn sockets_remaining(&self) -> usize {
+        let max_sockets = self.get_max_sockets().unwrap_or_default().max(0) as usize;
+        max_sockets.saturating_sub(self.open_sockets.load(Ordering::Relaxed))
+    }
+
+    /// Number of handles keeping the underlying ØMQ context alive: this context, its clones, and
+    /// every socket built from it, each of which holds a reference of its own.
+    ///
+    /// The ØMQ context is only terminated once this drops to zero, so a count that stays above
+    /// one during shutdown points at a leaked clone or socket. Sockets created with the zmq crate
+    /// rather than a [`SocketBuilder`] are not counted.
+    ///
+    /// [`SocketBuilder`]: ../struct.SocketBuilder.html
+    pub fn ref_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// Count a socket as open until the returned value is dropped.
+    pub(crate) fn track_socket(&self) -> OpenSocket {
+        self.open_sockets.fetch_add(1, Ordering::Relaxed);
+        OpenSocket(self.open_sockets.clone())
+    }
+
+    /// Reserve an inproc name until the returned claim is dropped.
+    pub(crate) fn claim_inproc(&self, name: &str) -> Result<InprocClaim, SocketError> {
+        if !self.inproc.lock().unwrap().insert(name.to_owned()) {
+            return Err(SocketError::InprocNameTaken(name.to_owned()));
+        }
+
+        Ok(InprocClaim {
+            registry: self.inproc.clone(),
+            name: name.to_owned(),
+        })
+    }
+}
+
+impl Default for Context {
+    /// Get the process-wide default context, which sockets built without `with_context` use.
+    ///
+    /// It is created on first use and shared by every caller, so sockets built from it can
+    /// reach each other over `inproc://`, and its inproc names and open sockets are tracked as
+    /// for any other context. It is never terminated on its own; calling `shutdown` on it
+    /// affects every socket built without a context.
+    fn default() -> Self {
+        DEFAULT.clone()
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("open_sockets", &self.open_sockets.load(Ordering::Relaxed))
+            .field("inproc_endpoints", &self.inproc_endpoints())
+            .finish()
+    }
+}
+
+impl From<&Context> for Context {
+    fn from(context: &Context) -> Self {
+        context.clone()
+    }
+}
+
+impl From<&zmq::Context> for Context {
+    /// Wrap a clone of a context of the zmq crate, see `From<zmq::Context>`.
+    fn from(context: &zmq::Context) -> Self {
+        Self::from(context.clone())
+    }
+}
+
+impl From<zmq::Context> for Context {
+    /// Wrap a context of the zmq crate, with a registry of its own: convert it once and share the
+    /// result, so that every socket built with it is tracked together.
+    fn from(context: zmq::Context) -> Self {
+        Self::with_handle(Handle::Zmq(context))
+    }
+}
+
+/// Inproc name reserved in the registry of a [`Context`], released on drop.
+pub(crate) struct InprocClaim {
+    registry: Arc<Mutex<BTreeSet<String>>>,
+    name: String,
+}
+
+impl Drop for InprocClaim {
+    fn drop(&mut self) {
+        self.registry.lock().unwrap().remove(&self.name);
+    }
+}
+
+/// Socket counted as open by a [`Context`], until dropped.
+pub(crate) struct OpenSocket(Arc<AtomicUsize>);
+
+impl Drop for OpenSocket {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -2,6 +2,14 @@
 //!
 //! This module provides types and functions for working with CURVE security in ZMQ.
 //!
+//! # Switching roles
+//!
+//! The `set_curve_server` method of the sockets can turn a CURVE client into a server, which
+//! applies to the connections set up afterwards. In server mode ØMQ ignores any CURVE server key
+//! set before, which only matters to clients, so the socket handshakes with server semantics.
+//! ØMQ offers no way to erase that key: it stays stored, unused, until the socket becomes a
+//! client again.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -19,9 +27,12 @@
 //! }
 //! ```
 
+use std::convert::TryInto;
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 
+use crate::sys;
+
 /// A wrapper around zmq::CurveKeyPair that provides a more convenient API.
 ///
 /// This struct holds a CURVE key pair for use with ZMQ CURVE security.
@@ -35,6 +46,98 @@ impl CurveKeyPair {
     pub fn new() -> Result<Self, zmq::Error> {
         Ok(Self(zmq::CurveKeyPair::new()?))
     }
+
+    /// Rebuild a key pair from its Z85-encoded secret key, deriving the public key.
+    ///
+    /// This is the way to load a server identity kept as a single secret, for example in a
+    /// configuration file. Fails with `EINVAL` if `secret` is not a 40 character Z85 key.
+    pub fn from_secret_key(secret: &str) -> Result<Self, zmq::Error> {
+        let secret_key = decode_key(secret)?;
+        let public_key = decode_key(&sys::curve_public(secret)?)?;
+        Ok(Self(zmq::CurveKeyPair {
+            public_key,
+            secret_key,
+        }))
+    }
+
+    /// Rebuild a key pair from its Z85-encoded public and secret keys.
+    ///
+    /// Fails with `EINVAL` if either key is not a 40 character Z85 key, or if `public` is not the
+    /// public key of `secret`.
+    pub fn from_z85(public: &str, secret: &str) -> Result<Self, zmq::Error> {
+        let pair = Self::from_secret_key(secret)?;
+        if pair.public_key != decode_key(public)? {
+            return Err(zmq::Error::EINVAL);
+        }
+        Ok(pair)
+    }
+}
+
+/// Keys of a [`CurveKeyPair`] as serialized with serde, in Z85.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "CurveKeyPair")]
+struct Z85KeyPair {
+    public_key: String,
+    secret_key: String,
+}
+
+/// Serialize the key pair as its `public_key` and `secret_key`, both Z85-encoded. Unlike the
+/// `Debug` output, this includes the secret key.
+///
+/// Only available with the `serde` feature.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CurveKeyPair {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let keys = Z85KeyPair {
+            public_key: z85_encode(&self.public_key).map_err(S::Error::custom)?,
+            secret_key: z85_encode(&self.secret_key).map_err(S::Error::custom)?,
+        };
+        keys.serialize(serializer)
+    }
+}
+
+/// Deserialize a key pair serialized as Z85 strings, checking it as
+/// [`from_z85`](struct.CurveKeyPair.html#method.from_z85) does.
+///
+/// Only available with the `serde` feature.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CurveKeyPair {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let keys = Z85KeyPair::deserialize(deserializer)?;
+        Self::from_z85(&keys.public_key, &keys.secret_key)
+            .map_err(|_| D::Error::custom("invalid CURVE key pair"))
+    }
+}
+
+/// Encode binary data, such as a CURVE key, as Z85 text.
+///
+/// Fails with `EINVAL` if the length of `data` is not a multiple of 4.
+pub fn z85_encode(data: &[u8]) -> Result<String, zmq::Error> {
+    if !data.len().is_multiple_of(4) {
+        return Err(zmq::Error::EINVAL);
+    }
+    zmq::z85_encode(data).map_err(|_| zmq::Error::EINVAL)
+}
+
+/// Decode Z85 text, such as a CURVE key read from a configuration file, into binary data.
+///
+/// Fails with `EINVAL` if the length of `text` is not a multiple of 5, or if it holds characters
+/// outside of the Z85 alphabet.
+pub fn z85_decode(text: &str) -> Result<Vec<u8>, zmq::Error> {
+    if !text.len().is_multiple_of(5) {
+        return Err(zmq::Error::EINVAL);
+    }
+    sys::z85_decode(text)
+}
+
+/// Decode a Z85-encoded CURVE key.
+fn decode_key(key: &str) -> Result<[u8; 32], zmq::Error> {
+    z85_decode(key)?.try_into().map_err(|_| zmq::Error::EINVAL)
 }
 
 impl Deref for CurveKeyPair {
@@ -70,4 +173,4 @@ impl AsRef<zmq::CurveKeyPair> for CurveKeyPair {
     fn as_ref(&self) -> &zmq::CurveKeyPair {
         &self.0
     }
-} 
\ No newline at end of file
+}
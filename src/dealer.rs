@@ -6,9 +6,23 @@
 //! A dealer socket must be paired with a [`router`], [`reply`] or another
 //! dealer socket.
 //!
+//! Receiving is cancel safe, see [cancellation](../index.html#cancellation).
+//!
 //! # Example
 //!
 //! ```no_run
+//! use async_zmq::Result;
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let mut zmq = async_zmq::dealer("tcp://127.0.0.1:5555")?.connect()?;
+//!
+//!     // A REP peer expects an empty delimiter frame before the request.
+//!     zmq.send(vec!["", "request"].into()).await?;
+//!     let reply = zmq.recv().await?;
+//!     println!("{:?}", reply);
+//!     Ok(())
+//! }
 //! ```
 //!
 //! [`router`]: ../router/index.html
@@ -20,32 +34,261 @@
 //! [`StreamExt`]: ../trait.StreamExt.html
 
 use std::{
+    iter::once,
     pin::Pin,
     task::{Context, Poll},
 };
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Broker, Multipart, MultipartIter, SocketBuilder},
+    endpoint::Endpoint,
+    reactor::AsRawSocket,
+    socket::{
+        close_socket, set_identity, set_metadata, Broker, FromParts, Multipart, MultipartIter,
+        SendQueue, SocketBuilder, SocketParts,
+    },
     RecvError, SendError, Sink, SocketError, Stream,
 };
+use futures::future::poll_fn;
 use zmq::{Message, SocketType};
 
 /// Create a ZMQ socket with DEALER type
-pub fn dealer<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Dealer<I, T>>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn dealer<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Dealer<I, T>>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::DEALER, endpoint))
 }
 
+/// Create a ZMQ socket with DEALER type, built with `context`
+///
+/// This is [`dealer`](fn.dealer.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn dealer_in<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Dealer<I, T>>, SocketError> {
+    Ok(dealer(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with DEALER type
-pub struct Dealer<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Broker<I, T>);
+pub struct Dealer<I: Iterator<Item = T> + Unpin, T: Into<Message>>(pub(crate) Broker<I, T>);
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Dealer<I, T> {
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    /// Close the socket gracefully, completing once ØMQ is done with it.
+    ///
+    /// Messages waiting in the send queue are flushed first. ØMQ then keeps delivering the
+    /// messages it holds for the linger period of the socket, see `set_linger` on the raw
+    /// socket, and the returned future completes once they are all delivered or the linger
+    /// period is over, when ØMQ destroys the socket. With the default infinite linger period,
+    /// this waits until a peer receives the messages, so set a finite one to bound the wait.
+    pub async fn close(mut self) -> Result<(), SocketError> {
+        poll_fn(|cx| Pin::new(&mut self.0).poll_flush(cx)).await?;
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Send a multipart message, going through the send queue of the `Sink`.
+    ///
+    /// Unlike a request socket, a dealer can send any number of messages without waiting for
+    /// replies. This is the same as `SinkExt::send`, without importing it.
+    pub async fn send(&mut self, msg: MultipartIter<I, T>) -> Result<(), SendError> {
+        futures::SinkExt::send(self, msg).await
+    }
+
+    /// Receive a multipart message, the same as the next item of the `Stream`.
+    pub async fn recv(&self) -> Result<Multipart, RecvError> {
+        Ok(poll_fn(|cx| self.0.socket.recv(cx)).await?)
+    }
+
+    /// Send `msg` as a single-frame message, without building a [`MultipartIter`].
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    ///
+    /// [`MultipartIter`]: ../struct.MultipartIter.html
+    pub async fn send_msg(&mut self, msg: Message) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.0).poll_flush(cx)).await?;
+
+        let mut msg = MultipartIter(once(msg));
+        poll_fn(|cx| self.0.socket.send(cx, &mut msg)).await?;
+        Ok(())
+    }
+
+    /// Receive a single-frame message as a plain `Message`, without allocating a [`Multipart`].
+    ///
+    /// Only the first frame is returned; the frames following it in a multipart message are
+    /// received and discarded, so use the `Stream` for sockets carrying multipart messages.
+    ///
+    /// [`Multipart`]: ../struct.Multipart.html
+    pub async fn recv_msg(&self) -> Result<Message, RecvError> {
+        Ok(poll_fn(|cx| self.0.socket.recv_msg(cx)).await?)
+    }
+
+    /// Set the CURVE server flag on the socket.
+    ///
+    /// See [switching roles](../curve/index.html#switching-roles) for a socket which was a
+    /// client before.
+    pub fn set_curve_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the CURVE public key on the socket.
+    pub fn set_curve_publickey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_publickey(key)?;
+        Ok(self)
+    }
+
+    /// Set the CURVE secret key on the socket.
+    pub fn set_curve_secretkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_secretkey(key)?;
+        Ok(self)
+    }
+
+    /// Set the CURVE server key on the socket.
+    ///
+    /// This makes the socket a CURVE client, resetting the server flag if it was set.
+    pub fn set_curve_serverkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_serverkey(key)?;
+        Ok(self)
+    }
+
+    /// Set the ZAP domain for authentication.
+    pub fn set_zap_domain(&mut self, domain: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_zap_domain(domain)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN server flag on the socket, so that it authenticates its peers with the
+    /// username and password they present, through the ZAP handler of the context.
+    pub fn set_plain_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN username presented by the socket, making it a PLAIN client.
+    pub fn set_plain_username(&mut self, username: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_username(Some(username))?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN password presented by the socket, making it a PLAIN client.
+    pub fn set_plain_password(&mut self, password: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_password(Some(password))?;
+        Ok(self)
+    }
+
+    /// Set the routing id this socket presents to its peers, which a ROUTER peer sees as the
+    /// first frame of the messages from this socket.
+    ///
+    /// The routing id is exchanged when a connection is made, so it only applies to the
+    /// connections made afterwards. This fails with `EINVAL` on a socket its builder connected,
+    /// where it would have no effect: set the routing id before connecting with
+    /// [`SocketBuilder::with_identity`] instead.
+    ///
+    /// [`SocketBuilder::with_identity`]: ../struct.SocketBuilder.html#method.with_identity
+    pub fn set_identity(&mut self, id: &[u8]) -> Result<&mut Self, zmq::Error> {
+        set_identity(&self.0.socket, id)?;
+        Ok(self)
+    }
+
+    /// Get the routing id this socket presents to its peers, empty unless one was set.
+    pub fn get_identity(&self) -> Result<Vec<u8>, zmq::Error> {
+        self.as_raw_socket().get_identity()
+    }
+
+    /// Add a metadata property, which ØMQ sends to the peers of the socket when connecting.
+    ///
+    /// ØMQ requires the `key` to start with `X-`. Peers read the property from the messages
+    /// of the connection with `Message::gets`. The metadata is exchanged when a connection is
+    /// made, so this fails with `EINVAL` on a socket its builder connected: add the property
+    /// before connecting with [`SocketBuilder::with_metadata`] instead. Like it, this needs a
+    /// libzmq built with the draft API.
+    ///
+    /// [`SocketBuilder::with_metadata`]: ../struct.SocketBuilder.html#method.with_metadata
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<&mut Self, zmq::Error> {
+        set_metadata(&self.0.socket, key, value)?;
+        Ok(self)
+    }
+
+    /// Set the send high water mark for the socket.
+    /// The high water mark is a hard limit on the maximum number of outstanding messages
+    /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards.
+    pub fn set_send_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_sndhwm(value)?;
+        Ok(self)
+    }
+
+    /// Get the send high water mark for the socket.
+    pub fn get_send_hwm(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_sndhwm()
+    }
+
+    /// Set the receive high water mark for the socket.
+    /// The high water mark is a hard limit on the maximum number of outstanding messages
+    /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards.
+    pub fn set_receive_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_rcvhwm(value)?;
+        Ok(self)
+    }
+
+    /// Get the receive high water mark for the socket.
+    pub fn get_receive_hwm(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_rcvhwm()
+    }
+
+    /// Set the initial reconnection interval in milliseconds, `100` by default, or `-1` to
+    /// disable reconnecting.
+    /// After a peer goes away, ØMQ waits this long before trying to connect to it again. The
+    /// wait doubles after each failed attempt, up to the
+    /// [maximum interval](#method.set_reconnect_interval_max) if one is set.
+    pub fn set_reconnect_interval(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_reconnect_ivl(value)?;
+        Ok(self)
+    }
+
+    /// Get the initial reconnection interval in milliseconds.
+    pub fn get_reconnect_interval(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_reconnect_ivl()
+    }
+
+    /// Set the maximum reconnection interval in milliseconds, the limit of the exponential
+    /// backoff between attempts. The default `0` keeps the initial interval for every attempt.
+    pub fn set_reconnect_interval_max(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_reconnect_ivl_max(value)?;
+        Ok(self)
+    }
+
+    /// Get the maximum reconnection interval in milliseconds.
+    pub fn get_reconnect_interval_max(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_reconnect_ivl_max()
+    }
+
+    /// Number of messages dropped so far because the send queue was full.
+    ///
+    /// This stays at zero unless the socket was built with a dropping [`SendOverflow`] policy.
+    ///
+    /// [`SendOverflow`]: ../enum.SendOverflow.html
+    pub fn dropped_messages(&self) -> u64 {
+        self.0.queue.dropped()
+    }
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Dealer<I, T> {
@@ -85,9 +328,17 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for Dealer<I, T> {
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Dealer<I, T> {
     fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> FromParts for Dealer<I, T> {
+    const SEND_QUEUE: bool = true;
+
+    fn from_parts(parts: SocketParts) -> Self {
         Self(Broker {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
+            socket: parts.socket,
+            queue: SendQueue::new(parts.send_queue),
         })
     }
 }
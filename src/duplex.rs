@@ -0,0 +1,124 @@
+//! Common interface of the sockets both sending and receiving messages
+
+use std::future::Future;
+
+use futures::{SinkExt, Stream, StreamExt};
+use zmq::Message;
+
+use crate::{
+    socket::{Multipart, MultipartIter},
+    Dealer, Pair, RecvError, Reply, Request, RequestReplyError, Router, SendError,
+};
+
+/// Socket both sending and receiving multipart messages.
+///
+/// It is implemented by [`Request`], [`Reply`], [`Dealer`], [`Router`] and [`Pair`], so that
+/// protocol code can be written once over `T: Duplex`. Each socket keeps its own rules: a
+/// request socket still has to alternate between `send` and `recv`, and a router still expects
+/// the routing id of the peer as the first frame of the messages it sends.
+///
+/// Dealer, router and pair sockets also get a `send` method from `SinkExt`. When both traits are
+/// in scope, call the one of this trait as `Duplex::send(&mut socket, msg)`.
+///
+/// ```no_run
+/// use async_zmq::Duplex;
+///
+/// async fn echo<S: Duplex>(socket: &mut S) -> async_zmq::Result<()>
+/// where
+///     async_zmq::Error: From<S::SendError> + From<S::RecvError>,
+/// {
+///     let msg = socket.recv().await?;
+///     Ok(socket.send(msg).await?)
+/// }
+/// ```
+///
+/// [`Request`]: struct.Request.html
+/// [`Reply`]: struct.Reply.html
+/// [`Dealer`]: struct.Dealer.html
+/// [`Router`]: struct.Router.html
+/// [`Pair`]: struct.Pair.html
+pub trait Duplex {
+    /// Error returned by [`send`](#tymethod.send).
+    type SendError;
+    /// Error returned by [`recv`](#tymethod.recv).
+    type RecvError;
+
+    /// Send a multipart message.
+    fn send(&mut self, msg: Multipart) -> impl Future<Output = Result<(), Self::SendError>>;
+
+    /// Receive a multipart message.
+    fn recv(&mut self) -> impl Future<Output = Result<Multipart, Self::RecvError>>;
+}
+
+impl<I, T> Duplex for Request<I, T>
+where
+    I: Iterator<Item = T> + Unpin,
+    T: Into<Message>,
+    Multipart: Into<MultipartIter<I, T>>,
+{
+    type SendError = RequestReplyError;
+    type RecvError = RequestReplyError;
+
+    async fn send(&mut self, msg: Multipart) -> Result<(), Self::SendError> {
+        Request::send(self, msg).await
+    }
+
+    async fn recv(&mut self) -> Result<Multipart, Self::RecvError> {
+        Request::recv(self).await
+    }
+}
+
+impl<I, T> Duplex for Reply<I, T>
+where
+    I: Iterator<Item = T> + Unpin,
+    T: Into<Message>,
+    Multipart: Into<MultipartIter<I, T>>,
+{
+    type SendError = RequestReplyError;
+    type RecvError = RequestReplyError;
+
+    async fn send(&mut self, msg: Multipart) -> Result<(), Self::SendError> {
+        Reply::send(self, msg).await
+    }
+
+    async fn recv(&mut self) -> Result<Multipart, Self::RecvError> {
+        Reply::recv(self).await
+    }
+}
+
+/// Implement `Duplex` on top of the `Sink` and `Stream` implementations of a socket.
+macro_rules! impl_duplex {
+    ($socket:ident) => {
+        impl<I, T> Duplex for $socket<I, T>
+        where
+            I: Iterator<Item = T> + Unpin,
+            T: Into<Message>,
+            Multipart: Into<MultipartIter<I, T>>,
+        {
+            type SendError = SendError;
+            type RecvError = RecvError;
+
+            async fn send(&mut self, msg: Multipart) -> Result<(), Self::SendError> {
+                SinkExt::send(self, msg.into()).await
+            }
+
+            async fn recv(&mut self) -> Result<Multipart, Self::RecvError> {
+                next(self).await
+            }
+        }
+    };
+}
+
+impl_duplex!(Dealer);
+impl_duplex!(Router);
+impl_duplex!(Pair);
+
+/// Receive the next message of a socket stream, which never ends.
+async fn next<S>(socket: &mut S) -> Result<Multipart, RecvError>
+where
+    S: Stream<Item = Result<Multipart, RecvError>> + Unpin,
+{
+    StreamExt::next(socket)
+        .await
+        .expect("socket streams never end")
+}
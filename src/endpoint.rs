@@ -0,0 +1,184 @@
+//! Endpoint parsing and validation
+//!
+//! Socket constructors such as [`publish`] accept anything convertible into an [`Endpoint`],
+//! including plain strings. The transport and the shape of the address are checked right away,
+//! so a typo like `tpc://` is reported when creating the builder instead of when binding.
+//!
+//! # Example
+//!
+//! ```
+//! use async_zmq::endpoint::{Endpoint, Transport};
+//!
+//! let endpoint = Endpoint::parse("tcp://127.0.0.1:5555").unwrap();
+//! assert_eq!(endpoint.transport(), Transport::Tcp);
+//! assert_eq!(endpoint.address(), "127.0.0.1:5555");
+//!
+//! assert!(Endpoint::parse("tpc://127.0.0.1:5555").is_err());
+//! ```
+//!
+//! [`publish`]: ../fn.publish.html
+//! [`Endpoint`]: struct.Endpoint.html
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::EndpointError;
+
+/// Transport protocols supported by ØMQ.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transport {
+    /// Unicast transport using TCP, `tcp://host:port`.
+    Tcp,
+    /// Local inter-process communication, `ipc://path`.
+    Ipc,
+    /// Local in-process communication between threads sharing a context, `inproc://name`.
+    Inproc,
+    /// Reliable multicast using PGM, `pgm://interface;address:port`.
+    Pgm,
+    /// PGM encapsulated in UDP datagrams, `epgm://interface;address:port`.
+    Epgm,
+    /// Transparent Inter Process Communication, `tipc://address`.
+    Tipc,
+    /// Virtual Machine Communications Interface, `vmci://cid:port`.
+    Vmci,
+}
+
+impl Transport {
+    /// The scheme of the transport, as written before `://`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transport::Tcp => "tcp",
+            Transport::Ipc => "ipc",
+            Transport::Inproc => "inproc",
+            Transport::Pgm => "pgm",
+            Transport::Epgm => "epgm",
+            Transport::Tipc => "tipc",
+            Transport::Vmci => "vmci",
+        }
+    }
+
+    fn from_scheme(scheme: &str) -> Option<Self> {
+        Some(match scheme {
+            "tcp" => Transport::Tcp,
+            "ipc" => Transport::Ipc,
+            "inproc" => Transport::Inproc,
+            "pgm" => Transport::Pgm,
+            "epgm" => Transport::Epgm,
+            "tipc" => Transport::Tipc,
+            "vmci" => Transport::Vmci,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A validated `transport://address` endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    transport: Transport,
+    endpoint: String,
+}
+
+impl Endpoint {
+    /// Parse `endpoint`, checking its transport and the basic shape of its address.
+    ///
+    /// TCP addresses need a `host:port` part, with `*` accepted for both, and PGM addresses an
+    /// `interface;address:port` part. For the other transports the address only has to be
+    /// non-empty. Host names are not resolved.
+    pub fn parse(endpoint: &str) -> Result<Self, EndpointError> {
+        let (scheme, address) = endpoint
+            .split_once("://")
+            .ok_or_else(|| EndpointError::MissingTransport(endpoint.to_owned()))?;
+        let transport = Transport::from_scheme(scheme)
+            .ok_or_else(|| EndpointError::UnknownTransport(scheme.to_owned()))?;
+
+        let valid = match transport {
+            Transport::Tcp => has_port(address),
+            Transport::Pgm | Transport::Epgm => address
+                .split_once(';')
+                .is_some_and(|(interface, group)| !interface.is_empty() && has_port(group)),
+            _ => !address.is_empty(),
+        };
+        if !valid {
+            return Err(EndpointError::InvalidAddress(endpoint.to_owned()));
+        }
+
+        Ok(Self {
+            transport,
+            endpoint: endpoint.to_owned(),
+        })
+    }
+
+    /// The transport of the endpoint.
+    pub fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    /// The address of the endpoint, following `transport://`.
+    pub fn address(&self) -> &str {
+        &self.endpoint[self.transport.as_str().len() + 3..]
+    }
+
+    /// The whole endpoint, as handed to ØMQ.
+    pub fn as_str(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+/// Check for a non-empty host followed by `:` and a port number or `*`.
+fn has_port(address: &str) -> bool {
+    match address.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && (port == "*" || port.parse::<u16>().is_ok()),
+        None => false,
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.endpoint)
+    }
+}
+
+impl AsRef<str> for Endpoint {
+    fn as_ref(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = EndpointError;
+
+    fn from_str(endpoint: &str) -> Result<Self, Self::Err> {
+        Self::parse(endpoint)
+    }
+}
+
+impl TryFrom<&str> for Endpoint {
+    type Error = EndpointError;
+
+    fn try_from(endpoint: &str) -> Result<Self, Self::Error> {
+        Self::parse(endpoint)
+    }
+}
+
+impl TryFrom<&String> for Endpoint {
+    type Error = EndpointError;
+
+    fn try_from(endpoint: &String) -> Result<Self, Self::Error> {
+        Self::parse(endpoint)
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = EndpointError;
+
+    fn try_from(endpoint: String) -> Result<Self, Self::Error> {
+        Self::parse(&endpoint)
+    }
+}
@@ -18,10 +18,12 @@
 //! this crate.
 //!
 
+use std::convert::Infallible;
+
 use thiserror::Error;
 
 /// The type of errors that can occur when creating a new ØMQ socket.
-#[derive(Clone, Copy, Debug, Error)]
+#[derive(Clone, Debug, Error)]
 pub enum SocketError {
     /// The requested socket type is invalid.
     /// Corresponds to ØMQ error code `EINVAL`.
@@ -43,6 +45,16 @@ pub enum SocketError {
     #[error("the context specified was terminated")]
     ContextTerminated,
 
+    /// Another socket of the same context has already bound this inproc name.
+    /// Corresponds to ØMQ error code `EADDRINUSE`.
+    #[error("the inproc endpoint `{0}` is already bound in this context")]
+    InprocNameTaken(String),
+
+    /// The endpoint is malformed, see [`EndpointError`](enum.EndpointError.html).
+    /// Corresponds to ØMQ error code `EINVAL`.
+    #[error(transparent)]
+    InvalidEndpoint(EndpointError),
+
     /// ØMQ produced an error variant that is not documented to occur when
     /// creating a new socket. This should never happen and should be treated
     /// as a bug.
@@ -51,12 +63,14 @@ pub enum SocketError {
 }
 
 impl SocketError {
-    fn to_zmq_error(self) -> zmq::Error {
+    fn into_zmq_error(self) -> zmq::Error {
         match self {
             SocketError::InvalidSocketType => zmq::Error::EINVAL,
             SocketError::InvalidContext => zmq::Error::EFAULT,
             SocketError::SocketLimitReached => zmq::Error::EMFILE,
             SocketError::ContextTerminated => zmq::Error::ETERM,
+            SocketError::InprocNameTaken(_) => zmq::Error::EADDRINUSE,
+            SocketError::InvalidEndpoint(_) => zmq::Error::EINVAL,
             SocketError::Unexpected(error) => error,
         }
     }
@@ -64,7 +78,7 @@ impl SocketError {
 
 impl From<SocketError> for zmq::Error {
     fn from(other: SocketError) -> Self {
-        other.to_zmq_error()
+        other.into_zmq_error()
     }
 }
 
@@ -80,20 +94,54 @@ impl From<zmq::Error> for SocketError {
     }
 }
 
+impl From<EndpointError> for SocketError {
+    fn from(other: EndpointError) -> Self {
+        SocketError::InvalidEndpoint(other)
+    }
+}
+
+impl From<Infallible> for SocketError {
+    fn from(other: Infallible) -> Self {
+        match other {}
+    }
+}
+
+/// The type of errors that can occur when parsing an endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum EndpointError {
+    /// The endpoint doesn't start with `transport://`.
+    #[error("the endpoint `{0}` has no `transport://` prefix")]
+    MissingTransport(String),
+
+    /// The transport isn't one supported by ØMQ.
+    #[error("the transport `{0}` is not supported")]
+    UnknownTransport(String),
+
+    /// The address doesn't have the shape expected by its transport.
+    #[error("the endpoint `{0}` has an invalid address")]
+    InvalidAddress(String),
+}
+
+impl From<EndpointError> for zmq::Error {
+    fn from(_: EndpointError) -> Self {
+        zmq::Error::EINVAL
+    }
+}
+
 /// The type of errors that can occur when sending a ØMQ message.
 ///
 /// The following ØMQ error codes may occur in the underlying ØMQ implementation,
 /// but do not need to be handled by users of this crate:
 ///
 ///  * `EAGAIN` - this crate will automatically retry if this error code is
-///     produced
+///    produced
 ///  * `ENOTSUP` - unsupported operations are prevented by the design of this
-///     crate
+///    crate
 ///  * `EINVAL` - multipart messages are not yet supported
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 ///  * `EFSM` - this applies only to REP/REQ sockets which have their own error
-///     type
+///    type
 #[derive(Clone, Copy, Debug, Error)]
 pub enum SendError {
     /// The ØMQ context associated with the specified socket was terminated.
@@ -134,6 +182,16 @@ pub enum SendError {
 }
 
 impl SendError {
+    /// Check whether the error means the socket can no longer be used, because its context
+    /// was terminated or the socket was closed. Other errors are transient, so a receiving or
+    /// sending loop may keep going after them.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            SendError::ContextTerminated | SendError::Unexpected(zmq::Error::ENOTSOCK)
+        )
+    }
+
     fn to_zmq_error(self) -> zmq::Error {
         match self {
             SendError::ContextTerminated => zmq::Error::ETERM,
@@ -169,14 +227,14 @@ impl From<zmq::Error> for SendError {
 /// but do not need to be handled by users of this crate:
 ///
 ///  * `EAGAIN` - this crate will automatically retry if this error code is
-///     produced
+///    produced
 ///  * `ENOTSUP` - unsupported operations are prevented by the design of this
-///     crate
+///    crate
 ///  * `EINVAL` - multipart messages are not yet supported
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 ///  * `EFSM` - this applies only to REP/REQ sockets which have their own error
-///     type
+///    type
 #[derive(Clone, Copy, Debug, Error)]
 pub enum RecvError {
     /// The ØMQ context associated with the specified socket was terminated.
@@ -207,6 +265,16 @@ pub enum RecvError {
 }
 
 impl RecvError {
+    /// Check whether the error means the socket can no longer be used, because its context
+    /// was terminated or the socket was closed. Other errors are transient, so a receiving or
+    /// sending loop may keep going after them.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            RecvError::ContextTerminated | RecvError::Unexpected(zmq::Error::ENOTSOCK)
+        )
+    }
+
     fn to_zmq_error(self) -> zmq::Error {
         match self {
             RecvError::ContextTerminated => zmq::Error::ETERM,
@@ -237,13 +305,11 @@ impl From<zmq::Error> for RecvError {
 /// The following ØMQ error codes may occur in the underlying ØMQ implementation,
 /// but do not need to be handled by users of this crate:
 ///
-///  * `EAGAIN` - this crate will automatically retry if this error code is
-///     produced
 ///  * `ENOTSUP` - unsupported operations are prevented by the design of this
-///     crate
+///    crate
 ///  * `EINVAL` - multipart messages are not yet supported
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 #[derive(Clone, Copy, Debug, Error)]
 pub enum RequestReplyError {
     /// The socket was in the incorrect state for the operation.
@@ -255,6 +321,20 @@ pub enum RequestReplyError {
     #[error("this socket cannot send when it is awaiting a reply")]
     AwaitingReply,
 
+    /// A reply was sent before any request was received, so there is no peer
+    /// to route it back to.
+    ///
+    /// Corresponds to ØMQ error code `EFSM`.
+    #[error("no request has been received to reply to")]
+    NoPeerCaptured,
+
+    /// Another operation is already in flight on the socket, for example a `recv` awaited
+    /// concurrently with a send. ØMQ sockets handle one operation at a time.
+    ///
+    /// Corresponds to ØMQ error code `EBUSY`.
+    #[error("another operation is in flight on this socket")]
+    ConcurrentAccess,
+
     /// The ØMQ context associated with the specified socket was terminated.
     ///
     /// Note that this error cannot occur unless you access the raw socket
@@ -270,6 +350,23 @@ pub enum RequestReplyError {
     #[error("the message cannot be routed")]
     HostUnreachable,
 
+    /// The send or receive timeout set on the socket elapsed. Without a
+    /// timeout the operation waits, and `EAGAIN` is retried automatically.
+    ///
+    /// Corresponds to ØMQ error code `EAGAIN`.
+    #[error("the operation timed out")]
+    TimedOut,
+
+    /// A peer disconnected while the reply was awaited, so the request was lost with the
+    /// connection. Only reported once detection is enabled with
+    /// [`Request::detect_disconnects`], on a best-effort basis.
+    ///
+    /// Corresponds to ØMQ error code `ENOTCONN`.
+    ///
+    /// [`Request::detect_disconnects`]: request/struct.Request.html#method.detect_disconnects
+    #[error("the peer disconnected before replying")]
+    PeerDisconnected,
+
     /// The operation was interrupted by delivery of a signal before the
     /// message was sent.
     ///
@@ -290,8 +387,12 @@ impl RequestReplyError {
     fn to_zmq_error(self) -> zmq::Error {
         match self {
             RequestReplyError::AwaitingReply => zmq::Error::EFSM,
+            RequestReplyError::NoPeerCaptured => zmq::Error::EFSM,
+            RequestReplyError::ConcurrentAccess => zmq::Error::EBUSY,
             RequestReplyError::ContextTerminated => zmq::Error::ETERM,
             RequestReplyError::HostUnreachable => zmq::Error::EHOSTUNREACH,
+            RequestReplyError::TimedOut => zmq::Error::EAGAIN,
+            RequestReplyError::PeerDisconnected => zmq::Error::ENOTCONN,
             RequestReplyError::Interrupted => zmq::Error::EINTR,
             RequestReplyError::Unexpected(error) => error,
         }
@@ -310,6 +411,7 @@ impl From<zmq::Error> for RequestReplyError {
             zmq::Error::EFSM => RequestReplyError::AwaitingReply,
             zmq::Error::ETERM => RequestReplyError::ContextTerminated,
             zmq::Error::EHOSTUNREACH => RequestReplyError::HostUnreachable,
+            zmq::Error::EAGAIN => RequestReplyError::TimedOut,
             zmq::Error::EINTR => RequestReplyError::Interrupted,
             error => RequestReplyError::Unexpected(error),
         }
@@ -324,7 +426,7 @@ impl From<zmq::Error> for RequestReplyError {
 ///
 ///  * `EINVAL` - the option name is always correct
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 #[derive(Clone, Copy, Debug, Error)]
 pub enum SubscribeError {
     /// The ØMQ context associated with the specified socket was terminated.
@@ -377,3 +479,66 @@ impl From<zmq::Error> for SubscribeError {
         }
     }
 }
+
+/// The type of errors that can occur when making a call with an `RpcClient`.
+#[derive(Clone, Copy, Debug, Error)]
+pub enum RpcError {
+    /// No reply was received before the timeout of the call elapsed.
+    ///
+    /// Corresponds to ØMQ error code `EAGAIN`.
+    #[error("no reply was received before the timeout elapsed")]
+    Timeout,
+
+    /// The request could not be sent.
+    #[error(transparent)]
+    Send(SendError),
+
+    /// Replies could not be received.
+    #[error(transparent)]
+    Recv(RecvError),
+}
+
+impl From<RpcError> for zmq::Error {
+    fn from(other: RpcError) -> Self {
+        match other {
+            RpcError::Timeout => zmq::Error::EAGAIN,
+            RpcError::Send(error) => error.into(),
+            RpcError::Recv(error) => error.into(),
+        }
+    }
+}
+
+/// The type of errors yielded by the stream of [`Subscribe::reassemble`].
+///
+/// [`Subscribe::reassemble`]: subscribe/struct.Subscribe.html#method.reassemble
+#[derive(Clone, Copy, Debug, Error)]
+pub enum ReassembleError {
+    /// More than `limit` parts were received without the final one. They were discarded, and
+    /// reassembly starts over with the next part.
+    ///
+    /// Corresponds to ØMQ error code `EMSGSIZE`.
+    #[error("no final part was received within {limit} parts")]
+    Overflow {
+        /// The maximum number of parts of a logical message.
+        limit: usize,
+    },
+
+    /// Parts could not be received.
+    #[error(transparent)]
+    Recv(RecvError),
+}
+
+impl From<RecvError> for ReassembleError {
+    fn from(other: RecvError) -> Self {
+        ReassembleError::Recv(other)
+    }
+}
+
+impl From<ReassembleError> for zmq::Error {
+    fn from(other: ReassembleError) -> Self {
+        match other {
+            ReassembleError::Overflow { .. } => zmq::Error::EMSGSIZE,
+            ReassembleError::Recv(error) => error.into(),
+        }
+    }
+}
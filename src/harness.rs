@@ -0,0 +1,64 @@
+//! Helpers for tests: connected socket pairs, and a check of the reactor.
+//!
+//! Only available with the `test-util` feature.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use zmq::Message;
+
+use crate::{
+    context::Context, publish, reply, request, subscribe, Publish, Reply, Request, SocketError,
+    Subscribe,
+};
+
+static PAIR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Unique inproc endpoint, so pairs created concurrently never collide.
+fn inproc_endpoint(kind: &str) -> String {
+    format!(
+        "inproc://async-zmq-{}-{}",
+        kind,
+        PAIR_ID.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Create a REQ socket connected to a REP socket over a unique inproc endpoint.
+///
+/// Both sockets are created from `context`, which must outlive them.
+///
+/// Only available with the `test-util` feature.
+#[allow(clippy::type_complexity)]
+pub fn req_rep_pair<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &Context,
+) -> Result<(Request<I, T>, Reply<I, T>), SocketError> {
+    let endpoint = inproc_endpoint("req-rep");
+    let reply = reply(&endpoint)?.with_context(context).bind()?;
+    let request = request(&endpoint)?.with_context(context).connect()?;
+    Ok((request, reply))
+}
+
+/// Create a PUB socket connected to a SUB socket over a unique inproc endpoint.
+///
+/// The SUB socket is subscribed to every topic. Subscriptions still propagate asynchronously,
+/// so messages published right after this returns may be missed.
+///
+/// Only available with the `test-util` feature.
+pub fn pub_sub_pair<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &Context,
+) -> Result<(Publish<I, T>, Subscribe), SocketError> {
+    let endpoint = inproc_endpoint("pub-sub");
+    let publish = publish(&endpoint)?.with_context(context).bind()?;
+    let subscribe = subscribe(&endpoint)?.with_context(context).connect()?;
+    subscribe.as_raw_socket().set_subscribe(b"")?;
+    Ok((publish, subscribe))
+}
+
+/// Check whether `socket` is registered with the reactor waking up its tasks.
+///
+/// Every socket built by this crate is registered as soon as it is bound or connected, and stays
+/// registered until it is dropped. A raw `zmq::Socket` that was never wrapped is not registered.
+///
+/// Only available with the `test-util` feature.
+pub fn reactor_registered(socket: &zmq::Socket) -> bool {
+    crate::reactor::is_registered(socket)
+}
@@ -22,15 +22,30 @@
 //! ```
 //! 
 //! Since the use case of this crate is mostly for sending/recieving multipart message. So it provides [`Multipart`]
-//! which is a type alias for `Vec<Message>` when recieving message on type implemented with `Stream`, and [`MultipartIter`]
+//! which is a thin wrapper around `Vec<Message>` when recieving message on type implemented with `Stream`, and [`MultipartIter`]
 //! which is a generic struct make any queue can turn into iterator and then send via type  implemented with `Sink`.
 //!
 //! To learn more about each socket type usage. See [modules](#modules) below.
 //!
+//! ## Runtimes
+//!
+//! There is no runtime to pick with a feature flag. Every socket, whatever its context, is
+//! registered with a single process-wide reactor, which polls the ØMQ file descriptors from its
+//! own thread and wakes the waiting tasks through their `Waker`. Timeouts run on the timer
+//! thread of `futures-timer` the same way. Sockets therefore work on async-std, tokio or any
+//! other executor, and a socket created on one runtime can be polled from another.
+//!
+//! ## Cancellation
+//!
+//! ØMQ delivers multipart messages atomically, and the sockets read every frame of a message in
+//! the same poll, after the first frame is available. A receive future dropped before completion,
+//! for example by losing a `select!` race, has therefore consumed either nothing or a whole
+//! message it already returned, and the next receive starts at a message boundary.
+//!
 //! [`Result`]: type.Result.html
 //! [`zmq`]: https://crates.io/crates/zmq
 //! [`async-std`]: https://crates.io/crates/async-std
-//! [`Multipart`]: type.Multipart.html
+//! [`Multipart`]: struct.Multipart.html
 //! [`MultipartIter`]: struct.MultipartIter.html
 
 
@@ -38,7 +53,9 @@
 #![warn(missing_docs, rust_2018_idioms, unreachable_pub)]
 
 pub mod dealer;
+pub mod endpoint;
 pub mod errors;
+pub mod monitor;
 pub mod pair;
 pub mod publish;
 pub mod pull;
@@ -46,31 +63,52 @@ pub mod push;
 pub mod reply;
 pub mod request;
 pub mod router;
+pub mod rpc;
 pub mod stream;
 pub mod subscribe;
 pub mod xpublish;
+pub mod worker_pool;
 pub mod xsubscribe;
 pub mod curve;
+pub mod context;
 
+#[cfg(feature = "compress")]
+mod compress;
+mod duplex;
+#[cfg(feature = "test-util")]
+mod harness;
+mod proxy;
 mod reactor;
 mod socket;
+mod sys;
+mod ttl;
 
-pub use crate::dealer::{dealer, Dealer};
+pub use crate::dealer::{dealer, dealer_in, Dealer};
+pub use crate::duplex::Duplex;
+pub use crate::endpoint::{Endpoint, Transport};
 pub use crate::errors::*;
-pub use crate::pair::{pair, Pair};
-pub use crate::publish::{publish, Publish};
-pub use crate::pull::{pull, Pull};
-pub use crate::push::{push, Push};
+#[cfg(feature = "test-util")]
+pub use crate::harness::{pub_sub_pair, reactor_registered, req_rep_pair};
+pub use crate::pair::{pair, pair_in, Pair};
+pub use crate::publish::{publish, publish_in, Publish};
+pub use crate::pull::{pull, pull_in, Pull};
+pub use crate::proxy::{proxy, proxy_with_capture, ProxySocket};
+pub use crate::push::{push, push_in, Push};
 pub use crate::reactor::AsRawSocket;
-pub use crate::reply::{reply, Reply};
-pub use crate::request::{request, Request};
-pub use crate::router::{router, Router};
-pub use crate::socket::{Multipart, MultipartIter, SocketBuilder};
-pub use crate::stream::{stream, ZmqStream};
-pub use crate::subscribe::{subscribe, Subscribe};
-pub use crate::xpublish::{xpublish, XPublish};
-pub use crate::xsubscribe::{xsubscribe, XSubscribe};
+pub use crate::reply::{reply, reply_in, Reply};
+pub use crate::request::{request, request_in, Request};
+pub use crate::router::{router, router_in, Router};
+pub use crate::rpc::{rpc_client, rpc_client_in, RpcClient};
+pub use crate::socket::{
+    msg, Multipart, MultipartBuilder, MultipartIter, SendOverflow, SocketBuilder,
+};
+pub use crate::stream::{stream, stream_in, ZmqStream};
+pub use crate::subscribe::{subscribe, subscribe_in, Subscribe};
+pub use crate::xpublish::{xpublish, xpublish_in, XPublish};
+pub use crate::worker_pool::WorkerPool;
+pub use crate::xsubscribe::{xsubscribe, xsubscribe_in, XSubscribe};
 pub use crate::curve::CurveKeyPair;
+pub use crate::context::{Context, SocketOptions};
 pub use futures::sink::{Sink, SinkExt};
 pub use futures::stream::{Stream, StreamExt};
-pub use zmq::{self, Context, Error, Message, Result};
+pub use zmq::{self, Error, Message, Result};
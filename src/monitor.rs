@@ -0,0 +1,356 @@
+//! Socket events as an async stream
+//!
+//! ØMQ reports what happens to the connections of a socket, such as peers connecting,
+//! disconnecting or failing their handshake, as monitor events. Call [`AsRawSocket::monitor`]
+//! on any socket of this crate to receive them as a [`MonitorStream`], for example to drive
+//! reconnection logic. The stream ends once the monitored socket is closed.
+//!
+//! ØMQ allows a single monitor per socket. The sockets of this crate start theirs once and share
+//! it between every stream and every method relying on events, such as
+//! `Subscribe::with_drop_notifications` or `Publish::connected_subscriber_estimate`, so any
+//! number of them can be used together. Only calling `monitor` on the `zmq::Socket` returned by
+//! `as_raw_socket` replaces that shared monitor, ending the streams.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{AsRawSocket, Result, StreamExt};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let zmq = async_zmq::pull("tcp://127.0.0.1:5555")?.connect()?;
+//!     let mut events = zmq.monitor(async_zmq::zmq::SocketEvent::ALL)?;
+//!
+//!     while let Some(event) = events.next().await {
+//!         let event = event?;
+//!         println!("{:?} on {}", event.event, event.address);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`AsRawSocket::monitor`]: ../trait.AsRawSocket.html#method.monitor
+//! [`MonitorStream`]: struct.MonitorStream.html
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use once_cell::sync::OnceCell;
+use zmq::SocketEvent;
+
+use crate::{
+    context::Context as ZmqContext, reactor::AsRawSocket, socket::monitor_socket, Dealer,
+    Multipart, Pair, Publish, Pull, Push, RecvError, Reply, Request, Router, RpcClient,
+    SocketError, Stream, Subscribe, XPublish, XSubscribe, ZmqStream,
+};
+
+/// Events ØMQ can report, except `ALL`, which is a mask rather than an event.
+const EVENTS: [SocketEvent; 15] = [
+    SocketEvent::CONNECTED,
+    SocketEvent::CONNECT_DELAYED,
+    SocketEvent::CONNECT_RETRIED,
+    SocketEvent::LISTENING,
+    SocketEvent::BIND_FAILED,
+    SocketEvent::ACCEPTED,
+    SocketEvent::ACCEPT_FAILED,
+    SocketEvent::CLOSED,
+    SocketEvent::CLOSE_FAILED,
+    SocketEvent::DISCONNECTED,
+    SocketEvent::MONITOR_STOPPED,
+    SocketEvent::HANDSHAKE_FAILED_NO_DETAIL,
+    SocketEvent::HANDSHAKE_SUCCEEDED,
+    SocketEvent::HANDSHAKE_FAILED_PROTOCOL,
+    SocketEvent::HANDSHAKE_FAILED_AUTH,
+];
+
+/// An event reported by the monitor of a socket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonitorEvent {
+    /// What happened.
+    pub event: SocketEvent,
+    /// Value attached to the event: the file descriptor of the connection for most events, the
+    /// reconnect interval for `CONNECT_RETRIED`, or the error code for failures.
+    pub value: u32,
+    /// Endpoint of the connection the event concerns.
+    pub address: String,
+}
+
+impl MonitorEvent {
+    /// Decode the frames of an event, or return `None` for malformed frames and unknown events.
+    fn decode(msg: &Multipart) -> Option<Self> {
+        // The first frame holds the event id and the value, the second one the endpoint.
+        let frame = msg.frame_bytes(0)?;
+        let raw = u16::from_ne_bytes(frame.get(..2)?.try_into().ok()?);
+        let value = u32::from_ne_bytes(frame.get(2..6)?.try_into().ok()?);
+        let event = EVENTS.iter().copied().find(|event| event.to_raw() == raw)?;
+        let address = String::from_utf8_lossy(msg.frame_bytes(1).unwrap_or_default()).into_owned();
+        Some(Self {
+            event,
+            value,
+            address,
+        })
+    }
+}
+
+/// Stream of the events of a socket, created by [`AsRawSocket::monitor`].
+///
+/// [`AsRawSocket::monitor`]: ../trait.AsRawSocket.html#method.monitor
+pub struct MonitorStream {
+    events: mpsc::UnboundedReceiver<MonitorEvent>,
+}
+
+impl Stream for MonitorStream {
+    type Item = Result<MonitorEvent, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().events)
+            .poll_next(cx)
+            .map(|event| event.map(Ok))
+    }
+}
+
+type Callback = Box<dyn FnMut(&MonitorEvent) + Send>;
+
+/// The single ØMQ monitor of a socket, shared by the streams of [`AsRawSocket::monitor`] and
+/// the methods of this crate relying on events.
+///
+/// ØMQ allows one monitor per socket, and starting another one silently replaces it. The hub
+/// starts the monitor for every event on first use, and a background thread hands each event
+/// to the listeners interested in it, until the socket is closed.
+///
+/// The events go through an `inproc://` endpoint, which only a socket of the same context can
+/// reach. The zmq crate doesn't tell the context of a socket, so only the hubs of the sockets
+/// built by a `SocketBuilder` know it, and the others fail to start with `EFAULT`.
+///
+/// [`AsRawSocket::monitor`]: ../trait.AsRawSocket.html#method.monitor
+#[derive(Default)]
+pub(crate) struct EventHub {
+    context: Option<ZmqContext>,
+    listeners: OnceCell<Arc<Mutex<Listeners>>>,
+}
+
+#[derive(Default)]
+struct Listeners {
+    callbacks: Vec<(u16, Callback)>,
+    streams: Vec<(u16, mpsc::UnboundedSender<MonitorEvent>)>,
+    /// File descriptors of the connections accepted and not disconnected yet.
+    accepted: HashSet<u32>,
+    /// Name of the connections seen, by file descriptor, see `EventHub::connection_name`.
+    connections: HashMap<u32, String>,
+    /// Whether the monitor has stopped, after which no event is reported anymore.
+    stopped: bool,
+}
+
+impl EventHub {
+    /// Hub of a socket of `context`.
+    pub(crate) fn new(context: ZmqContext) -> Self {
+        Self {
+            context: Some(context),
+            listeners: OnceCell::new(),
+        }
+    }
+
+    /// Call `on_event` from the background thread with each event of `socket` among `events`.
+    pub(crate) fn subscribe<F>(
+        &self,
+        socket: &zmq::Socket,
+        events: &[SocketEvent],
+        on_event: F,
+    ) -> Result<(), zmq::Error>
+    where
+        F: FnMut(&MonitorEvent) + Send + 'static,
+    {
+        let mask = events.iter().fold(0, |mask, event| mask | event.to_raw());
+        let mut listeners = self.listeners(socket)?.lock().unwrap();
+        if !listeners.stopped {
+            listeners.callbacks.push((mask, Box::new(on_event)));
+        }
+        Ok(())
+    }
+
+    /// Receive the `events` of `socket`, or every event with `SocketEvent::ALL`, as a stream
+    /// ending once the socket is closed.
+    pub(crate) fn stream(
+        &self,
+        socket: &zmq::Socket,
+        events: SocketEvent,
+    ) -> Result<MonitorStream, SocketError> {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut listeners = self.listeners(socket)?.lock().unwrap();
+        if !listeners.stopped {
+            listeners.streams.push((events.to_raw(), sender));
+        }
+        Ok(MonitorStream { events: receiver })
+    }
+
+    /// Start monitoring `socket`, if not done yet.
+    pub(crate) fn start(&self, socket: &zmq::Socket) -> Result<(), zmq::Error> {
+        self.listeners(socket).map(drop)
+    }
+
+    /// Number of connections accepted by the socket and not disconnected since its monitor
+    /// started, or `None` if it hasn't.
+    pub(crate) fn accepted_connections(&self) -> Option<usize> {
+        let listeners = self.listeners.get()?;
+        Some(listeners.lock().unwrap().accepted.len())
+    }
+
+    /// Name of the connection with the file descriptor `fd`, or `None` if the monitor didn't
+    /// report it: the endpoint it was made to, or for a connection the socket accepted, the
+    /// endpoint it was bound to followed by `#` and `fd`. The name of a closed connection is kept
+    /// until its descriptor is reused.
+    pub(crate) fn connection_name(&self, fd: u32) -> Option<String> {
+        let listeners = self.listeners.get()?.lock().unwrap();
+        listeners.connections.get(&fd).cloned()
+    }
+
+    /// Listeners of the events of `socket`, starting its monitor on first use.
+    fn listeners(&self, socket: &zmq::Socket) -> Result<&Arc<Mutex<Listeners>>, zmq::Error> {
+        self.listeners.get_or_try_init(|| {
+            let context = self.context.as_ref().ok_or(zmq::Error::EFAULT)?;
+            let monitor = monitor_socket(socket, context, &[SocketEvent::ALL])?;
+            // The PAIR socket ØMQ sends the events from, and the one receiving them.
+            let open = [context.track_socket(), context.track_socket()];
+            let listeners = Arc::new(Mutex::new(Listeners::default()));
+            let shared = listeners.clone();
+            std::thread::Builder::new()
+                .name("async-zmq/monitor".to_string())
+                .spawn(move || {
+                    dispatch(monitor, &shared);
+                    drop(open);
+                })
+                .map_err(|_| zmq::Error::ENOMEM)?;
+            Ok(listeners)
+        })
+    }
+}
+
+/// Hand the events received by `monitor` to `listeners` until the monitor stops.
+fn dispatch(monitor: zmq::Socket, listeners: &Mutex<Listeners>) {
+    while let Ok(frames) = monitor.recv_multipart(0) {
+        let event = match MonitorEvent::decode(&Multipart::of(frames)) {
+            Some(event) => event,
+            None => continue,
+        };
+        if event.event == SocketEvent::MONITOR_STOPPED {
+            break;
+        }
+
+        let raw = event.event.to_raw();
+        let mut listeners = listeners.lock().unwrap();
+        if event.event == SocketEvent::CONNECTED {
+            listeners
+                .connections
+                .insert(event.value, event.address.clone());
+        } else if event.event == SocketEvent::ACCEPTED {
+            listeners.accepted.insert(event.value);
+            let name = format!("{}#{}", event.address, event.value);
+            listeners.connections.insert(event.value, name);
+        } else if event.event == SocketEvent::DISCONNECTED {
+            // Connections the socket made itself aren't counted.
+            listeners.accepted.remove(&event.value);
+        }
+        for (mask, on_event) in &mut listeners.callbacks {
+            if *mask & raw != 0 {
+                on_event(&event);
+            }
+        }
+        // Streams dropped by their reader are forgotten.
+        listeners.streams.retain(|(mask, sender)| {
+            !sender.is_closed()
+                && (*mask & raw == 0 || sender.unbounded_send(event.clone()).is_ok())
+        });
+    }
+
+    // The monitored socket is closed: end the streams and release the callbacks.
+    let mut listeners = listeners.lock().unwrap();
+    listeners.stopped = true;
+    listeners.callbacks.clear();
+    listeners.streams.clear();
+}
+
+/// Implement `AsRawSocket` and `AsRawFd` for the socket wrappers, through their
+/// `as_raw_socket` method, monitoring them through the [`EventHub`] of their `ZmqSocket`,
+/// reached at the given field path, which also reads their raw options.
+macro_rules! impl_as_raw_socket {
+    ($($socket:ident => $($field:tt).+),*; $($generic:ident => $($generic_field:tt).+),*) => {
+        $(
+            impl AsRawSocket for $socket {
+                fn as_socket(&self) -> &zmq::Socket {
+                    self.as_raw_socket()
+                }
+
+                fn monitor(&self, events: SocketEvent) -> Result<MonitorStream, SocketError> {
+                    self.$($field).+.monitor(events)
+                }
+
+                fn get_raw_option(&self, option: i32, len: usize) -> Result<Vec<u8>, zmq::Error> {
+                    self.$($field).+.get_raw_option(option, len)
+                }
+            }
+
+            impl AsRawFd for $socket {
+                fn as_raw_fd(&self) -> RawFd {
+                    raw_fd(self.as_raw_socket())
+                }
+            }
+        )*
+        $(
+            impl<I, T> AsRawSocket for $generic<I, T>
+            where
+                I: Iterator<Item = T> + Unpin,
+                T: Into<zmq::Message>,
+            {
+                fn as_socket(&self) -> &zmq::Socket {
+                    self.as_raw_socket()
+                }
+
+                fn monitor(&self, events: SocketEvent) -> Result<MonitorStream, SocketError> {
+                    self.$($generic_field).+.monitor(events)
+                }
+
+                fn get_raw_option(&self, option: i32, len: usize) -> Result<Vec<u8>, zmq::Error> {
+                    self.$($generic_field).+.get_raw_option(option, len)
+                }
+            }
+
+            impl<I, T> AsRawFd for $generic<I, T>
+            where
+                I: Iterator<Item = T> + Unpin,
+                T: Into<zmq::Message>,
+            {
+                fn as_raw_fd(&self) -> RawFd {
+                    raw_fd(self.as_raw_socket())
+                }
+            }
+        )*
+    };
+}
+
+/// `ZMQ_FD` of `socket`, which can only fail once the socket is closed.
+fn raw_fd(socket: &zmq::Socket) -> RawFd {
+    socket
+        .get_fd()
+        .expect("cannot get the socket file descriptor")
+}
+
+impl_as_raw_socket!(
+    Pull => inner.socket,
+    RpcClient => socket,
+    Subscribe => inner.socket,
+    XSubscribe => 0.socket,
+    ZmqStream => 0.socket;
+    Dealer => 0.socket,
+    Pair => 0.socket,
+    Publish => inner.socket,
+    Push => 0.socket,
+    Reply => inner.socket,
+    Request => inner.socket,
+    Router => inner.socket,
+    XPublish => 0.socket
+);
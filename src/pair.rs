@@ -25,32 +25,106 @@
 //! [`Stream`]: ../trait.Stream.html
 //! [`StreamExt`]: ../trait.StreamExt.html
 
+use std::iter::once;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use futures::future::poll_fn;
 use zmq::{Message, SocketType};
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Broker, Multipart, MultipartIter, SocketBuilder},
+    endpoint::Endpoint,
+    reactor::AsRawSocket,
+    socket::{
+        close_socket, Broker, FromParts, Multipart, MultipartIter, SendQueue, SocketBuilder,
+        SocketParts,
+    },
     RecvError, SendError, Sink, SocketError, Stream,
 };
 
 /// Create a ZMQ socket with PAIR type
-pub fn pair<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Pair<I, T>>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn pair<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Pair<I, T>>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::PAIR, endpoint))
 }
 
+/// Create a ZMQ socket with PAIR type, built with `context`
+///
+/// This is [`pair`](fn.pair.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn pair_in<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Pair<I, T>>, SocketError> {
+    Ok(pair(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with PAIR type
-pub struct Pair<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Broker<I, T>);
+pub struct Pair<I: Iterator<Item = T> + Unpin, T: Into<Message>>(pub(crate) Broker<I, T>);
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Pair<I, T> {
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    /// Close the socket gracefully, completing once ØMQ is done with it.
+    ///
+    /// Messages waiting in the send queue are flushed first. ØMQ then keeps delivering the
+    /// messages it holds for the linger period of the socket, see `set_linger` on the raw
+    /// socket, and the returned future completes once they are all delivered or the linger
+    /// period is over, when ØMQ destroys the socket. With the default infinite linger period,
+    /// this waits until a peer receives the messages, so set a finite one to bound the wait.
+    pub async fn close(mut self) -> Result<(), SocketError> {
+        poll_fn(|cx| Pin::new(&mut self.0).poll_flush(cx)).await?;
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Send `msg` as a single-frame message, without building a [`MultipartIter`].
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    ///
+    /// [`MultipartIter`]: ../struct.MultipartIter.html
+    pub async fn send_msg(&mut self, msg: Message) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.0).poll_flush(cx)).await?;
+
+        let mut msg = MultipartIter(once(msg));
+        poll_fn(|cx| self.0.socket.send(cx, &mut msg)).await?;
+        Ok(())
+    }
+
+    /// Receive a single-frame message as a plain `Message`, without allocating a [`Multipart`].
+    ///
+    /// Only the first frame is returned; the frames following it in a multipart message are
+    /// received and discarded, so use the `Stream` for sockets carrying multipart messages.
+    ///
+    /// [`Multipart`]: ../struct.Multipart.html
+    pub async fn recv_msg(&self) -> Result<Message, RecvError> {
+        Ok(poll_fn(|cx| self.0.socket.recv_msg(cx)).await?)
+    }
+
+    /// Number of messages dropped so far because the send queue was full.
+    ///
+    /// This stays at zero unless the socket was built with a dropping [`SendOverflow`] policy.
+    ///
+    /// [`SendOverflow`]: ../enum.SendOverflow.html
+    pub fn dropped_messages(&self) -> u64 {
+        self.0.queue.dropped()
+    }
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Pair<I, T> {
@@ -90,9 +164,17 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for Pair<I, T> {
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Pair<I, T> {
     fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> FromParts for Pair<I, T> {
+    const SEND_QUEUE: bool = true;
+
+    fn from_parts(parts: SocketParts) -> Self {
         Self(Broker {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
+            socket: parts.socket,
+            queue: SendQueue::new(parts.send_queue),
         })
     }
 }
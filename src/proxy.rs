@@ -0,0 +1,144 @@
+//! Forwarding of messages between two sockets over the reactor
+
+use std::pin::Pin;
+use std::task::Poll;
+use std::vec::IntoIter;
+
+use futures::future::poll_fn;
+use futures::{Sink, SinkExt, Stream};
+use zmq::Message;
+
+use crate::{
+    socket::{Multipart, MultipartIter},
+    RecvError, SendError,
+};
+
+/// Forward every message received by `frontend` to `backend` and back, until either socket
+/// fails.
+///
+/// This does what `zmq_proxy` does, but on the reactor instead of a blocking thread, so the
+/// returned future can be spawned as a task of any executor. The sockets are usually already
+/// bound or connected wrappers of the zmq crate, such as a [`Router`] frontend and a [`Dealer`]
+/// backend for a request broker, or an [`XSubscribe`] and an [`XPublish`] for a pub-sub
+/// forwarder. Both sockets are polled in turn, so neither direction starves the other.
+///
+/// Errors from sending are converted into a [`RecvError`] with the same ØMQ error code.
+///
+/// ```no_run
+/// use std::vec::IntoIter;
+///
+/// use async_zmq::{Message, Result};
+///
+/// #[async_std::main]
+/// async fn main() -> Result<()> {
+///     let frontend = async_zmq::router::<IntoIter<Message>, Message>("tcp://*:5559")?.bind()?;
+///     let backend = async_zmq::dealer::<IntoIter<Message>, Message>("tcp://*:5560")?.bind()?;
+///
+///     async_zmq::proxy(frontend, backend).await?;
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Router`]: router/struct.Router.html
+/// [`Dealer`]: dealer/struct.Dealer.html
+/// [`XSubscribe`]: xsubscribe/struct.XSubscribe.html
+/// [`XPublish`]: xpublish/struct.XPublish.html
+/// [`RecvError`]: enum.RecvError.html
+pub async fn proxy<F, B>(frontend: F, backend: B) -> Result<(), RecvError>
+where
+    F: ProxySocket,
+    B: ProxySocket,
+{
+    forward(frontend, backend, None::<&mut B>).await
+}
+
+/// Forward messages between `frontend` and `backend` as [`proxy`] does, sending a copy of every
+/// forwarded message to `capture` first.
+///
+/// The capture socket is typically a [`Push`] or a [`Publish`] feeding a logger or a monitor.
+/// Sending to it applies backpressure to the proxy like the two other sockets.
+///
+/// [`proxy`]: fn.proxy.html
+/// [`Push`]: push/struct.Push.html
+/// [`Publish`]: publish/struct.Publish.html
+pub async fn proxy_with_capture<F, B, C>(
+    frontend: F,
+    backend: B,
+    mut capture: C,
+) -> Result<(), RecvError>
+where
+    F: ProxySocket,
+    B: ProxySocket,
+    C: Sink<MultipartIter<IntoIter<Message>, Message>, Error = SendError> + Unpin,
+{
+    forward(frontend, backend, Some(&mut capture)).await
+}
+
+/// Socket both receiving and sending multipart messages, which [`proxy`] can forward between.
+///
+/// It is implemented by every socket with a `Stream` and a `Sink` of multipart messages built
+/// out of `Message` frames, such as `Router<IntoIter<Message>, Message>`.
+///
+/// [`proxy`]: fn.proxy.html
+pub trait ProxySocket:
+    Stream<Item = Result<Multipart, RecvError>>
+    + Sink<MultipartIter<IntoIter<Message>, Message>, Error = SendError>
+    + Unpin
+{
+}
+
+impl<S> ProxySocket for S where
+    S: Stream<Item = Result<Multipart, RecvError>>
+        + Sink<MultipartIter<IntoIter<Message>, Message>, Error = SendError>
+        + Unpin
+{
+}
+
+async fn forward<F, B, C>(
+    mut frontend: F,
+    mut backend: B,
+    mut capture: Option<&mut C>,
+) -> Result<(), RecvError>
+where
+    F: ProxySocket,
+    B: ProxySocket,
+    C: Sink<MultipartIter<IntoIter<Message>, Message>, Error = SendError> + Unpin,
+{
+    let mut frontend_first = true;
+    loop {
+        let (msg, from_frontend) = poll_fn(|cx| {
+            for from_frontend in [frontend_first, !frontend_first] {
+                let poll = if from_frontend {
+                    Pin::new(&mut frontend).poll_next(cx)
+                } else {
+                    Pin::new(&mut backend).poll_next(cx)
+                };
+                if let Poll::Ready(msg) = poll {
+                    return Poll::Ready((msg, from_frontend));
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+        frontend_first = !from_frontend;
+
+        let msg = match msg {
+            Some(msg) => msg?,
+            None => return Ok(()),
+        };
+        if let Some(capture) = capture.as_mut() {
+            let copy: Multipart = msg.iter().map(|frame| Message::from(&frame[..])).collect();
+            capture.send(copy.into()).await.map_err(recv_error)?;
+        }
+        let sent = if from_frontend {
+            backend.send(msg.into()).await
+        } else {
+            frontend.send(msg.into()).await
+        };
+        sent.map_err(recv_error)?;
+    }
+}
+
+fn recv_error(error: SendError) -> RecvError {
+    zmq::Error::from(error).into()
+}
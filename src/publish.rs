@@ -25,34 +25,286 @@
 //! [`Sink`]: ../trait.Sink.html
 //! [`SinkExt`]: ../trait.SinkExt.html
 
+use std::collections::HashMap;
+use std::iter::{once, Once};
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
+use futures::future::poll_fn;
 use zmq::{Message, SocketType};
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{MultipartIter, Sender, SocketBuilder},
-    SendError, Sink, SocketError,
+    endpoint::Endpoint,
+    reactor::AsRawSocket,
+    socket::{
+        close_socket, set_metadata, FromParts, MultipartIter, SendQueue, Sender, SocketBuilder,
+        SocketParts,
+    },
+    ttl, SendError, Sink, SocketError,
 };
 
 /// Create a ZMQ socket with PUB type
-pub fn publish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Publish<I, T>>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn publish<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Publish<I, T>>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::PUB, endpoint))
 }
 
+/// Create a ZMQ socket with PUB type, built with `context`
+///
+/// This is [`publish`](fn.publish.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn publish_in<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Publish<I, T>>, SocketError> {
+    Ok(publish(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with PUB type
-pub struct Publish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Sender<I, T>);
+pub struct Publish<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
+    pub(crate) inner: Sender<I, T>,
+    /// Frames registered with `with_cached_frame`, copied on every `send_cached`.
+    cached: HashMap<String, Vec<u8>>,
+}
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Publish<I, T> {
+    /// Turn the socket into a [`ByteSink`] sending each `Vec<u8>` item as a single-frame message.
+    ///
+    /// This fits pipelines producing raw byte chunks, such as codecs. The byte sink keeps the send
+    /// queue settings of the socket, but messages queued and not yet flushed are dropped.
+    ///
+    /// [`ByteSink`]: struct.ByteSink.html
+    pub fn into_byte_sink(self) -> ByteSink {
+        ByteSink(Sender {
+            queue: SendQueue::settings_of(&self.inner.queue),
+            socket: self.inner.socket,
+        })
+    }
+
+    /// Send a multipart message made of borrowed frames.
+    ///
+    /// Each frame is copied exactly once, straight into the buffer of its ØMQ message, without
+    /// an intermediate owned value like the `Vec<u8>` needed to go through `Sink`. A true
+    /// zero-copy send isn't possible for borrowed data: ØMQ may still be reading a message after
+    /// the send returns, when the caller is free to drop or modify the buffer. Messages waiting
+    /// in the send queue are flushed first, so ordering is preserved.
+    pub async fn send_borrowed(&mut self, frames: &[&[u8]]) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+
+        let mut msg = MultipartIter(frames.iter().map(|frame| Message::from(*frame)));
+        poll_fn(|cx| self.inner.socket.send(cx, &mut msg)).await?;
+        Ok(())
+    }
+
+    /// Send `body` under `topic`, to be dropped by subscribers once `ttl` has elapsed.
+    ///
+    /// A timestamp frame is inserted between the topic and the body, holding the send time and
+    /// `ttl`; subscribers read it through [`Subscribe::with_ttl_check`]. The frame is 16 bytes:
+    /// the send time in milliseconds since the Unix epoch, then `ttl` in milliseconds, both as
+    /// big-endian `u64`. Expiry compares the clocks of both hosts, so they must be roughly in
+    /// sync, within a small fraction of `ttl`.
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    ///
+    /// [`Subscribe::with_ttl_check`]: ../subscribe/struct.Subscribe.html#method.with_ttl_check
+    pub async fn send_with_ttl<B: Into<Message>>(
+        &mut self,
+        topic: &str,
+        body: impl IntoIterator<Item = B>,
+        ttl: Duration,
+    ) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+
+        let frames = [Message::from(topic), ttl::timestamp_frame(ttl)];
+        let mut msg = MultipartIter(frames.into_iter().chain(body.into_iter().map(Into::into)));
+        poll_fn(|cx| self.inner.socket.send(cx, &mut msg)).await?;
+        Ok(())
+    }
+
+    /// Send `body` under `topic`, with the body compressed.
+    ///
+    /// The body frame is compressed with LZ4 and starts with a marker, so that
+    /// [`Subscribe::decompress`] recognises it; the topic frame is left as is for subscriptions
+    /// to match. Compression trades CPU time on both ends for bandwidth, which pays off for
+    /// large and repetitive bodies over slow links. Subscribers must decompress, they receive
+    /// the compressed frame otherwise.
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    ///
+    /// Only available with the `compress` feature.
+    ///
+    /// [`Subscribe::decompress`]: ../subscribe/struct.Subscribe.html#method.decompress
+    #[cfg(feature = "compress")]
+    pub async fn send_compressed(&mut self, topic: &str, body: &[u8]) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+
+        let frames = [Message::from(topic), crate::compress::compress(body)];
+        let mut msg = MultipartIter(frames.into_iter());
+        poll_fn(|cx| self.inner.socket.send(cx, &mut msg)).await?;
+        Ok(())
+    }
+
+    /// Send every message of `batch` within a single wakeup of the reactor, returning how many
+    /// were sent.
+    ///
+    /// Going through the `Sink` polls the socket once per message, which dominates the cost of
+    /// sending many small messages. This waits once for the socket to be writable, then hands
+    /// the messages to ØMQ one after the other without waiting. A PUB socket drops the
+    /// messages beyond its high water mark silently, and they count as sent. With
+    /// `ZMQ_XPUB_NODROP` set on the raw socket, ØMQ refuses them with `EAGAIN` instead: the
+    /// first refused message ends the batch, and the returned count tells how far the batch
+    /// got. ØMQ takes the refused message anyway, so it is dropped, and a caller retrying later
+    /// must build that one again. The messages after it are still in `batch`, to send later.
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    pub async fn send_all_batched<B>(&mut self, batch: &mut B) -> Result<usize, SendError>
+    where
+        B: Iterator<Item = MultipartIter<I, T>>,
+    {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+
+        Ok(poll_fn(|cx| self.inner.socket.send_batch(cx, batch)).await?)
+    }
+
+    /// Send `body` under each of `topics`, as one message per topic.
+    ///
+    /// The body is built once and copied into the message of every topic, which saves building
+    /// it again for each of them. Messages are sent in the order of `topics`, after the messages
+    /// waiting in the send queue. Each message is delivered on its own, so a failure part way
+    /// leaves the messages of the earlier topics sent.
+    pub async fn send_to_topics(
+        &mut self,
+        topics: &[&str],
+        body: impl Into<Message>,
+    ) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+
+        let body = body.into();
+        for topic in topics {
+            let mut msg =
+                MultipartIter([Message::from(*topic), Message::from(&body[..])].into_iter());
+            poll_fn(|cx| self.inner.socket.send(cx, &mut msg)).await?;
+        }
+        Ok(())
+    }
+
+    /// Register a frame under `key` to be sent repeatedly with [`send_cached`].
+    ///
+    /// The bytes are kept by the socket until [`clear_cached_frames`] is called or the socket is
+    /// dropped; registering another frame under the same key replaces them. Every send copies
+    /// them into a new message, which ØMQ owns until it is delivered, so the payload is only
+    /// built once but the cache holds one more copy of it for as long as it is registered.
+    ///
+    /// [`send_cached`]: #method.send_cached
+    /// [`clear_cached_frames`]: #method.clear_cached_frames
+    pub fn with_cached_frame(
+        &mut self,
+        key: impl Into<String>,
+        bytes: impl Into<Vec<u8>>,
+    ) -> &mut Self {
+        self.cached.insert(key.into(), bytes.into());
+        self
+    }
+
+    /// Send the frame cached under `key` as a single-frame message.
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    /// Returns [`SendError::InvalidMessage`] if no frame is cached under `key`.
+    ///
+    /// [`SendError::InvalidMessage`]: ../enum.SendError.html#variant.InvalidMessage
+    pub async fn send_cached(&mut self, key: &str) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+
+        let frame = self
+            .cached
+            .get(key)
+            .map(|bytes| Message::from(&bytes[..]))
+            .ok_or(SendError::InvalidMessage)?;
+        let mut msg = MultipartIter(once(frame));
+        poll_fn(|cx| self.inner.socket.send(cx, &mut msg)).await?;
+        Ok(())
+    }
+
+    /// Drop every frame registered with [`with_cached_frame`](#method.with_cached_frame).
+    pub fn clear_cached_frames(&mut self) {
+        self.cached.clear();
+    }
+
+    /// Estimate how many subscribers are connected to the socket.
+    ///
+    /// The estimate counts the connections accepted minus those disconnected, as reported by
+    /// the monitor of the socket. It lags behind reality, and ignores subscribers reached
+    /// through `connect` rather than `bind`. The first call starts the monitor unless
+    /// [`SocketBuilder::with_monitor`] did before binding, so only with the latter are the
+    /// subscribers connected beforehand counted. Returns 0 if monitoring could not be started.
+    ///
+    /// [`SocketBuilder::with_monitor`]: ../struct.SocketBuilder.html#method.with_monitor
+    pub fn connected_subscriber_estimate(&self) -> usize {
+        let events = self.inner.socket.events();
+        if events.start(self.as_raw_socket()).is_err() {
+            return 0;
+        }
+        events.accepted_connections().unwrap_or(0)
+    }
+
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
-        self.0.socket.as_socket()
+        self.inner.socket.as_socket()
+    }
+
+    /// Close the socket gracefully, completing once ØMQ is done with it.
+    ///
+    /// Messages waiting in the send queue are flushed first. ØMQ then keeps delivering the
+    /// messages it holds for the linger period of the socket, see `set_linger` on the raw
+    /// socket, and the returned future completes once they are all delivered or the linger
+    /// period is over, when ØMQ destroys the socket. With the default infinite linger period,
+    /// this waits until a peer receives the messages, so set a finite one to bound the wait.
+    pub async fn close(mut self) -> Result<(), SocketError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Send `msg` as a single-frame message, without building a [`MultipartIter`].
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    ///
+    /// [`MultipartIter`]: ../struct.MultipartIter.html
+    pub async fn send_msg(&mut self, msg: Message) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+
+        let mut msg = MultipartIter(once(msg));
+        poll_fn(|cx| self.inner.socket.send(cx, &mut msg)).await?;
+        Ok(())
+    }
+
+    /// Number of messages dropped so far because the send queue was full.
+    ///
+    /// This stays at zero unless the socket was built with a dropping [`SendOverflow`] policy.
+    ///
+    /// [`SendOverflow`]: ../enum.SendOverflow.html
+    pub fn dropped_messages(&self) -> u64 {
+        self.inner.queue.dropped()
     }
 
     /// Set the CURVE server flag on the socket.
+    ///
+    /// See [switching roles](../curve/index.html#switching-roles) for a socket which was a
+    /// client before.
     pub fn set_curve_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_curve_server(enabled)?;
         Ok(self)
@@ -71,6 +323,8 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Publish<I, T> {
     }
 
     /// Set the CURVE server key on the socket.
+    ///
+    /// This makes the socket a CURVE client, resetting the server flag if it was set.
     pub fn set_curve_serverkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_curve_serverkey(key)?;
         Ok(self)
@@ -82,9 +336,30 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Publish<I, T> {
         Ok(self)
     }
 
+    /// Set the PLAIN server flag on the socket, so that it authenticates its peers with the
+    /// username and password they present, through the ZAP handler of the context.
+    pub fn set_plain_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN username presented by the socket, making it a PLAIN client.
+    pub fn set_plain_username(&mut self, username: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_username(Some(username))?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN password presented by the socket, making it a PLAIN client.
+    pub fn set_plain_password(&mut self, password: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_password(Some(password))?;
+        Ok(self)
+    }
+
     /// Set the send high water mark for the socket.
     /// The high water mark is a hard limit on the maximum number of outstanding messages
     /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
     pub fn set_send_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_sndhwm(value)?;
         Ok(self)
@@ -94,38 +369,206 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Publish<I, T> {
     pub fn get_send_hwm(&self) -> Result<i32, zmq::Error> {
         self.as_raw_socket().get_sndhwm()
     }
+
+    /// Set the linger period of the socket, in milliseconds.
+    /// It bounds how long messages still queued when the socket is dropped are kept trying to
+    /// be delivered, during which terminating its context blocks. `-1` waits forever, which is
+    /// ØMQ's default, and `0` discards them right away.
+    pub fn set_linger(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_linger(millis)?;
+        Ok(self)
+    }
+
+    /// Get the linger period of the socket, in milliseconds.
+    pub fn get_linger(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_linger()
+    }
+
+    /// Add a metadata property, which ØMQ sends to the peers of the socket when connecting.
+    ///
+    /// ØMQ requires the `key` to start with `X-`. Peers read the property from the messages
+    /// of the connection with `Message::gets`. The metadata is exchanged when a connection is
+    /// made, so this fails with `EINVAL` on a socket its builder connected: add the property
+    /// before connecting with [`SocketBuilder::with_metadata`] instead. Like it, this needs a
+    /// libzmq built with the draft API.
+    ///
+    /// [`SocketBuilder::with_metadata`]: ../struct.SocketBuilder.html#method.with_metadata
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<&mut Self, zmq::Error> {
+        set_metadata(&self.inner.socket, key, value)?;
+        Ok(self)
+    }
+
+    /// Enable or disable IPv6 on the socket, which ØMQ disables by default.
+    /// The option is read when binding or connecting, so it only applies to the endpoints bound
+    /// or connected afterwards, see [`reset_connections`](#method.reset_connections). Prefer
+    /// [`SocketBuilder::with_ipv6`](../struct.SocketBuilder.html#method.with_ipv6) to have it
+    /// apply to the endpoint of the builder.
+    pub fn set_ipv6(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_ipv6(enabled)?;
+        Ok(self)
+    }
+
+    /// Check whether IPv6 is enabled on the socket.
+    pub fn get_ipv6(&self) -> Result<bool, zmq::Error> {
+        self.as_raw_socket().is_ipv6()
+    }
+
+    /// Set the TCP keepalive of the socket: `1` to enable it, `0` to disable it, or `-1`, the
+    /// default, to leave the setting of the operating system.
+    ///
+    /// Keepalives keep idle connections through NATs and firewalls alive and detect dead
+    /// peers. Like the other TCP options, it applies to the connections set up afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
+    pub fn set_tcp_keepalive(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive(value)?;
+        Ok(self)
+    }
+
+    /// Get the TCP keepalive of the socket.
+    pub fn get_tcp_keepalive(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive()
+    }
+
+    /// Set the idle time in seconds before the first keepalive probe, or `-1` for the default
+    /// of the operating system.
+    pub fn set_tcp_keepalive_idle(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_idle(value)?;
+        Ok(self)
+    }
+
+    /// Get the idle time in seconds before the first keepalive probe.
+    pub fn get_tcp_keepalive_idle(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_idle()
+    }
+
+    /// Set the number of unanswered keepalive probes before the connection is dropped, or `-1`
+    /// for the default of the operating system.
+    pub fn set_tcp_keepalive_cnt(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_cnt(value)?;
+        Ok(self)
+    }
+
+    /// Get the number of unanswered keepalive probes before the connection is dropped.
+    pub fn get_tcp_keepalive_cnt(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_cnt()
+    }
+
+    /// Set the interval in seconds between keepalive probes, or `-1` for the default of the
+    /// operating system.
+    pub fn set_tcp_keepalive_intvl(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_intvl(value)?;
+        Ok(self)
+    }
+
+    /// Get the interval in seconds between keepalive probes.
+    pub fn get_tcp_keepalive_intvl(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_intvl()
+    }
+
+    /// Drop the connections of the socket and establish them again.
+    ///
+    /// Options such as the high water marks are read when a connection is set up, so setting
+    /// them after `bind` or `connect` leaves the connections already established unchanged.
+    /// This applies them by disconnecting and reconnecting the endpoint of a connected socket,
+    /// or by unbinding and binding again that of a bound socket, whose peers then reconnect.
+    /// Messages queued for the dropped connections are lost.
+    ///
+    /// Only the endpoints given to the builder of the socket are reset: those bound or connected
+    /// through `as_raw_socket` are left as they are. A socket not built by a `SocketBuilder`
+    /// fails with `EINVAL`.
+    pub async fn reset_connections(&mut self) -> Result<&mut Self, zmq::Error> {
+        crate::socket::reset_connections(&self.inner.socket).await?;
+        Ok(self)
+    }
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Publish<I, T> {
     type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_ready(Pin::new(&mut self.get_mut().0), cx)
+        Sink::poll_ready(Pin::new(&mut self.get_mut().inner), cx)
             .map(|result| result.map_err(Into::into))
     }
 
     fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        Pin::new(&mut self.get_mut().0)
+        Pin::new(&mut self.get_mut().inner)
             .start_send(item)
             .map_err(Into::into)
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_flush(Pin::new(&mut self.get_mut().0), cx)
+        Sink::poll_flush(Pin::new(&mut self.get_mut().inner), cx)
             .map(|result| result.map_err(Into::into))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_close(Pin::new(&mut self.get_mut().0), cx)
+        Sink::poll_close(Pin::new(&mut self.get_mut().inner), cx)
             .map(|result| result.map_err(Into::into))
     }
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Publish<I, T> {
     fn from(socket: zmq::Socket) -> Self {
-        Self(Sender {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
-        })
+        Self::from_parts(socket.into())
+    }
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> FromParts for Publish<I, T> {
+    const SEND_QUEUE: bool = true;
+
+    fn from_parts(parts: SocketParts) -> Self {
+        Self {
+            inner: Sender {
+                socket: parts.socket,
+                queue: SendQueue::new(parts.send_queue),
+            },
+            cached: HashMap::new(),
+        }
+    }
+}
+
+/// Publish socket accepting raw bytes, created by [`Publish::into_byte_sink`].
+///
+/// [`Publish::into_byte_sink`]: struct.Publish.html#method.into_byte_sink
+pub struct ByteSink(Sender<Once<Vec<u8>>, Vec<u8>>);
+
+impl ByteSink {
+    /// Represent as `Socket` from zmq crate in case you want to call its methods.
+    pub fn as_raw_socket(&self) -> &zmq::Socket {
+        self.0.socket.as_socket()
+    }
+
+    /// Number of messages dropped so far because the send queue was full.
+    ///
+    /// This stays at zero unless the socket was built with a dropping [`SendOverflow`] policy.
+    ///
+    /// [`SendOverflow`]: ../enum.SendOverflow.html
+    pub fn dropped_messages(&self) -> u64 {
+        self.0.queue.dropped()
+    }
+}
+
+impl Sink<Vec<u8>> for ByteSink {
+    type Error = SendError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_ready(Pin::new(&mut self.get_mut().0), cx)
+            .map(|result| result.map_err(Into::into))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Vec<u8>) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().0)
+            .start_send(MultipartIter(once(item)))
+            .map_err(Into::into)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_flush(Pin::new(&mut self.get_mut().0), cx)
+            .map(|result| result.map_err(Into::into))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_close(Pin::new(&mut self.get_mut().0), cx)
+            .map(|result| result.map_err(Into::into))
     }
 }
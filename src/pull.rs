@@ -5,6 +5,12 @@
 //!
 //! A pull socket must be paired with a [`push`] socket.
 //!
+//! A pull socket connected to several push peers fair-queues them: ØMQ takes the next message
+//! from each peer in turn, skipping the peers with nothing pending. A fast peer is therefore not
+//! slowed down by a slow one, but it takes a larger share of the messages only when the others
+//! have nothing to send. [`Pull::with_source_stats`] counts the messages received from each
+//! peer, to see how the load of a pipeline is actually distributed.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -26,40 +32,222 @@
 //!
 //! [`push`]: ../push/index.html
 //! [`pull`]: fn.pull.html
+//! [`Pull::with_source_stats`]: struct.Pull.html#method.with_source_stats
 //! [`Stream`]: ../trait.Stream.html
 //! [`StreamExt`]: ../trait.StreamExt.html
 
+use std::collections::BTreeMap;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use zmq::SocketType;
+use futures::future::poll_fn;
+use zmq::{Message, SocketEvent, SocketType};
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, Receiver, SocketBuilder},
+    endpoint::Endpoint,
+    reactor::{AsRawSocket, SourceCounts},
+    socket::{close_socket, FromParts, Multipart, Receiver, SocketBuilder, SocketParts},
     RecvError, SocketError, Stream,
 };
 
 /// Create a ZMQ socket with PULL type
-pub fn pull(endpoint: &str) -> Result<SocketBuilder<'_, Pull>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn pull<'a>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Pull>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::PULL, endpoint))
 }
 
+/// Create a ZMQ socket with PULL type, built with `context`
+///
+/// This is [`pull`](fn.pull.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn pull_in<'a>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Pull>, SocketError> {
+    Ok(pull(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with PULL type
-pub struct Pull(Receiver);
+pub struct Pull {
+    pub(crate) inner: Receiver,
+    /// Messages received on each connection, counted by the socket once source stats are enabled.
+    sources: Option<Arc<SourceCounts>>,
+}
 
 impl Pull {
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
-        self.0.socket.as_socket()
+        self.inner.socket.as_socket()
+    }
+
+    /// Count the messages received from each peer, see [`source_stats`](#method.source_stats).
+    ///
+    /// Peers are told apart by the connection their messages come from, named after the events
+    /// of the monitor of the socket: a connection the socket made is named by its endpoint, such
+    /// as `tcp://127.0.0.1:5555`, and one it accepted by the endpoint it is bound to followed by
+    /// `#` and the file descriptor of the connection, such as `tcp://*:5555#12`. Messages of
+    /// connections without a file descriptor, such as `inproc` ones, are counted under an empty
+    /// name.
+    ///
+    /// This starts the monitor, unless the socket was built with
+    /// [`SocketBuilder::with_monitor`], and fails if it can't, see [`AsRawSocket::monitor`]. The
+    /// connections set up before it, which the monitor didn't report, are named by `#` followed
+    /// by their file descriptor. The count of a connection is dropped once the monitor reports
+    /// it closed, since its file descriptor can be reused by a later one.
+    ///
+    /// [`SocketBuilder::with_monitor`]: ../struct.SocketBuilder.html#method.with_monitor
+    /// [`AsRawSocket::monitor`]: ../trait.AsRawSocket.html#method.monitor
+    pub fn with_source_stats(mut self) -> Result<Self, zmq::Error> {
+        if self.sources.is_some() {
+            return Ok(self);
+        }
+        let sources = Arc::new(SourceCounts::default());
+        let closed = sources.clone();
+        let socket = &self.inner.socket;
+        socket.events().subscribe(
+            socket.as_socket(),
+            &[SocketEvent::DISCONNECTED, SocketEvent::CLOSED],
+            move |event| closed.forget(event.value),
+        )?;
+        self.inner.socket.attach_guard(sources.clone());
+        self.sources = Some(sources);
+        Ok(self)
+    }
+
+    /// Number of messages received from each peer connection since source stats were enabled
+    /// with [`with_source_stats`](#method.with_source_stats), empty if they were not.
+    pub fn source_stats(&self) -> BTreeMap<String, u64> {
+        let mut stats = BTreeMap::new();
+        let sources = match &self.sources {
+            Some(sources) => sources.get(),
+            None => return stats,
+        };
+        for (fd, count) in sources {
+            let name = match fd {
+                Some(fd) => self
+                    .inner
+                    .socket
+                    .events()
+                    .connection_name(fd)
+                    .unwrap_or_else(|| format!("#{}", fd)),
+                None => String::new(),
+            };
+            *stats.entry(name).or_insert(0) += count;
+        }
+        stats
+    }
+
+    /// Close the socket, completing once ØMQ has destroyed it.
+    ///
+    /// Messages received by ØMQ and not read yet are discarded.
+    pub async fn close(self) -> Result<(), SocketError> {
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Set the receive high water mark for the socket.
+    /// The high water mark is a hard limit on the maximum number of outstanding messages
+    /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards.
+    pub fn set_receive_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_rcvhwm(value)?;
+        Ok(self)
+    }
+
+    /// Get the receive high water mark for the socket.
+    pub fn get_receive_hwm(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_rcvhwm()
+    }
+
+    /// Set the largest message in bytes the socket accepts from its peers, or `-1`, the
+    /// default, for no limit.
+    /// This protects against peers sending messages too large to hold in memory. ØMQ doesn't
+    /// deliver an oversized message nor report it to the receiver: it drops the connection of
+    /// the peer which sent it, which shows as a `DISCONNECTED` event on the
+    /// [monitor](../trait.AsRawSocket.html#method.monitor) of the socket. The peer may then
+    /// reconnect. It only applies to connections established afterwards, and not to `inproc`
+    /// connections.
+    pub fn set_max_message_size(&mut self, value: i64) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_maxmsgsize(value)?;
+        Ok(self)
+    }
+
+    /// Get the largest message in bytes the socket accepts from its peers.
+    pub fn get_max_message_size(&self) -> Result<i64, zmq::Error> {
+        self.as_raw_socket().get_maxmsgsize()
+    }
+
+    /// Keep only the most recent message in the inbound queue, dropping the older ones that
+    /// haven't been received yet, which suits feeds where only the latest value matters.
+    ///
+    /// Conflation doesn't support multipart messages: only single-frame messages should be
+    /// sent to a conflating socket. ØMQ reads the option when a connection is set up, so it
+    /// only applies to the connections made afterwards; set it before connecting with
+    /// [`SocketBuilder::with_conflate`].
+    ///
+    /// [`SocketBuilder::with_conflate`]: ../struct.SocketBuilder.html#method.with_conflate
+    pub fn set_conflate(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_conflate(enabled)?;
+        Ok(self)
+    }
+
+    /// Check whether the socket keeps only the most recent message.
+    pub fn get_conflate(&self) -> Result<bool, zmq::Error> {
+        self.as_raw_socket().is_conflate()
+    }
+
+    /// Receive a single-frame message as a plain `Message`, without allocating a [`Multipart`].
+    ///
+    /// Only the first frame is returned; the frames following it in a multipart message are
+    /// received and discarded, so use the `Stream` for sockets carrying multipart messages.
+    ///
+    /// [`Multipart`]: ../struct.Multipart.html
+    pub async fn recv_msg(&self) -> Result<Message, RecvError> {
+        Ok(poll_fn(|cx| self.inner.socket.recv_msg(cx)).await?)
+    }
+
+    /// Receive messages for `duration` and return all of them, in the order they came.
+    ///
+    /// This samples whatever arrives during the window, which may be nothing at all, so the
+    /// returned `Vec` can be empty. The window is measured with the reactor timer and isn't
+    /// extended by a busy socket. A receive error ends the window early and discards the
+    /// messages collected so far.
+    pub async fn collect_for(&mut self, duration: Duration) -> Result<Vec<Multipart>, RecvError> {
+        Ok(self.inner.collect_for(duration).await?)
     }
 }
 
 impl From<zmq::Socket> for Pull {
     fn from(socket: zmq::Socket) -> Self {
-        Self(Receiver {
-            socket: ZmqSocket::from(socket),
-        })
+        Self::from_parts(socket.into())
+    }
+}
+
+impl FromParts for Pull {
+    const SEND_QUEUE: bool = false;
+
+    fn from_parts(parts: SocketParts) -> Self {
+        Self {
+            inner: Receiver {
+                socket: parts.socket,
+            },
+            sources: None,
+        }
     }
 }
 
@@ -67,7 +255,7 @@ impl Stream for Pull {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().0)
+        Pin::new(&mut self.get_mut().inner)
             .poll_next(cx)
             .map(|poll| poll.map(|result| result.map_err(Into::into)))
     }
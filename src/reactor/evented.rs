@@ -1,10 +1,64 @@
+use std::any::Any;
+#[cfg(feature = "test-util")]
+use std::collections::HashMap;
 use std::io;
+use std::os::unix::io::RawFd;
+#[cfg(feature = "test-util")]
+use std::sync::Mutex;
 
 use mio::unix::EventedFd;
 use mio::{Evented, Poll, PollOpt, Ready, Token};
+#[cfg(feature = "test-util")]
+use once_cell::sync::Lazy;
 use zmq::Socket;
 
-pub(crate) struct ZmqSocket(pub(crate) Socket);
+use crate::monitor::EventHub;
+use crate::sys::RawSocket;
+
+type Guard = Box<dyn Any + Send>;
+
+/// Reactor token of every registered socket, keyed by its file descriptor.
+#[cfg(feature = "test-util")]
+static TOKENS: Lazy<Mutex<HashMap<RawFd, Token>>> = Lazy::new(Default::default);
+
+/// Reactor token `fd` is registered with, if any.
+#[cfg(feature = "test-util")]
+pub(crate) fn registered_token(fd: RawFd) -> Option<Token> {
+    TOKENS.lock().unwrap().get(&fd).copied()
+}
+
+/// ØMQ socket along with its file descriptor, cached so that it can still be deregistered once
+/// the context has been terminated and `get_fd` fails, the values kept alive as long as it, its
+/// monitor, and its raw pointer.
+pub(crate) struct ZmqSocket(pub(crate) Socket, RawFd, Vec<Guard>, EventHub, RawSocket);
+
+impl ZmqSocket {
+    pub(crate) fn new(mut socket: Socket, events: EventHub) -> io::Result<Self> {
+        let fd = socket.get_fd()?;
+        let raw = RawSocket::of(&mut socket);
+        Ok(Self(socket, fd, Vec::new(), events, raw))
+    }
+
+    /// Pointer to the socket, for the options the zmq crate doesn't expose.
+    pub(crate) fn raw(&self) -> &RawSocket {
+        &self.4
+    }
+
+    /// The monitor shared by everything watching the events of the socket.
+    pub(crate) fn events(&self) -> &EventHub {
+        &self.3
+    }
+
+    /// Keep `guard` alive until the socket is dropped. Guards are dropped after the socket.
+    pub(crate) fn attach_guard<G: Any + Send>(&mut self, guard: G) {
+        self.2.push(Box::new(guard));
+    }
+
+    /// The first guard of type `G` attached to the socket, if any.
+    pub(crate) fn guard<G: Any>(&self) -> Option<&G> {
+        self.2.iter().find_map(|guard| guard.downcast_ref::<G>())
+    }
+}
 
 impl Evented for ZmqSocket {
     fn register(
@@ -14,7 +68,10 @@ impl Evented for ZmqSocket {
         interest: Ready,
         opts: PollOpt,
     ) -> io::Result<()> {
-        EventedFd(&self.0.get_fd()?).register(poll, token, interest, opts)
+        EventedFd(&self.1).register(poll, token, interest, opts)?;
+        #[cfg(feature = "test-util")]
+        TOKENS.lock().unwrap().insert(self.1, token);
+        Ok(())
     }
 
     fn reregister(
@@ -24,10 +81,15 @@ impl Evented for ZmqSocket {
         interest: Ready,
         opts: PollOpt,
     ) -> io::Result<()> {
-        EventedFd(&self.0.get_fd()?).reregister(poll, token, interest, opts)
+        EventedFd(&self.1).reregister(poll, token, interest, opts)?;
+        #[cfg(feature = "test-util")]
+        TOKENS.lock().unwrap().insert(self.1, token);
+        Ok(())
     }
 
     fn deregister(&self, poll: &Poll) -> io::Result<()> {
-        EventedFd(&self.0.get_fd()?).deregister(poll)
+        #[cfg(feature = "test-util")]
+        TOKENS.lock().unwrap().remove(&self.1);
+        EventedFd(&self.1).deregister(poll)
     }
 }
@@ -1,24 +1,236 @@
-//! Socket type registered in async-std reactor
+//! Socket type registered in the reactor
+//!
+//! The reactor is a single process-wide instance, started on first use with a thread of its
+//! own, so it doesn't depend on the runtime polling the sockets.
 pub(crate) mod evented;
 mod watcher;
 
+use crate::monitor::{EventHub, MonitorEvent, MonitorStream};
 use crate::socket::{Multipart, MultipartIter};
+use crate::SocketError;
 pub(crate) use watcher::Watcher;
 
+use futures::channel::oneshot;
+use futures::future::{select, Either};
 use futures::ready;
+use futures_timer::Delay;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::future::Future;
 use std::io::{self, ErrorKind};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
+use std::time::Duration;
 use zmq::Error;
 
 /// Trait to get the raw zmq socket.
+///
+/// It is implemented by every socket of this crate.
+///
+/// # File descriptor
+///
+/// The sockets also implement `AsRawFd`, returning the `ZMQ_FD` of the socket, to plug them
+/// into an external event loop such as `epoll` or mio. That descriptor doesn't carry messages
+/// and must never be read from or written to: it only signals, edge-triggered, that the state
+/// of the socket may have changed. On each signal, and after every send or receive done on
+/// the socket, check [`get_events`](#method.get_events) to learn whether it can actually
+/// receive or send, and keep going until it reports neither, as the descriptor may not signal
+/// again for the events already pending. The socket stays registered with the reactor of this
+/// crate.
 pub trait AsRawSocket {
     /// Method to get the raw zmq socket reference if users need to use it directly.
     fn as_socket(&self) -> &zmq::Socket;
+
+    /// Receive the `events` of the socket, or every event with `SocketEvent::ALL`, as a stream.
+    ///
+    /// The events go through an `inproc://` endpoint of the context of the socket. ØMQ allows a
+    /// single monitor per socket, so the sockets of this crate share theirs between every stream
+    /// and every method relying on monitor events, such as
+    /// `Subscribe::with_drop_notifications`. Monitoring the socket returned by `as_socket`
+    /// directly replaces that monitor. See the [`monitor`](monitor/index.html) module.
+    ///
+    /// The zmq crate doesn't tell the context of a socket, so only the sockets built by a
+    /// `SocketBuilder` can be monitored. The others, such as sockets converted from a
+    /// `zmq::Socket`, fail with `EFAULT`, and so does the default implementation.
+    fn monitor(&self, events: zmq::SocketEvent) -> Result<MonitorStream, SocketError> {
+        let _ = events;
+        Err(Error::EFAULT.into())
+    }
+
+    /// Check whether the socket can receive (`POLLIN`) or send (`POLLOUT`) a message right away,
+    /// reading the `ZMQ_EVENTS` option.
+    ///
+    /// This is how to interpret a signal of the `ZMQ_FD` descriptor of the socket, returned by
+    /// `as_raw_fd`. The descriptor is edge-triggered and ØMQ queues messages in batches, so a
+    /// single signal may stand for several messages, and a message may arrive while the
+    /// previous ones are being received without a new signal. Receiving a single message per
+    /// signal would then leave the others stuck until the next, unrelated signal. Instead,
+    /// receive until `POLLIN` is no longer reported, checking again after every message:
+    ///
+    /// ```no_run
+    /// use async_zmq::{zmq, AsRawSocket, Result, Subscribe};
+    ///
+    /// /// Called by the event loop whenever the descriptor of `subscribe` signals.
+    /// fn on_signal(subscribe: &Subscribe) -> Result<()> {
+    ///     while subscribe.get_events()?.contains(zmq::POLLIN) {
+    ///         if let Some(msg) = subscribe.try_recv()? {
+    ///             println!("{:?}", msg);
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    fn get_events(&self) -> Result<zmq::PollEvents, Error> {
+        self.as_socket().get_events()
+    }
+
+    /// Read the ØMQ option numbered `option` as raw bytes, at most `len` of them, as
+    /// `zmq_getsockopt` does.
+    ///
+    /// This reads back the options set with
+    /// [`SocketBuilder::set_raw_option`](struct.SocketBuilder.html#method.set_raw_option), or any
+    /// other option the crate has no getter for. Integer options are the native-endian bytes of
+    /// an `i32`, or of an `i64` for the few 64-bit ones.
+    ///
+    /// The zmq crate gives no access to the raw options of a `zmq::Socket`, so every socket of
+    /// this crate implements it on its own, and other implementations fail with `ENOTSUP`.
+    fn get_raw_option(&self, option: i32, len: usize) -> Result<Vec<u8>, Error> {
+        let _ = (option, len);
+        Err(Error::ENOTSUP)
+    }
 }
 
 pub(crate) type ZmqSocket = Watcher<evented::ZmqSocket>;
 
+/// Number of messages a socket received from each of its connections, by file descriptor, with
+/// `None` for the connections without one, such as `inproc` ones.
+///
+/// Attached to a socket as a guard, it counts every message the socket receives, and keeps the
+/// connection of the last one.
+#[derive(Default)]
+pub(crate) struct SourceCounts(Mutex<Sources>);
+
+#[derive(Default)]
+struct Sources {
+    counts: BTreeMap<Option<u32>, u64>,
+    last: Option<u32>,
+}
+
+impl SourceCounts {
+    /// The counts so far.
+    pub(crate) fn get(&self) -> BTreeMap<Option<u32>, u64> {
+        self.0.lock().unwrap().counts.clone()
+    }
+
+    /// File descriptor of the connection the last message came from.
+    pub(crate) fn last(&self) -> Option<u32> {
+        self.0.lock().unwrap().last
+    }
+
+    /// Drop the count of the connection with the file descriptor `fd`, once it is closed, so
+    /// that a later connection reusing the descriptor starts from zero.
+    pub(crate) fn forget(&self, fd: u32) {
+        self.0.lock().unwrap().counts.remove(&Some(fd));
+    }
+
+    fn record(&self, fd: Option<u32>) {
+        let mut sources = self.0.lock().unwrap();
+        *sources.counts.entry(fd).or_insert(0) += 1;
+        sources.last = fd;
+    }
+}
+
+/// Timer future driven by a background thread, so it works on any async runtime.
+pub(crate) fn sleep(duration: Duration) -> Delay {
+    Delay::new(duration)
+}
+
+/// Run `future`, failing with `EAGAIN` once `millis` milliseconds have elapsed. A negative
+/// value waits forever, as for the `ZMQ_RCVTIMEO` and `ZMQ_SNDTIMEO` options.
+///
+/// The reactor only makes non-blocking calls, on which ØMQ ignores those options, so they are
+/// enforced here with a timer instead.
+pub(crate) async fn timeout<F, R, E>(millis: i32, future: F) -> Result<R, E>
+where
+    F: Future<Output = Result<R, E>>,
+    E: From<Error>,
+{
+    if millis < 0 {
+        return future.await;
+    }
+    let future = std::pin::pin!(future);
+    match select(future, sleep(Duration::from_millis(millis as u64))).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(Error::EAGAIN.into()),
+    }
+}
+
+/// Run `future`, giving up with `None` once `duration` has elapsed.
+pub(crate) async fn within<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let future = std::pin::pin!(future);
+    match select(future, sleep(duration)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+/// Run a blocking closure on its own thread and wait for its result without blocking the executor.
+pub(crate) async fn spawn_blocking<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    std::thread::Builder::new()
+        .name("async-zmq/blocking".to_string())
+        .spawn(move || {
+            let _ = sender.send(f());
+        })
+        .expect("cannot start a thread driving blocking tasks");
+
+    receiver.await.expect("blocking task has panicked")
+}
+
+/// Check whether the file descriptor of `socket` is registered with the reactor.
+#[cfg(feature = "test-util")]
+pub(crate) fn is_registered(socket: &zmq::Socket) -> bool {
+    socket
+        .get_fd()
+        .ok()
+        .and_then(evented::registered_token)
+        .is_some_and(watcher::is_armed)
+}
+
 impl ZmqSocket {
+    /// Keep `guard` alive until the socket is dropped.
+    pub(crate) fn attach_guard<G: Any + Send>(&mut self, guard: G) {
+        self.get_mut().attach_guard(guard);
+    }
+
+    /// The first guard of type `G` attached to the socket, if any.
+    pub(crate) fn guard<G: Any>(&self) -> Option<&G> {
+        self.get_ref().guard()
+    }
+
+    /// The monitor shared by everything watching the events of the socket.
+    pub(crate) fn events(&self) -> &EventHub {
+        self.get_ref().events()
+    }
+
+    /// Set the option numbered `option` to the raw bytes `value`, as `zmq_setsockopt` does.
+    pub(crate) fn set_raw_option(&self, option: i32, value: &[u8]) -> Result<(), Error> {
+        self.get_ref().raw().set_option(option, value)
+    }
+
+    /// Call `on_event` from a background thread with each of the `events` of the socket, through
+    /// its shared monitor.
+    pub(crate) fn on_events<F>(&self, events: &[zmq::SocketEvent], on_event: F) -> Result<(), Error>
+    where
+        F: FnMut(&MonitorEvent) + Send + 'static,
+    {
+        self.events().subscribe(self.as_socket(), events, on_event)
+    }
+
     fn poll_event(&self, event: zmq::PollEvents) -> Result<(), io::Error> {
         if self.as_socket().get_events()?.contains(event) {
             Ok(())
@@ -36,6 +248,7 @@ impl ZmqSocket {
         //ready!()?;
 
         let mut buffer = buffer.0.by_ref().peekable();
+        let mut first = true;
         while let Some(msg) = buffer.next() {
             let mut flags = zmq::DONTWAIT;
             if buffer.peek().is_some() {
@@ -44,23 +257,91 @@ impl ZmqSocket {
 
             match self.as_socket().send(msg, flags) {
                 Ok(_) => {}
-                Err(Error::EAGAIN) => return Poll::Pending,
+                // ØMQ takes the rest of a message once it took its first frame.
+                Err(Error::EAGAIN) if first => return Poll::Pending,
                 Err(e) => return Poll::Ready(Err(e)),
             }
+            first = false;
         }
 
         Poll::Ready(Ok(()))
     }
 
+    /// Send the messages of `batch` in turn, waiting only once for the socket to be writable.
+    ///
+    /// Stops at the first message ØMQ refuses with `EAGAIN`, which is dropped, and returns the
+    /// number of messages sent before it. The messages after it are left in `batch`. ØMQ takes
+    /// the frames of a message all at once or not at all, so only its first frame can be
+    /// refused, and `EAGAIN` on a later one is returned as an error.
+    pub(crate) fn send_batch<B, I, T>(
+        &self,
+        cx: &mut Context<'_>,
+        batch: &mut B,
+    ) -> Poll<Result<usize, Error>>
+    where
+        B: Iterator<Item = MultipartIter<I, T>>,
+        I: Iterator<Item = T>,
+        T: Into<zmq::Message>,
+    {
+        let _ = ready!(self.poll_write_with(cx, |_| { self.poll_event(zmq::POLLOUT) }));
+
+        let mut sent = 0;
+        for msg in batch.by_ref() {
+            let mut frames = msg.0.peekable();
+            let mut first = true;
+            while let Some(frame) = frames.next() {
+                let mut flags = zmq::DONTWAIT;
+                if frames.peek().is_some() {
+                    flags |= zmq::SNDMORE;
+                }
+
+                match self.as_socket().send(frame, flags) {
+                    Ok(_) => {}
+                    Err(Error::EAGAIN) if first => return Poll::Ready(Ok(sent)),
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+                first = false;
+            }
+            sent += 1;
+        }
+
+        Poll::Ready(Ok(sent))
+    }
+
+    /// Send a single frame taken from `frame`, flagged with `SNDMORE` if `more` frames follow.
+    pub(crate) fn send_frame(
+        &self,
+        cx: &mut Context<'_>,
+        frame: &mut Option<zmq::Message>,
+        more: bool,
+    ) -> Poll<Result<(), Error>> {
+        let _ = ready!(self.poll_write_with(cx, |_| { self.poll_event(zmq::POLLOUT) }));
+
+        let flags = if more {
+            zmq::DONTWAIT | zmq::SNDMORE
+        } else {
+            zmq::DONTWAIT
+        };
+        match frame.take() {
+            Some(msg) => match self.as_socket().send(msg, flags) {
+                Err(Error::EAGAIN) => Poll::Pending,
+                result => Poll::Ready(result),
+            },
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
     pub(crate) fn recv(&self, cx: &mut Context<'_>) -> Poll<Result<Multipart, Error>> {
         let _ = ready!(self.poll_read_with(cx, |_| { self.poll_event(zmq::POLLIN) }));
 
-        let mut buffer = Vec::new();
+        // All frames are read within this poll: ØMQ only signals a multipart message once all its
+        // frames have arrived, so a cancelled receive never leaves a partial message behind.
+        let mut buffer = Multipart::default();
         let mut more = true;
 
         while more {
             let mut msg = zmq::Message::new();
-            match self.as_socket().recv(&mut msg, zmq::DONTWAIT) {
+            match self.recv_frame(&mut msg, buffer.is_empty()) {
                 Ok(_) => {
                     more = msg.get_more();
                     buffer.push(msg);
@@ -71,11 +352,74 @@ impl ZmqSocket {
 
         Poll::Ready(Ok(buffer))
     }
+
+    /// Receive a message if one is available right away, without involving the reactor.
+    pub(crate) fn try_recv(&self) -> Result<Option<Multipart>, Error> {
+        let mut buffer = Multipart::default();
+        let mut more = true;
+
+        while more {
+            let mut msg = zmq::Message::new();
+            match self.recv_frame(&mut msg, buffer.is_empty()) {
+                Ok(_) => {
+                    more = msg.get_more();
+                    buffer.push(msg);
+                }
+                // Frames of a multipart message arrive together, so only the first can be missing.
+                Err(Error::EAGAIN) if buffer.is_empty() => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Some(buffer))
+    }
+
+    /// Receive a frame without blocking, counting it in the [`SourceCounts`] of the socket, if
+    /// any, when it is the `first` frame of a message.
+    fn recv_frame(&self, msg: &mut zmq::Message, first: bool) -> Result<(), Error> {
+        match self.guard::<Arc<SourceCounts>>() {
+            Some(sources) if first => {
+                let (frame, fd) = self.get_ref().raw().recv_with_source(zmq::DONTWAIT)?;
+                *msg = frame;
+                sources.record(fd);
+                Ok(())
+            }
+            _ => self.as_socket().recv(msg, zmq::DONTWAIT),
+        }
+    }
+
+    /// Receive the first frame of a message, discarding any frame following it.
+    pub(crate) fn recv_msg(&self, cx: &mut Context<'_>) -> Poll<Result<zmq::Message, Error>> {
+        let _ = ready!(self.poll_read_with(cx, |_| { self.poll_event(zmq::POLLIN) }));
+
+        let mut msg = zmq::Message::new();
+        self.recv_frame(&mut msg, true)?;
+        if msg.get_more() {
+            let mut rest = zmq::Message::new();
+            loop {
+                self.as_socket().recv(&mut rest, zmq::DONTWAIT)?;
+                if !rest.get_more() {
+                    break;
+                }
+            }
+        }
+
+        Poll::Ready(Ok(msg))
+    }
+}
+
+impl ZmqSocket {
+    /// Wrap `socket`, whose monitor, if already started, is `events`.
+    pub(crate) fn with_events(socket: zmq::Socket, events: EventHub) -> Self {
+        let socket =
+            evented::ZmqSocket::new(socket, events).expect("cannot get the socket file descriptor");
+        Watcher::new(socket)
+    }
 }
 
 impl From<zmq::Socket> for ZmqSocket {
     fn from(socket: zmq::Socket) -> Self {
-        Watcher::new(evented::ZmqSocket(socket))
+        Self::with_events(socket, EventHub::default())
     }
 }
 
@@ -83,4 +427,12 @@ impl AsRawSocket for ZmqSocket {
     fn as_socket(&self) -> &zmq::Socket {
         &self.get_ref().0
     }
+
+    fn monitor(&self, events: zmq::SocketEvent) -> Result<MonitorStream, SocketError> {
+        self.events().stream(self.as_socket(), events)
+    }
+
+    fn get_raw_option(&self, option: i32, len: usize) -> Result<Vec<u8>, Error> {
+        self.get_ref().raw().get_option(option, len)
+    }
 }
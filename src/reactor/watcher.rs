@@ -141,6 +141,16 @@ static REACTOR: Lazy<Reactor> = Lazy::new(|| {
     Reactor::new().expect("cannot initialize reactor")
 });
 
+/// Check whether `token` belongs to an entry currently armed in the reactor's poller.
+pub(crate) fn is_armed(token: mio::Token) -> bool {
+    REACTOR
+        .entries
+        .lock()
+        .unwrap()
+        .get(token.0)
+        .is_some_and(|entry| entry.token == token && token != REACTOR.notify_token)
+}
+
 /// Waits on the poller for new events and wakes up tasks blocked on I/O handles.
 fn main_loop() -> io::Result<()> {
     let reactor = &REACTOR;
@@ -219,6 +229,11 @@ impl<T: Evented> Watcher<T> {
         self.source.as_ref().unwrap()
     }
 
+    /// Returns a mutable reference to the inner I/O event source.
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.source.as_mut().unwrap()
+    }
+
     /// Polls the inner I/O source for a non-blocking read operation.
     ///
     /// If the operation returns an error of the `io::ErrorKind::WouldBlock` kind, the current task
@@ -28,39 +28,89 @@ use std::{
     pin::Pin,
     sync::atomic::{AtomicBool, Ordering},
     task::{Context, Poll},
+    time::Duration,
 };
 
 use zmq::{Message, SocketType};
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, MultipartIter, Sender, SocketBuilder},
+    endpoint::Endpoint,
+    reactor::{timeout, within, AsRawSocket},
+    socket::{
+        close_socket, set_identity, set_metadata, FromParts, Multipart, MultipartIter, SendQueue,
+        Sender, SocketBuilder, SocketParts,
+    },
     RecvError, RequestReplyError, SocketError,
 };
 
-use futures::{future::poll_fn, Stream};
+use futures::{future::poll_fn, Sink, Stream, StreamExt};
 
 /// Create a ZMQ socket with REP type
-pub fn reply<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Reply<I, T>>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn reply<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Reply<I, T>>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::REP, endpoint))
 }
 
+/// Create a ZMQ socket with REP type, built with `context`
+///
+/// This is [`reply`](fn.reply.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+///
+/// ```no_run
+/// use std::vec::IntoIter;
+///
+/// use async_zmq::{context::Context, Result};
+///
+/// #[async_std::main]
+/// async fn main() -> Result<()> {
+///     let context = Context::new();
+///     let reply = async_zmq::reply_in::<IntoIter<&str>, &str>(&context, "inproc://echo")?.bind()?;
+///     let request =
+///         async_zmq::request_in::<IntoIter<&str>, &str>(&context, "inproc://echo")?.connect()?;
+///
+///     request.send(vec!["ping"]).await?;
+///     let ping = reply.recv().await?;
+///     reply.send(vec![ping[0].as_str().unwrap_or_default()]).await?;
+///     println!("{:?}", request.recv().await?);
+///     Ok(())
+/// }
+/// ```
+pub fn reply_in<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Reply<I, T>>, SocketError> {
+    Ok(reply(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with REP type
 pub struct Reply<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
-    inner: Sender<I, T>,
+    pub(crate) inner: Sender<I, T>,
     received: AtomicBool,
+    /// Set while `recv` or a send is in flight.
+    busy: AtomicBool,
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Reply<I, T> {
     fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> FromParts for Reply<I, T> {
+    const SEND_QUEUE: bool = false;
+
+    fn from_parts(parts: SocketParts) -> Self {
         Self {
             inner: Sender {
-                socket: ZmqSocket::from(socket),
-                buffer: None,
+                socket: parts.socket,
+                queue: SendQueue::new(parts.send_queue),
             },
             received: AtomicBool::new(false),
+            busy: AtomicBool::new(false),
         }
     }
 }
@@ -68,19 +118,134 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Repl
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
     /// Receive request from REQ/DEALER socket. This should be the first method to be called, and then
     /// continue with receive/send pattern in synchronous way.
+    ///
+    /// Only one `recv` or send may be in flight at a time: starting another one, for example
+    /// from a future joined with this one, fails with [`RequestReplyError::ConcurrentAccess`].
+    ///
+    /// [`RequestReplyError::ConcurrentAccess`]: ../enum.RequestReplyError.html#variant.ConcurrentAccess
     pub async fn recv(&self) -> Result<Multipart, RequestReplyError> {
-        let msg = poll_fn(|cx| self.inner.socket.recv(cx)).await?;
-        self.received.store(true, Ordering::Relaxed);
+        let _busy = Busy::acquire(&self.busy)?;
+        let millis = self.as_raw_socket().get_rcvtimeo()?;
+        timeout(millis, poll_fn(|cx| self.poll_recv(cx))).await
+    }
+
+    /// Receive the next request, waiting at most `duration` for it.
+    ///
+    /// Unlike a receive timeout set on the socket, running out of time is not an error: it
+    /// returns `Ok(None)`, and the socket stays ready for the next receive. The deadline is
+    /// measured with the timer of the reactor, so this works on any runtime.
+    ///
+    /// The receive timeout of the socket doesn't apply, `duration` replaces it. Like `recv`,
+    /// this fails with [`RequestReplyError::ConcurrentAccess`] if another operation is in flight.
+    ///
+    /// [`RequestReplyError::ConcurrentAccess`]: ../enum.RequestReplyError.html#variant.ConcurrentAccess
+    pub async fn recv_timeout(
+        &self,
+        duration: Duration,
+    ) -> Result<Option<Multipart>, RequestReplyError> {
+        let _busy = Busy::acquire(&self.busy)?;
+        within(duration, poll_fn(|cx| self.poll_recv(cx)))
+            .await
+            .transpose()
+    }
+
+    /// Receive a request if one is available right away, or return `Ok(None)` without waiting.
+    ///
+    /// This is a building block for hand-written poll loops. A received request must be
+    /// answered with [`send`](#method.send) as usual. Like `recv`, this fails with
+    /// [`RequestReplyError::ConcurrentAccess`] if another operation is in flight.
+    ///
+    /// [`RequestReplyError::ConcurrentAccess`]: ../enum.RequestReplyError.html#variant.ConcurrentAccess
+    pub fn try_recv(&self) -> Result<Option<Multipart>, RequestReplyError> {
+        let _busy = Busy::acquire(&self.busy)?;
+        let msg = self.inner.socket.try_recv()?;
+        if msg.is_some() {
+            self.received.store(true, Ordering::Relaxed);
+        }
         Ok(msg)
     }
 
+    /// Poll receiving a request, for use in hand-written futures. [`recv`](#method.recv) is
+    /// built on it.
+    ///
+    /// The receive timeout of the socket is not applied here, since it needs a timer, and
+    /// neither is the check against concurrent operations, which spans several polls.
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RequestReplyError>> {
+        let msg = futures::ready!(self.inner.socket.recv(cx))?;
+        self.received.store(true, Ordering::Relaxed);
+        Poll::Ready(Ok(msg))
+    }
+
     /// Send reply to REQ/DEALER socket. [`recv`](#method.recv) must be called first in order to reply.
+    ///
+    /// ØMQ keeps the routing envelope of the last request and puts it back in front of the
+    /// reply, so the reply always goes to the peer that sent the request, including DEALER
+    /// peers behind a ROUTER. Sending without a pending request returns
+    /// [`RequestReplyError::NoPeerCaptured`].
     pub async fn send<S: Into<MultipartIter<I, T>>>(
         &self,
         msg: S,
     ) -> Result<(), RequestReplyError> {
-        let mut msg = msg.into();
-        poll_fn(move |cx| self.inner.socket.send(cx, &mut msg)).await?;
+        self.send_ref(&mut msg.into()).await
+    }
+
+    /// Send a reply whose frames are taken from `msg`, which the caller keeps.
+    /// [`recv`](#method.recv) must be called first in order to reply.
+    ///
+    /// No frame is taken from `msg` until the socket reports it can send, so a send which times
+    /// out or is cancelled while waiting for that leaves `msg` untouched and can be retried with
+    /// the same iterator. Frames taken are not given back: a successful send exhausts `msg`.
+    pub async fn send_ref(&self, msg: &mut MultipartIter<I, T>) -> Result<(), RequestReplyError> {
+        let _busy = Busy::acquire(&self.busy)?;
+        let millis = self.as_raw_socket().get_sndtimeo()?;
+        timeout(millis, poll_fn(|cx| self.poll_send(cx, msg))).await
+    }
+
+    /// Poll sending the reply whose frames are borrowed from `msg`, for use in hand-written
+    /// futures. [`send_ref`](#method.send_ref) is built on it.
+    ///
+    /// It fails right away with [`RequestReplyError::NoPeerCaptured`] if no request is pending.
+    /// The send timeout of the socket is not applied here, since it needs a timer, and neither
+    /// is the check against concurrent operations.
+    ///
+    /// [`RequestReplyError::NoPeerCaptured`]: ../enum.RequestReplyError.html#variant.NoPeerCaptured
+    pub fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        msg: &mut MultipartIter<I, T>,
+    ) -> Poll<Result<(), RequestReplyError>> {
+        if !self.received.load(Ordering::Relaxed) {
+            return Poll::Ready(Err(RequestReplyError::NoPeerCaptured));
+        }
+        futures::ready!(self.inner.socket.send(cx, msg))?;
+        self.received.store(false, Ordering::Relaxed);
+        Poll::Ready(Ok(()))
+    }
+
+    /// Send a reply whose frames are produced by `frames`, sending each frame as soon as it is
+    /// computed. [`recv`](#method.recv) must be called first in order to reply.
+    ///
+    /// The frames still form one multipart message: the peer receives the reply at once, after
+    /// the last frame is sent. Each frame is held until the next one is produced, to know whether
+    /// more follow. An empty stream sends a single empty frame, since a reply cannot be empty.
+    pub async fn send_stream<S: Stream<Item = Message>>(
+        &self,
+        frames: S,
+    ) -> Result<(), RequestReplyError> {
+        let _busy = Busy::acquire(&self.busy)?;
+        if !self.received.load(Ordering::Relaxed) {
+            return Err(RequestReplyError::NoPeerCaptured);
+        }
+        futures::pin_mut!(frames);
+
+        let mut pending = frames.next().await.unwrap_or_else(Message::new);
+        while let Some(next) = frames.next().await {
+            let mut frame = Some(pending);
+            poll_fn(|cx| self.inner.socket.send_frame(cx, &mut frame, true)).await?;
+            pending = next;
+        }
+        let mut frame = Some(pending);
+        poll_fn(|cx| self.inner.socket.send_frame(cx, &mut frame, false)).await?;
         self.received.store(false, Ordering::Relaxed);
         Ok(())
     }
@@ -90,7 +255,32 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
         self.inner.socket.as_socket()
     }
 
+    /// Close the socket gracefully, completing once ØMQ is done with it.
+    ///
+    /// Messages waiting in the send queue are flushed first. ØMQ then keeps delivering the
+    /// messages it holds for the linger period of the socket, see `set_linger` on the raw
+    /// socket, and the returned future completes once they are all delivered or the linger
+    /// period is over, when ØMQ destroys the socket. With the default infinite linger period,
+    /// this waits until a peer receives the messages, so set a finite one to bound the wait.
+    pub async fn close(mut self) -> Result<(), SocketError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
     /// Set the CURVE server flag on the socket.
+    ///
+    /// See [switching roles](../curve/index.html#switching-roles) for a socket which was a
+    /// client before.
     pub fn set_curve_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
         self.inner.socket.as_socket().set_curve_server(enabled)?;
         Ok(self)
@@ -109,6 +299,8 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
     }
 
     /// Set the CURVE server key on the socket.
+    ///
+    /// This makes the socket a CURVE client, resetting the server flag if it was set.
     pub fn set_curve_serverkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
         self.inner.socket.as_socket().set_curve_serverkey(key)?;
         Ok(self)
@@ -120,9 +312,69 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
         Ok(self)
     }
 
+    /// Set the PLAIN server flag on the socket, so that it authenticates its peers with the
+    /// username and password they present, through the ZAP handler of the context.
+    pub fn set_plain_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.inner.socket.as_socket().set_plain_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN username presented by the socket, making it a PLAIN client.
+    pub fn set_plain_username(&mut self, username: &str) -> Result<&mut Self, zmq::Error> {
+        self.inner
+            .socket
+            .as_socket()
+            .set_plain_username(Some(username))?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN password presented by the socket, making it a PLAIN client.
+    pub fn set_plain_password(&mut self, password: &str) -> Result<&mut Self, zmq::Error> {
+        self.inner
+            .socket
+            .as_socket()
+            .set_plain_password(Some(password))?;
+        Ok(self)
+    }
+
+    /// Set the routing id this socket presents to its peers, which a ROUTER peer sees as the
+    /// first frame of the messages from this socket.
+    ///
+    /// The routing id is exchanged when a connection is made, so it only applies to the
+    /// connections made afterwards. This fails with `EINVAL` on a socket its builder connected,
+    /// where it would have no effect: set the routing id before connecting with
+    /// [`SocketBuilder::with_identity`] instead.
+    ///
+    /// [`SocketBuilder::with_identity`]: ../struct.SocketBuilder.html#method.with_identity
+    pub fn set_identity(&mut self, id: &[u8]) -> Result<&mut Self, zmq::Error> {
+        set_identity(&self.inner.socket, id)?;
+        Ok(self)
+    }
+
+    /// Get the routing id this socket presents to its peers, empty unless one was set.
+    pub fn get_identity(&self) -> Result<Vec<u8>, zmq::Error> {
+        self.as_raw_socket().get_identity()
+    }
+
+    /// Add a metadata property, which ØMQ sends to the peers of the socket when connecting.
+    ///
+    /// ØMQ requires the `key` to start with `X-`. Peers read the property from the messages
+    /// of the connection with `Message::gets`. The metadata is exchanged when a connection is
+    /// made, so this fails with `EINVAL` on a socket its builder connected: add the property
+    /// before connecting with [`SocketBuilder::with_metadata`] instead. Like it, this needs a
+    /// libzmq built with the draft API.
+    ///
+    /// [`SocketBuilder::with_metadata`]: ../struct.SocketBuilder.html#method.with_metadata
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<&mut Self, zmq::Error> {
+        set_metadata(&self.inner.socket, key, value)?;
+        Ok(self)
+    }
+
     /// Set the send high water mark for the socket.
     /// The high water mark is a hard limit on the maximum number of outstanding messages
     /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
     pub fn set_send_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_sndhwm(value)?;
         Ok(self)
@@ -136,6 +388,8 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
     /// Set the receive high water mark for the socket.
     /// The high water mark is a hard limit on the maximum number of outstanding messages
     /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
     pub fn set_receive_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_rcvhwm(value)?;
         Ok(self)
@@ -145,12 +399,189 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
     pub fn get_receive_hwm(&self) -> Result<i32, zmq::Error> {
         self.as_raw_socket().get_rcvhwm()
     }
+
+    /// Set the largest message in bytes the socket accepts from its peers, or `-1`, the
+    /// default, for no limit.
+    /// This protects against peers sending messages too large to hold in memory. ØMQ doesn't
+    /// deliver an oversized message nor report it to the receiver: it drops the connection of
+    /// the peer which sent it, which shows as a `DISCONNECTED` event on the
+    /// [monitor](../trait.AsRawSocket.html#method.monitor) of the socket. The peer may then
+    /// reconnect. It only applies to connections established afterwards, and not to `inproc`
+    /// connections.
+    pub fn set_max_message_size(&mut self, value: i64) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_maxmsgsize(value)?;
+        Ok(self)
+    }
+
+    /// Get the largest message in bytes the socket accepts from its peers.
+    pub fn get_max_message_size(&self) -> Result<i64, zmq::Error> {
+        self.as_raw_socket().get_maxmsgsize()
+    }
+
+    /// Set the timeout of [`recv`](#method.recv), in milliseconds.
+    /// Once it elapses, `recv` fails with [`RequestReplyError::TimedOut`], which converts into
+    /// `zmq::Error::EAGAIN`. `-1`, the default, waits forever. The wait is still driven by the
+    /// reactor and a timer, so it never blocks the executor.
+    ///
+    /// [`RequestReplyError::TimedOut`]: ../enum.RequestReplyError.html#variant.TimedOut
+    pub fn set_receive_timeout(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_rcvtimeo(millis)?;
+        Ok(self)
+    }
+
+    /// Get the timeout of [`recv`](#method.recv), in milliseconds.
+    pub fn get_receive_timeout(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_rcvtimeo()
+    }
+
+    /// Set the timeout of [`send`](#method.send), in milliseconds, which applies while the
+    /// message cannot be queued, for example with no peer connected.
+    /// Once it elapses, `send` fails with [`RequestReplyError::TimedOut`]. `-1`, the default,
+    /// waits forever.
+    ///
+    /// [`RequestReplyError::TimedOut`]: ../enum.RequestReplyError.html#variant.TimedOut
+    pub fn set_send_timeout(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_sndtimeo(millis)?;
+        Ok(self)
+    }
+
+    /// Get the timeout of [`send`](#method.send), in milliseconds.
+    pub fn get_send_timeout(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_sndtimeo()
+    }
+
+    /// Set the linger period of the socket, in milliseconds.
+    /// It bounds how long messages still queued when the socket is dropped are kept trying to
+    /// be delivered, during which terminating its context blocks. `-1` waits forever, which is
+    /// ØMQ's default, and `0` discards them right away.
+    pub fn set_linger(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_linger(millis)?;
+        Ok(self)
+    }
+
+    /// Get the linger period of the socket, in milliseconds.
+    pub fn get_linger(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_linger()
+    }
+
+    /// Enable or disable IPv6 on the socket, which ØMQ disables by default.
+    /// The option is read when binding or connecting, so it only applies to the endpoints bound
+    /// or connected afterwards, see [`reset_connections`](#method.reset_connections). Prefer
+    /// [`SocketBuilder::with_ipv6`](../struct.SocketBuilder.html#method.with_ipv6) to have it
+    /// apply to the endpoint of the builder.
+    pub fn set_ipv6(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_ipv6(enabled)?;
+        Ok(self)
+    }
+
+    /// Check whether IPv6 is enabled on the socket.
+    pub fn get_ipv6(&self) -> Result<bool, zmq::Error> {
+        self.as_raw_socket().is_ipv6()
+    }
+
+    /// Set the TCP keepalive of the socket: `1` to enable it, `0` to disable it, or `-1`, the
+    /// default, to leave the setting of the operating system.
+    ///
+    /// Keepalives keep idle connections through NATs and firewalls alive and detect dead
+    /// peers. Like the other TCP options, it applies to the connections set up afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
+    pub fn set_tcp_keepalive(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive(value)?;
+        Ok(self)
+    }
+
+    /// Get the TCP keepalive of the socket.
+    pub fn get_tcp_keepalive(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive()
+    }
+
+    /// Set the idle time in seconds before the first keepalive probe, or `-1` for the default
+    /// of the operating system.
+    pub fn set_tcp_keepalive_idle(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_idle(value)?;
+        Ok(self)
+    }
+
+    /// Get the idle time in seconds before the first keepalive probe.
+    pub fn get_tcp_keepalive_idle(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_idle()
+    }
+
+    /// Set the number of unanswered keepalive probes before the connection is dropped, or `-1`
+    /// for the default of the operating system.
+    pub fn set_tcp_keepalive_cnt(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_cnt(value)?;
+        Ok(self)
+    }
+
+    /// Get the number of unanswered keepalive probes before the connection is dropped.
+    pub fn get_tcp_keepalive_cnt(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_cnt()
+    }
+
+    /// Set the interval in seconds between keepalive probes, or `-1` for the default of the
+    /// operating system.
+    pub fn set_tcp_keepalive_intvl(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_intvl(value)?;
+        Ok(self)
+    }
+
+    /// Get the interval in seconds between keepalive probes.
+    pub fn get_tcp_keepalive_intvl(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_intvl()
+    }
+
+    /// Drop the connections of the socket and establish them again.
+    ///
+    /// Options such as the high water marks are read when a connection is set up, so setting
+    /// them after `bind` or `connect` leaves the connections already established unchanged.
+    /// This applies them by disconnecting and reconnecting the endpoint of a connected socket,
+    /// or by unbinding and binding again that of a bound socket, whose peers then reconnect.
+    /// Messages queued for the dropped connections are lost.
+    ///
+    /// Only the endpoints given to the builder of the socket are reset: those bound or connected
+    /// through `as_raw_socket` are left as they are. A socket not built by a `SocketBuilder`
+    /// fails with `EINVAL`.
+    pub async fn reset_connections(&mut self) -> Result<&mut Self, zmq::Error> {
+        crate::socket::reset_connections(&self.inner.socket).await?;
+        Ok(self)
+    }
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for Reply<I, T> {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Poll::Ready(Some(Ok(futures::ready!(self.inner.socket.recv(cx))?)))
+        let msg = futures::ready!(self.inner.socket.recv(cx))?;
+        self.received.store(true, Ordering::Relaxed);
+        Poll::Ready(Some(Ok(msg)))
+    }
+}
+
+/// Mark of an operation in flight on a reply socket, cleared when it completes or is dropped.
+struct Busy<'a>(&'a AtomicBool);
+
+impl<'a> Busy<'a> {
+    fn acquire(busy: &'a AtomicBool) -> Result<Self, RequestReplyError> {
+        busy.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| Self(busy))
+            .map_err(|_| RequestReplyError::ConcurrentAccess)
+    }
+}
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(all(feature = "tracing", debug_assertions))]
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Drop for Reply<I, T> {
+    fn drop(&mut self) {
+        if self.received.load(Ordering::Relaxed) {
+            tracing::warn!(
+                "REP socket dropped after receiving a request but before sending the reply"
+            );
+        }
     }
 }
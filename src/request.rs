@@ -24,35 +24,80 @@
 //! [`request`]: fn.request.html
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, MultipartIter, Sender, SocketBuilder},
-    RequestReplyError, SocketError,
+    endpoint::Endpoint,
+    reactor::{timeout, within, AsRawSocket},
+    socket::{
+        close_socket, set_identity, set_metadata, FromParts, Multipart, MultipartIter, SendQueue,
+        Sender, SocketBuilder, SocketParts,
+    },
+    RequestReplyError, Sink, SocketError,
 };
 use futures::future::poll_fn;
-use std::sync::atomic::{AtomicBool, Ordering};
+use futures::task::AtomicWaker;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use zmq::{Message, SocketType};
 
 /// Create a ZMQ socket with REQ type
-pub fn request<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Request<I, T>>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn request<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Request<I, T>>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::REQ, endpoint))
 }
 
+/// Create a ZMQ socket with REQ type, built with `context`
+///
+/// This is [`request`](fn.request.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn request_in<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Request<I, T>>, SocketError> {
+    Ok(request(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with REQ type
 pub struct Request<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
-    inner: Sender<I, T>,
+    pub(crate) inner: Sender<I, T>,
     received: AtomicBool,
+    /// Disconnections reported by the monitor, once detection is enabled.
+    disconnects: Option<Arc<DisconnectWatch>>,
+    /// Disconnections already reported when the pending request was sent.
+    seen_disconnects: AtomicU64,
+}
+
+/// Disconnections of the peers of a request socket, waking the `recv` waiting for a reply.
+#[derive(Default)]
+struct DisconnectWatch {
+    count: AtomicU64,
+    waker: AtomicWaker,
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Request<I, T> {
     fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> FromParts for Request<I, T> {
+    const SEND_QUEUE: bool = false;
+
+    fn from_parts(parts: SocketParts) -> Self {
         Self {
             inner: Sender {
-                socket: ZmqSocket::from(socket),
-                buffer: None,
+                socket: parts.socket,
+                queue: SendQueue::new(parts.send_queue),
             },
-            received: AtomicBool::new(false),
+            // No request is pending on a fresh socket.
+            received: AtomicBool::new(true),
+            disconnects: None,
+            seen_disconnects: AtomicU64::new(0),
         }
     }
 }
@@ -64,17 +109,125 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
         &self,
         msg: S,
     ) -> Result<(), RequestReplyError> {
-        let mut msg = msg.into();
-        poll_fn(move |cx| self.inner.socket.send(cx, &mut msg)).await?;
-        self.received.store(false, Ordering::Relaxed);
-        Ok(())
+        self.send_ref(&mut msg.into()).await
+    }
+
+    /// Send a request whose frames are taken from `msg`, which the caller keeps.
+    ///
+    /// No frame is taken from `msg` until the socket reports it can send, so a send which times
+    /// out or is cancelled while waiting for that leaves `msg` untouched. A retry loop can
+    /// therefore call this again with the same iterator, without rebuilding the message. Frames
+    /// taken are not given back: a successful send exhausts `msg`, so sending the same request
+    /// again needs a new iterator, for example one more copy of a kept `Multipart`.
+    pub async fn send_ref(&self, msg: &mut MultipartIter<I, T>) -> Result<(), RequestReplyError> {
+        let millis = self.as_raw_socket().get_sndtimeo()?;
+        timeout(millis, poll_fn(|cx| self.poll_send(cx, msg))).await
     }
 
     /// Receive reply from REP/ROUTER socket. [`send`](#method.send) must be called first in order to receive reply.
     pub async fn recv(&self) -> Result<Multipart, RequestReplyError> {
-        let msg = poll_fn(|cx| self.inner.socket.recv(cx)).await?;
+        let millis = self.as_raw_socket().get_rcvtimeo()?;
+        timeout(millis, poll_fn(|cx| self.poll_recv(cx))).await
+    }
+
+    /// Receive the next reply, waiting at most `duration` for it.
+    ///
+    /// Unlike a receive timeout set on the socket, running out of time is not an error: it
+    /// returns `Ok(None)`, and the socket stays ready for the next receive. The deadline is
+    /// measured with the timer of the reactor, so this works on any runtime.
+    ///
+    /// The receive timeout of the socket doesn't apply, `duration` replaces it. The socket is
+    /// still awaiting the reply after a timeout, so `recv` can be called again to keep waiting.
+    pub async fn recv_timeout(
+        &self,
+        duration: Duration,
+    ) -> Result<Option<Multipart>, RequestReplyError> {
+        within(duration, poll_fn(|cx| self.poll_recv(cx)))
+            .await
+            .transpose()
+    }
+
+    /// Poll sending the request whose frames are borrowed from `msg`, for use in hand-written
+    /// futures. [`send_ref`](#method.send_ref) is built on it.
+    ///
+    /// The send timeout of the socket is not applied here, since it needs a timer. As with
+    /// `send_ref`, frames are only taken from `msg` once the socket is ready, so polling can be
+    /// abandoned and resumed with the same iterator.
+    pub fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        msg: &mut MultipartIter<I, T>,
+    ) -> Poll<Result<(), RequestReplyError>> {
+        futures::ready!(self.inner.socket.send(cx, msg))?;
+        self.received.store(false, Ordering::Relaxed);
+        if let Some(disconnects) = &self.disconnects {
+            let count = disconnects.count.load(Ordering::Acquire);
+            self.seen_disconnects.store(count, Ordering::Relaxed);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Poll receiving the reply, for use in hand-written futures. [`recv`](#method.recv) is
+    /// built on it.
+    ///
+    /// The receive timeout of the socket is not applied here, since it needs a timer.
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RequestReplyError>> {
+        let msg = match self.inner.socket.recv(cx) {
+            Poll::Ready(msg) => msg?,
+            Poll::Pending => return self.poll_disconnected(cx),
+        };
         self.received.store(true, Ordering::Relaxed);
-        Ok(msg)
+        Poll::Ready(Ok(msg))
+    }
+
+    /// Fail a pending `recv` with [`PeerDisconnected`] when a peer of the socket disconnects
+    /// while the reply is awaited.
+    ///
+    /// ØMQ hides disconnections from REQ sockets: the request is lost with the connection, and
+    /// `recv` would wait forever, or until the receive timeout. This starts monitoring the
+    /// socket, so that a disconnection reported after the request was sent ends the `recv`
+    /// with a distinct error, telling a lost peer apart from a slow one.
+    ///
+    /// Detection is best-effort. The monitor doesn't say which peer answers a request, so with
+    /// several peers, any of them disconnecting fails the pending `recv`. The event arrives
+    /// asynchronously, so a reply received just before it wins. The socket is still awaiting
+    /// the reply afterwards: drop it and connect a new one, or enable `set_req_relaxed` on the
+    /// raw socket to send the request again.
+    ///
+    /// The events come from the monitor the socket shares with [`AsRawSocket::monitor`], so
+    /// monitoring the socket that way too keeps the detection working.
+    ///
+    /// [`AsRawSocket::monitor`]: ../trait.AsRawSocket.html#method.monitor
+    /// [`PeerDisconnected`]: ../enum.RequestReplyError.html#variant.PeerDisconnected
+    pub fn detect_disconnects(&mut self) -> Result<&mut Self, zmq::Error> {
+        if self.disconnects.is_none() {
+            let disconnects = Arc::new(DisconnectWatch::default());
+            let watch = disconnects.clone();
+            self.inner
+                .socket
+                .on_events(&[zmq::SocketEvent::DISCONNECTED], move |_| {
+                    watch.count.fetch_add(1, Ordering::Release);
+                    watch.waker.wake();
+                })?;
+            self.disconnects = Some(disconnects);
+        }
+        Ok(self)
+    }
+
+    fn poll_disconnected(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Multipart, RequestReplyError>> {
+        let disconnects = match &self.disconnects {
+            Some(disconnects) => disconnects,
+            None => return Poll::Pending,
+        };
+        disconnects.waker.register(cx.waker());
+        let count = disconnects.count.load(Ordering::Acquire);
+        if count == self.seen_disconnects.swap(count, Ordering::Relaxed) {
+            return Poll::Pending;
+        }
+        Poll::Ready(Err(RequestReplyError::PeerDisconnected))
     }
 
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
@@ -82,7 +235,32 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
         self.inner.socket.as_socket()
     }
 
+    /// Close the socket gracefully, completing once ØMQ is done with it.
+    ///
+    /// Messages waiting in the send queue are flushed first. ØMQ then keeps delivering the
+    /// messages it holds for the linger period of the socket, see `set_linger` on the raw
+    /// socket, and the returned future completes once they are all delivered or the linger
+    /// period is over, when ØMQ destroys the socket. With the default infinite linger period,
+    /// this waits until a peer receives the messages, so set a finite one to bound the wait.
+    pub async fn close(mut self) -> Result<(), SocketError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
     /// Set the CURVE server flag on the socket.
+    ///
+    /// See [switching roles](../curve/index.html#switching-roles) for a socket which was a
+    /// client before.
     pub fn set_curve_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
         self.inner.socket.as_socket().set_curve_server(enabled)?;
         Ok(self)
@@ -101,6 +279,8 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
     }
 
     /// Set the CURVE server key on the socket.
+    ///
+    /// This makes the socket a CURVE client, resetting the server flag if it was set.
     pub fn set_curve_serverkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
         self.inner.socket.as_socket().set_curve_serverkey(key)?;
         Ok(self)
@@ -112,9 +292,69 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
         Ok(self)
     }
 
+    /// Set the PLAIN server flag on the socket, so that it authenticates its peers with the
+    /// username and password they present, through the ZAP handler of the context.
+    pub fn set_plain_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.inner.socket.as_socket().set_plain_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN username presented by the socket, making it a PLAIN client.
+    pub fn set_plain_username(&mut self, username: &str) -> Result<&mut Self, zmq::Error> {
+        self.inner
+            .socket
+            .as_socket()
+            .set_plain_username(Some(username))?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN password presented by the socket, making it a PLAIN client.
+    pub fn set_plain_password(&mut self, password: &str) -> Result<&mut Self, zmq::Error> {
+        self.inner
+            .socket
+            .as_socket()
+            .set_plain_password(Some(password))?;
+        Ok(self)
+    }
+
+    /// Set the routing id this socket presents to its peers, which a ROUTER peer sees as the
+    /// first frame of the messages from this socket.
+    ///
+    /// The routing id is exchanged when a connection is made, so it only applies to the
+    /// connections made afterwards. This fails with `EINVAL` on a socket its builder connected,
+    /// where it would have no effect: set the routing id before connecting with
+    /// [`SocketBuilder::with_identity`] instead.
+    ///
+    /// [`SocketBuilder::with_identity`]: ../struct.SocketBuilder.html#method.with_identity
+    pub fn set_identity(&mut self, id: &[u8]) -> Result<&mut Self, zmq::Error> {
+        set_identity(&self.inner.socket, id)?;
+        Ok(self)
+    }
+
+    /// Get the routing id this socket presents to its peers, empty unless one was set.
+    pub fn get_identity(&self) -> Result<Vec<u8>, zmq::Error> {
+        self.as_raw_socket().get_identity()
+    }
+
+    /// Add a metadata property, which ØMQ sends to the peers of the socket when connecting.
+    ///
+    /// ØMQ requires the `key` to start with `X-`. Peers read the property from the messages
+    /// of the connection with `Message::gets`. The metadata is exchanged when a connection is
+    /// made, so this fails with `EINVAL` on a socket its builder connected: add the property
+    /// before connecting with [`SocketBuilder::with_metadata`] instead. Like it, this needs a
+    /// libzmq built with the draft API.
+    ///
+    /// [`SocketBuilder::with_metadata`]: ../struct.SocketBuilder.html#method.with_metadata
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<&mut Self, zmq::Error> {
+        set_metadata(&self.inner.socket, key, value)?;
+        Ok(self)
+    }
+
     /// Set the send high water mark for the socket.
     /// The high water mark is a hard limit on the maximum number of outstanding messages
     /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
     pub fn set_send_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_sndhwm(value)?;
         Ok(self)
@@ -128,6 +368,8 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
     /// Set the receive high water mark for the socket.
     /// The high water mark is a hard limit on the maximum number of outstanding messages
     /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
     pub fn set_receive_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_rcvhwm(value)?;
         Ok(self)
@@ -137,4 +379,175 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
     pub fn get_receive_hwm(&self) -> Result<i32, zmq::Error> {
         self.as_raw_socket().get_rcvhwm()
     }
+
+    /// Set the initial reconnection interval in milliseconds, `100` by default, or `-1` to
+    /// disable reconnecting.
+    /// After a peer goes away, ØMQ waits this long before trying to connect to it again. The
+    /// wait doubles after each failed attempt, up to the
+    /// [maximum interval](#method.set_reconnect_interval_max) if one is set.
+    pub fn set_reconnect_interval(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_reconnect_ivl(value)?;
+        Ok(self)
+    }
+
+    /// Get the initial reconnection interval in milliseconds.
+    pub fn get_reconnect_interval(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_reconnect_ivl()
+    }
+
+    /// Set the maximum reconnection interval in milliseconds, the limit of the exponential
+    /// backoff between attempts. The default `0` keeps the initial interval for every attempt.
+    pub fn set_reconnect_interval_max(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_reconnect_ivl_max(value)?;
+        Ok(self)
+    }
+
+    /// Get the maximum reconnection interval in milliseconds.
+    pub fn get_reconnect_interval_max(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_reconnect_ivl_max()
+    }
+
+    /// Set the timeout of [`recv`](#method.recv), in milliseconds.
+    /// Once it elapses, `recv` fails with [`RequestReplyError::TimedOut`], which converts into
+    /// `zmq::Error::EAGAIN`. `-1`, the default, waits forever. The wait is still driven by the
+    /// reactor and a timer, so it never blocks the executor.
+    ///
+    /// The socket still awaits the reply after a timeout, so sending another request fails with
+    /// [`RequestReplyError::AwaitingReply`]: call `recv` again, or replace the socket.
+    ///
+    /// [`RequestReplyError::TimedOut`]: ../enum.RequestReplyError.html#variant.TimedOut
+    /// [`RequestReplyError::AwaitingReply`]: ../enum.RequestReplyError.html#variant.AwaitingReply
+    pub fn set_receive_timeout(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_rcvtimeo(millis)?;
+        Ok(self)
+    }
+
+    /// Get the timeout of [`recv`](#method.recv), in milliseconds.
+    pub fn get_receive_timeout(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_rcvtimeo()
+    }
+
+    /// Set the timeout of [`send`](#method.send), in milliseconds, which applies while the
+    /// message cannot be queued, for example with no peer connected.
+    /// Once it elapses, `send` fails with [`RequestReplyError::TimedOut`]. `-1`, the default,
+    /// waits forever.
+    ///
+    /// [`RequestReplyError::TimedOut`]: ../enum.RequestReplyError.html#variant.TimedOut
+    pub fn set_send_timeout(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_sndtimeo(millis)?;
+        Ok(self)
+    }
+
+    /// Get the timeout of [`send`](#method.send), in milliseconds.
+    pub fn get_send_timeout(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_sndtimeo()
+    }
+
+    /// Set the linger period of the socket, in milliseconds.
+    /// It bounds how long messages still queued when the socket is dropped are kept trying to
+    /// be delivered, during which terminating its context blocks. `-1` waits forever, which is
+    /// ØMQ's default, and `0` discards them right away.
+    pub fn set_linger(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_linger(millis)?;
+        Ok(self)
+    }
+
+    /// Get the linger period of the socket, in milliseconds.
+    pub fn get_linger(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_linger()
+    }
+
+    /// Enable or disable IPv6 on the socket, which ØMQ disables by default.
+    /// The option is read when binding or connecting, so it only applies to the endpoints bound
+    /// or connected afterwards, see [`reset_connections`](#method.reset_connections). Prefer
+    /// [`SocketBuilder::with_ipv6`](../struct.SocketBuilder.html#method.with_ipv6) to have it
+    /// apply to the endpoint of the builder.
+    pub fn set_ipv6(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_ipv6(enabled)?;
+        Ok(self)
+    }
+
+    /// Check whether IPv6 is enabled on the socket.
+    pub fn get_ipv6(&self) -> Result<bool, zmq::Error> {
+        self.as_raw_socket().is_ipv6()
+    }
+
+    /// Set the TCP keepalive of the socket: `1` to enable it, `0` to disable it, or `-1`, the
+    /// default, to leave the setting of the operating system.
+    ///
+    /// Keepalives keep idle connections through NATs and firewalls alive and detect dead
+    /// peers. Like the other TCP options, it applies to the connections set up afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
+    pub fn set_tcp_keepalive(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive(value)?;
+        Ok(self)
+    }
+
+    /// Get the TCP keepalive of the socket.
+    pub fn get_tcp_keepalive(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive()
+    }
+
+    /// Set the idle time in seconds before the first keepalive probe, or `-1` for the default
+    /// of the operating system.
+    pub fn set_tcp_keepalive_idle(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_idle(value)?;
+        Ok(self)
+    }
+
+    /// Get the idle time in seconds before the first keepalive probe.
+    pub fn get_tcp_keepalive_idle(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_idle()
+    }
+
+    /// Set the number of unanswered keepalive probes before the connection is dropped, or `-1`
+    /// for the default of the operating system.
+    pub fn set_tcp_keepalive_cnt(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_cnt(value)?;
+        Ok(self)
+    }
+
+    /// Get the number of unanswered keepalive probes before the connection is dropped.
+    pub fn get_tcp_keepalive_cnt(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_cnt()
+    }
+
+    /// Set the interval in seconds between keepalive probes, or `-1` for the default of the
+    /// operating system.
+    pub fn set_tcp_keepalive_intvl(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_intvl(value)?;
+        Ok(self)
+    }
+
+    /// Get the interval in seconds between keepalive probes.
+    pub fn get_tcp_keepalive_intvl(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_intvl()
+    }
+
+    /// Drop the connections of the socket and establish them again.
+    ///
+    /// Options such as the high water marks are read when a connection is set up, so setting
+    /// them after `bind` or `connect` leaves the connections already established unchanged.
+    /// This applies them by disconnecting and reconnecting the endpoint of a connected socket,
+    /// or by unbinding and binding again that of a bound socket, whose peers then reconnect.
+    /// Messages queued for the dropped connections are lost.
+    ///
+    /// Only the endpoints given to the builder of the socket are reset: those bound or connected
+    /// through `as_raw_socket` are left as they are. A socket not built by a `SocketBuilder`
+    /// fails with `EINVAL`.
+    pub async fn reset_connections(&mut self) -> Result<&mut Self, zmq::Error> {
+        crate::socket::reset_connections(&self.inner.socket).await?;
+        Ok(self)
+    }
+}
+
+#[cfg(all(feature = "tracing", debug_assertions))]
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Drop for Request<I, T> {
+    fn drop(&mut self) {
+        if !self.received.load(Ordering::Relaxed) {
+            tracing::warn!(
+                "REQ socket dropped after sending a request but before receiving the reply"
+            );
+        }
+    }
 }
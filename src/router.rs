@@ -6,11 +6,43 @@
 //! A router socket must be paired with a [`dealer`], [`request`] or another
 //! router socket.
 //!
+//! Receiving is cancel safe, see [cancellation](../index.html#cancellation).
+//!
+//! # Peer tracking
+//!
+//! The router remembers the identity of every peer it receives a message from, for
+//! [`Router::broadcast`]. A broker serving churning clients can bound that set with
+//! [`Router::set_peer_limit`], which evicts the least recently seen peers, and
+//! [`Router::set_peer_idle_timeout`], which forgets the peers that have been quiet for too long
+//! as the router receives. Peers are kept in the order they were last seen, so both only look at
+//! the peers they forget.
+//!
+//! The router also forgets a peer once its connection closes. It starts the monitor of the
+//! socket on its first receive for that, and matches the `DISCONNECTED` events to the connection
+//! each peer was last seen on. Connections without a file descriptor, such as `inproc` ones,
+//! are not reported, and neither are the connections of a router converted from a
+//! `zmq::Socket`, which can't be monitored.
+//!
 //! # Example
 //!
 //! ```no_run
+//! use async_zmq::Result;
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let mut zmq = async_zmq::router("tcp://127.0.0.1:5555")?.bind()?;
+//!
+//!     // Echo every message back to the peer it came from.
+//!     loop {
+//!         let msg = zmq.recv().await?;
+//!         zmq.send(msg.into()).await?;
+//!     }
+//! }
 //! ```
 //!
+//! [`Router::broadcast`]: struct.Router.html#method.broadcast
+//! [`Router::set_peer_limit`]: struct.Router.html#method.set_peer_limit
+//! [`Router::set_peer_idle_timeout`]: struct.Router.html#method.set_peer_idle_timeout
 //! [`dealer`]: ../dealer/index.html
 //! [`request`]: ../request/index.html
 //! [`router`]: fn.router.html
@@ -19,30 +51,442 @@
 //! [`Stream`]: ../trait.Stream.html
 //! [`StreamExt`]: ../trait.StreamExt.html
 
+use std::collections::BTreeMap;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::future::poll_fn;
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Broker, Multipart, MultipartIter, SocketBuilder},
+    endpoint::Endpoint,
+    monitor::MonitorEvent,
+    reactor::{AsRawSocket, SourceCounts},
+    socket::{
+        close_socket, set_identity, set_metadata, Broker, FromParts, Multipart, MultipartIter,
+        SendQueue, SocketBuilder, SocketParts,
+    },
     RecvError, SendError, Sink, SocketError, Stream,
 };
-use zmq::{Message, SocketType};
+use zmq::{Message, SocketEvent, SocketType};
 
 /// Create a ZMQ socket with ROUTER type
-pub fn router<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Router<I, T>>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn router<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Router<I, T>>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::ROUTER, endpoint))
 }
 
+/// Create a ZMQ socket with ROUTER type, built with `context`
+///
+/// This is [`router`](fn.router.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn router_in<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Router<I, T>>, SocketError> {
+    Ok(router(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with ROUTER type
-pub struct Router<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Broker<I, T>);
+pub struct Router<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
+    pub(crate) inner: Broker<I, T>,
+    peers: PeerTracker,
+}
+
+/// Identities of the peers the router has received messages from, with when they were last seen.
+struct PeerTracker {
+    /// When each peer was last seen, along with its key in `by_age`.
+    seen: BTreeMap<Vec<u8>, (u64, Instant)>,
+    /// The peers from the least to the most recently seen, keyed by a counter bumped every time
+    /// one is seen.
+    by_age: BTreeMap<u64, Vec<u8>>,
+    next_age: u64,
+    limit: Option<usize>,
+    idle_timeout: Option<Duration>,
+    /// The peer last seen on each connection, by file descriptor.
+    by_fd: BTreeMap<u32, Vec<u8>>,
+    /// Connections the monitor reported as closed since they were last handled, or `None` until
+    /// the router watches them.
+    disconnected: Option<Arc<Mutex<Vec<u32>>>>,
+    /// Connection of each message received, once the router watches disconnections.
+    sources: Option<Arc<SourceCounts>>,
+}
+
+impl PeerTracker {
+    fn new() -> Self {
+        Self {
+            seen: BTreeMap::new(),
+            by_age: BTreeMap::new(),
+            next_age: 0,
+            limit: None,
+            idle_timeout: None,
+            by_fd: BTreeMap::new(),
+            disconnected: None,
+            sources: None,
+        }
+    }
+
+    /// Track the sender of the message just received.
+    fn touch(&mut self, identity: &[u8]) {
+        if let Some(fd) = self.sources.as_ref().and_then(|sources| sources.last()) {
+            self.by_fd.insert(fd, identity.to_vec());
+        }
+        let age = self.next_age;
+        self.next_age += 1;
+        match self.seen.get_mut(identity) {
+            Some(seen) => {
+                let identity = self
+                    .by_age
+                    .remove(&seen.0)
+                    .unwrap_or_else(|| identity.to_vec());
+                *seen = (age, Instant::now());
+                self.by_age.insert(age, identity);
+            }
+            None => {
+                self.seen.insert(identity.to_vec(), (age, Instant::now()));
+                self.by_age.insert(age, identity.to_vec());
+                self.enforce_limit();
+            }
+        }
+    }
+
+    /// Evict the least recently seen peers until the set fits the limit.
+    fn enforce_limit(&mut self) {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        while self.seen.len() > limit {
+            match self.by_age.pop_first() {
+                Some((_, identity)) => self.seen.remove(&identity),
+                None => break,
+            };
+        }
+    }
+
+    /// Forget the peers whose connection closed.
+    fn forget_disconnected(&mut self) {
+        let closed = match &self.disconnected {
+            Some(disconnected) => std::mem::take(&mut *disconnected.lock().unwrap()),
+            None => return,
+        };
+        for fd in closed {
+            if let Some(identity) = self.by_fd.remove(&fd) {
+                if let Some((age, _)) = self.seen.remove(&identity) {
+                    self.by_age.remove(&age);
+                }
+            }
+        }
+    }
+
+    /// Forget the peers whose connection closed and those not seen for the idle timeout.
+    fn sweep(&mut self) {
+        self.forget_disconnected();
+        let idle_timeout = match self.idle_timeout {
+            Some(idle_timeout) => idle_timeout,
+            None => return,
+        };
+        while let Some(entry) = self.by_age.first_entry() {
+            match self.seen.get(entry.get()) {
+                Some((_, seen)) if seen.elapsed() < idle_timeout => break,
+                _ => {
+                    let identity = entry.remove();
+                    self.seen.remove(&identity);
+                }
+            }
+        }
+    }
+}
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Router<I, T> {
+    /// Send `body` to every peer the router has received a message from.
+    ///
+    /// Peers are only known once they have sent something; DEALER peers can announce themselves
+    /// on connect with `set_probe_router` on their raw socket. Peers whose connection closed are
+    /// skipped, see [peer tracking](index.html#peer-tracking). This is best-effort: a peer which
+    /// disconnected too recently for the monitor to report it silently misses the message, and a
+    /// peer which connected but hasn't sent anything yet is not reached.
+    ///
+    /// Messages waiting in the send queue are flushed first, so ordering is preserved.
+    pub async fn broadcast(&mut self, body: Multipart) -> Result<(), SendError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+        self.peers.forget_disconnected();
+
+        for identity in self.peers.seen.keys() {
+            let frames = std::iter::once(Message::from(&identity[..]))
+                .chain(body.iter().map(|frame| Message::from(&frame[..])));
+            let mut msg = MultipartIter(frames);
+            poll_fn(|cx| self.inner.socket.send(cx, &mut msg)).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a multipart message, going through the send queue of the `Sink`.
+    ///
+    /// The first frame must be the routing id of the peer to reach, as received in the first
+    /// frame of its messages. A message to an unknown peer is silently dropped, unless
+    /// [`set_router_mandatory`](#method.set_router_mandatory) is enabled.
+    pub async fn send(&mut self, msg: MultipartIter<I, T>) -> Result<(), SendError> {
+        futures::SinkExt::send(self, msg).await
+    }
+
+    /// Receive a multipart message, whose first frame is the routing id of the peer that sent it.
+    ///
+    /// The same as the next item of the `Stream`, the peer is tracked as well.
+    pub async fn recv(&mut self) -> Result<Multipart, RecvError> {
+        self.watch_disconnects();
+        self.peers.sweep();
+        let msg = poll_fn(|cx| self.inner.socket.recv(cx)).await?;
+        if let Some(identity) = msg.first() {
+            self.peers.touch(identity);
+        }
+        Ok(msg)
+    }
+
+    /// Start forgetting the peers whose connection closes, unless done already.
+    ///
+    /// A router which can't be monitored only forgets peers through the limit and idle timeout.
+    fn watch_disconnects(&mut self) {
+        if self.peers.disconnected.is_some() {
+            return;
+        }
+        let disconnected = Arc::new(Mutex::new(Vec::new()));
+        let shared = disconnected.clone();
+        let socket = &self.inner.socket;
+        let on_disconnect = move |event: &MonitorEvent| shared.lock().unwrap().push(event.value);
+        let watched = socket.events().subscribe(
+            socket.as_socket(),
+            &[SocketEvent::DISCONNECTED],
+            on_disconnect,
+        );
+        if watched.is_ok() {
+            let sources = Arc::new(SourceCounts::default());
+            self.inner.socket.attach_guard(sources.clone());
+            self.peers.sources = Some(sources);
+        }
+        self.peers.disconnected = Some(disconnected);
+    }
+
+    /// Identities of the peers the router has received messages from.
+    pub fn peers(&self) -> impl Iterator<Item = &[u8]> {
+        self.peers.seen.keys().map(Vec::as_slice)
+    }
+
+    /// Number of peer identities the router currently tracks.
+    pub fn tracked_peers(&self) -> usize {
+        self.peers.seen.len()
+    }
+
+    /// Track at most `limit` peers, evicting the least recently seen ones beyond that.
+    ///
+    /// An evicted peer is tracked again as soon as it sends another message.
+    pub fn set_peer_limit(&mut self, limit: usize) -> &mut Self {
+        self.peers.limit = Some(limit);
+        self.peers.enforce_limit();
+        self
+    }
+
+    /// Forget the peers not seen for `timeout`.
+    ///
+    /// Idle peers are forgotten as the router receives, before it tracks the sender of the
+    /// message, so they stay tracked until the router is polled again.
+    pub fn set_peer_idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.peers.idle_timeout = Some(timeout);
+        self
+    }
+
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
-        self.0.socket.as_socket()
+        self.inner.socket.as_socket()
+    }
+
+    /// Assign `id` as the routing id of the peer reached by the next connection.
+    ///
+    /// This only affects the next `connect` made through [`as_raw_socket`](#method.as_raw_socket),
+    /// and is cleared once that connection is made. Messages can be routed to the peer using
+    /// `id` right away, without waiting for it to send anything first.
+    pub fn set_connect_routing_id(&mut self, id: &[u8]) -> Result<&mut Self, zmq::Error> {
+        let option = zmq_sys::ZMQ_CONNECT_ROUTING_ID as i32;
+        self.inner.socket.set_raw_option(option, id)?;
+        Ok(self)
+    }
+
+    /// Report messages to unknown peers as errors instead of dropping them silently.
+    ///
+    /// Once enabled, sending to a routing id no peer has fails with `EHOSTUNREACH`, and sending
+    /// to a peer whose queue is full fails with `EAGAIN` instead of blocking.
+    pub fn set_router_mandatory(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_router_mandatory(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the CURVE server flag on the socket.
+    ///
+    /// See [switching roles](../curve/index.html#switching-roles) for a socket which was a
+    /// client before.
+    pub fn set_curve_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the CURVE public key on the socket.
+    pub fn set_curve_publickey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_publickey(key)?;
+        Ok(self)
+    }
+
+    /// Set the CURVE secret key on the socket.
+    pub fn set_curve_secretkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_secretkey(key)?;
+        Ok(self)
+    }
+
+    /// Set the CURVE server key on the socket.
+    ///
+    /// This makes the socket a CURVE client, resetting the server flag if it was set.
+    pub fn set_curve_serverkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_curve_serverkey(key)?;
+        Ok(self)
+    }
+
+    /// Set the ZAP domain for authentication.
+    pub fn set_zap_domain(&mut self, domain: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_zap_domain(domain)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN server flag on the socket, so that it authenticates its peers with the
+    /// username and password they present, through the ZAP handler of the context.
+    pub fn set_plain_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN username presented by the socket, making it a PLAIN client.
+    pub fn set_plain_username(&mut self, username: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_username(Some(username))?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN password presented by the socket, making it a PLAIN client.
+    pub fn set_plain_password(&mut self, password: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_password(Some(password))?;
+        Ok(self)
+    }
+
+    /// Set the routing id this socket presents to its peers, which a ROUTER peer sees as the
+    /// first frame of the messages from this socket.
+    ///
+    /// The routing id is exchanged when a connection is made, so it only applies to the
+    /// connections made afterwards. This fails with `EINVAL` on a socket its builder connected,
+    /// where it would have no effect: set the routing id before connecting with
+    /// [`SocketBuilder::with_identity`] instead.
+    ///
+    /// [`SocketBuilder::with_identity`]: ../struct.SocketBuilder.html#method.with_identity
+    pub fn set_identity(&mut self, id: &[u8]) -> Result<&mut Self, zmq::Error> {
+        set_identity(&self.inner.socket, id)?;
+        Ok(self)
+    }
+
+    /// Get the routing id this socket presents to its peers, empty unless one was set.
+    pub fn get_identity(&self) -> Result<Vec<u8>, zmq::Error> {
+        self.as_raw_socket().get_identity()
+    }
+
+    /// Add a metadata property, which ØMQ sends to the peers of the socket when connecting.
+    ///
+    /// ØMQ requires the `key` to start with `X-`. Peers read the property from the messages
+    /// of the connection with `Message::gets`. The metadata is exchanged when a connection is
+    /// made, so this fails with `EINVAL` on a socket its builder connected: add the property
+    /// before connecting with [`SocketBuilder::with_metadata`] instead. Like it, this needs a
+    /// libzmq built with the draft API.
+    ///
+    /// [`SocketBuilder::with_metadata`]: ../struct.SocketBuilder.html#method.with_metadata
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<&mut Self, zmq::Error> {
+        set_metadata(&self.inner.socket, key, value)?;
+        Ok(self)
+    }
+
+    /// Set the send high water mark for the socket.
+    /// The high water mark is a hard limit on the maximum number of outstanding messages
+    /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards.
+    pub fn set_send_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_sndhwm(value)?;
+        Ok(self)
+    }
+
+    /// Get the send high water mark for the socket.
+    pub fn get_send_hwm(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_sndhwm()
+    }
+
+    /// Set the receive high water mark for the socket.
+    /// The high water mark is a hard limit on the maximum number of outstanding messages
+    /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards.
+    pub fn set_receive_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_rcvhwm(value)?;
+        Ok(self)
+    }
+
+    /// Get the receive high water mark for the socket.
+    pub fn get_receive_hwm(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_rcvhwm()
+    }
+
+    /// Set the largest message in bytes the socket accepts from its peers, or `-1`, the
+    /// default, for no limit.
+    /// This protects against peers sending messages too large to hold in memory. ØMQ doesn't
+    /// deliver an oversized message nor report it to the receiver: it drops the connection of
+    /// the peer which sent it, which shows as a `DISCONNECTED` event on the
+    /// [monitor](../trait.AsRawSocket.html#method.monitor) of the socket. The peer may then
+    /// reconnect. It only applies to connections established afterwards, and not to `inproc`
+    /// connections.
+    pub fn set_max_message_size(&mut self, value: i64) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_maxmsgsize(value)?;
+        Ok(self)
+    }
+
+    /// Get the largest message in bytes the socket accepts from its peers.
+    pub fn get_max_message_size(&self) -> Result<i64, zmq::Error> {
+        self.as_raw_socket().get_maxmsgsize()
+    }
+
+    /// Close the socket gracefully, completing once ØMQ is done with it.
+    ///
+    /// Messages waiting in the send queue are flushed first. ØMQ then keeps delivering the
+    /// messages it holds for the linger period of the socket, see `set_linger` on the raw
+    /// socket, and the returned future completes once they are all delivered or the linger
+    /// period is over, when ØMQ destroys the socket. With the default infinite linger period,
+    /// this waits until a peer receives the messages, so set a finite one to bound the wait.
+    pub async fn close(mut self) -> Result<(), SocketError> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await?;
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Number of messages dropped so far because the send queue was full.
+    ///
+    /// This stays at zero unless the socket was built with a dropping [`SendOverflow`] policy.
+    ///
+    /// [`SendOverflow`]: ../enum.SendOverflow.html
+    pub fn dropped_messages(&self) -> u64 {
+        self.inner.queue.dropped()
     }
 }
 
@@ -50,23 +494,23 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>>
     type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_ready(Pin::new(&mut self.get_mut().0), cx)
+        Sink::poll_ready(Pin::new(&mut self.get_mut().inner), cx)
             .map(|result| result.map_err(Into::into))
     }
 
     fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        Pin::new(&mut self.get_mut().0)
+        Pin::new(&mut self.get_mut().inner)
             .start_send(item)
             .map_err(Into::into)
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_flush(Pin::new(&mut self.get_mut().0), cx)
+        Sink::poll_flush(Pin::new(&mut self.get_mut().inner), cx)
             .map(|result| result.map_err(Into::into))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_close(Pin::new(&mut self.get_mut().0), cx)
+        Sink::poll_close(Pin::new(&mut self.get_mut().inner), cx)
             .map(|result| result.map_err(Into::into))
     }
 }
@@ -75,17 +519,35 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for Router<I, T> {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().0)
-            .poll_next(cx)
-            .map(|poll| poll.map(|result| result.map_err(Into::into)))
+        let this = self.get_mut();
+        this.watch_disconnects();
+        this.peers.sweep();
+        let msg = futures::ready!(Pin::new(&mut this.inner).poll_next(cx));
+        if let Some(Ok(msg)) = &msg {
+            if let Some(identity) = msg.first() {
+                this.peers.touch(identity);
+            }
+        }
+        Poll::Ready(msg.map(|result| result.map_err(Into::into)))
     }
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Router<I, T> {
     fn from(socket: zmq::Socket) -> Self {
-        Self(Broker {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
-        })
+        Self::from_parts(socket.into())
+    }
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> FromParts for Router<I, T> {
+    const SEND_QUEUE: bool = true;
+
+    fn from_parts(parts: SocketParts) -> Self {
+        Self {
+            inner: Broker {
+                socket: parts.socket,
+                queue: SendQueue::new(parts.send_queue),
+            },
+            peers: PeerTracker::new(),
+        }
     }
 }
@@ -0,0 +1,202 @@
+//! Request-response client over a DEALER socket
+//!
+//! Use the [`rpc_client`] function to instantiate an [`RpcClient`]. Each call is tagged with a
+//! unique id frame, so several calls can be in flight at once on the same socket and every
+//! reply is matched with the call it answers, whatever order replies arrive in.
+//!
+//! A request is sent as the id frame followed by the frames of the request. The server, usually
+//! a [`router`] socket, must reply with the same id frame followed by the frames of the reply;
+//! a ROUTER receives the id right after the routing id of the client.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use async_zmq::{Multipart, Result};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let client = async_zmq::rpc_client("tcp://127.0.0.1:5555")?.connect()?;
+//!
+//!     let reply = client
+//!         .call_timeout(Multipart::of(["get", "temperature"]), Duration::from_secs(1))
+//!         .await?;
+//!     println!("{:?}", reply);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`rpc_client`]: fn.rpc_client.html
+//! [`RpcClient`]: struct.RpcClient.html
+//! [`router`]: ../router/index.html
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::iter::once;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::channel::oneshot;
+use futures::future::{poll_fn, select, Either};
+use futures::FutureExt;
+use zmq::{Message, SocketType};
+
+use crate::{
+    endpoint::Endpoint,
+    reactor::{sleep, AsRawSocket, ZmqSocket},
+    socket::{FromParts, Multipart, MultipartIter, SocketBuilder, SocketParts},
+    RpcError, SocketError,
+};
+
+/// Create a ZMQ socket with DEALER type, wrapped as an RPC client
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn rpc_client<'a>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, RpcClient>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
+    Ok(SocketBuilder::new(SocketType::DEALER, endpoint))
+}
+
+/// Create a ZMQ socket with DEALER type, wrapped as an RPC client, built with `context`
+///
+/// This is [`rpc_client`](fn.rpc_client.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn rpc_client_in<'a>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, RpcClient>, SocketError> {
+    Ok(rpc_client(endpoint)?.with_context(context))
+}
+
+/// DEALER socket matching replies to concurrent requests by id
+pub struct RpcClient {
+    pub(crate) socket: ZmqSocket,
+    next_id: AtomicU64,
+    /// Calls waiting for their reply, by id.
+    pending: Mutex<HashMap<u64, oneshot::Sender<Multipart>>>,
+    orphaned: AtomicU64,
+}
+
+impl From<zmq::Socket> for RpcClient {
+    fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl FromParts for RpcClient {
+    const SEND_QUEUE: bool = false;
+
+    fn from_parts(parts: SocketParts) -> Self {
+        Self {
+            socket: parts.socket,
+            next_id: AtomicU64::new(0),
+            pending: Mutex::new(HashMap::new()),
+            orphaned: AtomicU64::new(0),
+        }
+    }
+}
+
+impl RpcClient {
+    /// Send `request` and wait for its reply, which is returned without the id frame.
+    ///
+    /// Calls can be awaited concurrently, for example with `join_all`: whichever call is polled
+    /// receives the replies and hands each one to the call it answers.
+    pub async fn call(&self, request: Multipart) -> Result<Multipart, RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, mut receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        let _pending = PendingCall { client: self, id };
+
+        let frames = once(Message::from(&id.to_be_bytes()[..])).chain(request);
+        let mut msg = MultipartIter(frames);
+        poll_fn(|cx| self.socket.send(cx, &mut msg))
+            .await
+            .map_err(|error| RpcError::Send(error.into()))?;
+
+        poll_fn(|cx| self.poll_reply(cx, &mut receiver)).await
+    }
+
+    /// Send `request` and wait at most `timeout` for its reply.
+    ///
+    /// Fails with [`RpcError::Timeout`] once `timeout` has elapsed. A reply arriving after that
+    /// is discarded and counted by [`orphaned_replies`](#method.orphaned_replies).
+    ///
+    /// [`RpcError::Timeout`]: ../enum.RpcError.html#variant.Timeout
+    pub async fn call_timeout(
+        &self,
+        request: Multipart,
+        timeout: Duration,
+    ) -> Result<Multipart, RpcError> {
+        let call = Box::pin(self.call(request));
+        match select(call, sleep(timeout)).await {
+            Either::Left((reply, _)) => reply,
+            Either::Right(_) => Err(RpcError::Timeout),
+        }
+    }
+
+    /// Number of calls waiting for their reply.
+    pub fn in_flight(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Number of replies discarded so far because no call was waiting for them, either because
+    /// the call timed out or was dropped, or because the id frame was missing or unknown.
+    pub fn orphaned_replies(&self) -> u64 {
+        self.orphaned.load(Ordering::Relaxed)
+    }
+
+    /// Represent as `Socket` from zmq crate in case you want to call its methods.
+    pub fn as_raw_socket(&self) -> &zmq::Socket {
+        self.socket.as_socket()
+    }
+
+    fn poll_reply(
+        &self,
+        cx: &mut Context<'_>,
+        receiver: &mut oneshot::Receiver<Multipart>,
+    ) -> Poll<Result<Multipart, RpcError>> {
+        loop {
+            if let Poll::Ready(Ok(reply)) = receiver.poll_unpin(cx) {
+                return Poll::Ready(Ok(reply));
+            }
+            match futures::ready!(self.socket.recv(cx)) {
+                Ok(reply) => self.dispatch(reply),
+                Err(error) => return Poll::Ready(Err(RpcError::Recv(error.into()))),
+            }
+        }
+    }
+
+    /// Hand `reply` to the call waiting for it, if any.
+    fn dispatch(&self, mut reply: Multipart) {
+        let id = reply
+            .frame_bytes(0)
+            .and_then(|frame| frame.try_into().ok())
+            .map(u64::from_be_bytes);
+        let waiting = id.and_then(|id| self.pending.lock().unwrap().remove(&id));
+        match waiting {
+            Some(sender) => {
+                reply.remove(0);
+                let _ = sender.send(reply);
+            }
+            None => {
+                self.orphaned.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Forget a call once it completes, times out or is dropped.
+struct PendingCall<'a> {
+    client: &'a RpcClient,
+    id: u64,
+}
+
+impl Drop for PendingCall<'_> {
+    fn drop(&mut self) {
+        self.client.pending.lock().unwrap().remove(&self.id);
+    }
+}
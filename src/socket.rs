@@ -1,8 +1,26 @@
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
 use std::convert::Into;
+use std::fmt;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, ToSocketAddrs};
+use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use crate::{reactor::ZmqSocket, Message, Sink, Stream};
+use crate::{
+    context::{default_context, InprocClaim},
+    curve::CurveKeyPair,
+    endpoint::Endpoint,
+    monitor::EventHub,
+    reactor::{sleep, spawn_blocking, AsRawSocket, ZmqSocket},
+    sys::RawSocket,
+    Message, Sink, SocketError, Stream,
+};
+use futures::future::{poll_fn, select, Either};
 use futures::ready;
 use zmq::Error;
 
@@ -23,104 +41,1129 @@ impl<T: Into<Message>> From<Vec<T>> for MultipartIter<std::vec::IntoIter<T>, T>
     }
 }
 
+impl<T: Into<Message>, const N: usize> From<[T; N]>
+    for MultipartIter<std::array::IntoIter<T, N>, T>
+{
+    fn from(array: [T; N]) -> Self {
+        MultipartIter(array.into_iter())
+    }
+}
+
 impl<T: Into<Message>> From<T> for MultipartIter<std::vec::IntoIter<T>, T> {
     fn from(m: T) -> Self {
         MultipartIter(vec![m].into_iter())
     }
 }
 
-/// Alias type of Multipart.
+/// Create a single frame [`Message`] from anything convertible into one.
+///
+/// This is shorthand for `Message::from`, to keep code building many frames terse.
 ///
-/// This is the type what we receive from zmq socket via [`Stream`]. Users can choose
-/// the type of queue they want when sending messages as long as it can turn into iterator.
-/// For more information, see [`MultipartIter`](struct.MultipartIter.html)
+/// ```
+/// use async_zmq::{msg, Multipart};
+///
+/// let request: Multipart = vec![msg("GET"), msg(&b"/index.html"[..])].into();
+/// assert_eq!(request[0].as_str(), Some("GET"));
+/// ```
+///
+/// [`Message`]: struct.Message.html
+pub fn msg(frame: impl Into<Message>) -> Message {
+    frame.into()
+}
+
+/// Multipart message.
+///
+/// This is the type what we receive from zmq socket via [`Stream`]. It's a thin wrapper around
+/// `Vec<Message>` and dereferences to it, so frames can be indexed and iterated directly. Users
+/// can choose the type of queue they want when sending messages as long as it can turn into
+/// iterator. For more information, see [`MultipartIter`](struct.MultipartIter.html)
+///
+/// Its `Debug` output shows the length of every frame with only a short preview of its content,
+/// so large messages can be logged safely.
 ///
 /// [`Stream`]: trait.Stream.html
-pub type Multipart = Vec<Message>;
+#[derive(Default, PartialEq, Eq)]
+pub struct Multipart(Vec<Message>);
+
+/// Number of bytes of each frame shown by the `Debug` output of `Multipart`.
+const FRAME_PREVIEW_LEN: usize = 32;
+
+impl Multipart {
+    /// Build a multipart message out of frames of any type convertible into a [`Message`].
+    ///
+    /// ```
+    /// use async_zmq::Multipart;
+    ///
+    /// let update = Multipart::of(["weather", "paris", "21"]);
+    /// assert_eq!(update.len(), 3);
+    /// assert_eq!(update[1].as_str(), Some("paris"));
+    /// ```
+    ///
+    /// [`Message`]: struct.Message.html
+    pub fn of<T: Into<Message>>(frames: impl IntoIterator<Item = T>) -> Self {
+        Self(frames.into_iter().map(Into::into).collect())
+    }
+
+    /// Borrow the content of the frame at `idx` straight from its `Message` buffer, without
+    /// copying. This is the preferred way to inspect frames; `to_vec` and friends allocate.
+    pub fn frame_bytes(&self, idx: usize) -> Option<&[u8]> {
+        self.0.get(idx).map(|frame| &frame[..])
+    }
+
+    /// Check whether the frame at `idx` is empty. Returns `false` if there is no such frame.
+    pub fn frame_is_empty(&self, idx: usize) -> bool {
+        self.0.get(idx).is_some_and(|frame| frame.is_empty())
+    }
+
+    /// Check whether the frame at `idx` starts with `prefix`, without copying it. Returns `false`
+    /// if there is no such frame.
+    pub fn frame_starts_with(&self, idx: usize, prefix: &[u8]) -> bool {
+        self.0.get(idx).is_some_and(|frame| frame.starts_with(prefix))
+    }
+
+    /// Check whether the frame at `idx` holds exactly `bytes`, without copying it. Returns
+    /// `false` if there is no such frame.
+    pub fn frame_eq(&self, idx: usize, bytes: &[u8]) -> bool {
+        self.frame_bytes(idx) == Some(bytes)
+    }
+
+    /// Check whether the frame at `idx` holds exactly the UTF-8 bytes of `text`. Returns `false`
+    /// if there is no such frame; a frame that isn't valid UTF-8 is simply not equal.
+    pub fn frame_eq_str(&self, idx: usize, text: &str) -> bool {
+        self.frame_eq(idx, text.as_bytes())
+    }
+
+    /// Remove the frame at `idx` and return it, shifting the frames after it to the left.
+    /// Returns `None`, leaving the message unchanged, if there is no such frame.
+    ///
+    /// ```
+    /// use async_zmq::Multipart;
+    ///
+    /// let mut msg = Multipart::of(["header", "topic", "body"]);
+    /// assert_eq!(msg.remove(0).unwrap().as_str(), Some("header"));
+    /// assert_eq!(msg[0].as_str(), Some("topic"));
+    /// assert!(msg.remove(2).is_none());
+    /// ```
+    pub fn remove(&mut self, idx: usize) -> Option<Message> {
+        if idx < self.0.len() {
+            Some(self.0.remove(idx))
+        } else {
+            None
+        }
+    }
+
+    /// Insert `frame` at `idx`, shifting the frames from `idx` onwards to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is greater than the number of frames.
+    pub fn insert(&mut self, idx: usize, frame: impl Into<Message>) {
+        self.0.insert(idx, frame.into());
+    }
+
+    /// Replace the frame at `idx` with `frame`, returning the frame it replaces. Returns `None`,
+    /// leaving the message unchanged, if there is no such frame.
+    pub fn replace(&mut self, idx: usize, frame: impl Into<Message>) -> Option<Message> {
+        self.0
+            .get_mut(idx)
+            .map(|slot| std::mem::replace(slot, frame.into()))
+    }
+
+    /// Find the first empty frame, which is the delimiter between the routing envelope and the
+    /// body of messages coming from REQ/REP peers.
+    pub fn find_delimiter(&self) -> Option<usize> {
+        self.0.iter().position(|frame| frame.is_empty())
+    }
+
+    /// Render every frame in full, one per line, prefixed by its index. Frames holding valid
+    /// UTF-8 are shown as quoted text, others as hex. This is the `Display` output of the
+    /// message; unlike `Debug`, nothing is truncated.
+    ///
+    /// ```
+    /// use async_zmq::Multipart;
+    ///
+    /// let msg = Multipart::of([&b"topic"[..], &[0xff, 0x00][..]]);
+    /// assert_eq!(msg.dump(), "[0] \"topic\"\n[1] 0xff00");
+    /// ```
+    pub fn dump(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Multipart({} frames) ", self.0.len())?;
+        f.debug_list()
+            .entries(self.0.iter().map(|frame| FramePreview(frame)))
+            .finish()
+    }
+}
+
+impl fmt::Display for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, frame) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "[{}] ", idx)?;
+            match std::str::from_utf8(frame) {
+                Ok(text) => write!(f, "{:?}", text)?,
+                Err(_) => {
+                    f.write_str("0x")?;
+                    for byte in frame.iter() {
+                        write!(f, "{:02x}", byte)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct FramePreview<'a>(&'a [u8]);
+
+impl fmt::Debug for FramePreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let preview = &self.0[..self.0.len().min(FRAME_PREVIEW_LEN)];
+        let ellipsis = if self.0.len() > preview.len() {
+            "..."
+        } else {
+            ""
+        };
+        write!(f, "{} bytes ", self.0.len())?;
+
+        // A preview cut in the middle of a UTF-8 character is still shown as text.
+        let text = match std::str::from_utf8(preview) {
+            Ok(text) => Some(text),
+            Err(e) if e.error_len().is_none() && !ellipsis.is_empty() => {
+                std::str::from_utf8(&preview[..e.valid_up_to()]).ok()
+            }
+            Err(_) => None,
+        };
+        match text {
+            Some(text) => write!(f, "{:?}{}", text, ellipsis),
+            None => {
+                f.write_str("0x")?;
+                for byte in preview {
+                    write!(f, "{:02x}", byte)?;
+                }
+                f.write_str(ellipsis)
+            }
+        }
+    }
+}
+
+impl Deref for Multipart {
+    type Target = Vec<Message>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Multipart {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Vec<Message>> for Multipart {
+    fn from(frames: Vec<Message>) -> Self {
+        Self(frames)
+    }
+}
+
+impl From<Multipart> for Vec<Message> {
+    fn from(multipart: Multipart) -> Self {
+        multipart.0
+    }
+}
+
+impl FromIterator<Message> for Multipart {
+    fn from_iter<I: IntoIterator<Item = Message>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Multipart {
+    type Item = Message;
+    type IntoIter = std::vec::IntoIter<Message>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Multipart {
+    type Item = &'a Message;
+    type IntoIter = std::slice::Iter<'a, Message>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<Multipart> for MultipartIter<std::vec::IntoIter<Message>, Message> {
+    fn from(multipart: Multipart) -> Self {
+        MultipartIter(multipart.0.into_iter())
+    }
+}
+
+/// Builder of a [`Multipart`] message, pushing one frame at a time.
+///
+/// This reads better than a `Vec<Message>` when writing the routing envelope of ROUTER/DEALER
+/// messages, where frames of different types are mixed with empty delimiters.
+///
+/// ```
+/// use async_zmq::MultipartBuilder;
+///
+/// let reply = MultipartBuilder::new()
+///     .push_bytes(b"client-1")
+///     .push_empty()
+///     .push("pong")
+///     .build();
+/// assert_eq!(reply.len(), 3);
+/// assert_eq!(reply.find_delimiter(), Some(1));
+/// ```
+///
+/// [`Multipart`]: struct.Multipart.html
+#[derive(Default)]
+pub struct MultipartBuilder(Vec<Message>);
+
+impl MultipartBuilder {
+    /// Create a builder with no frames.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a frame of any type convertible into a [`Message`].
+    ///
+    /// [`Message`]: struct.Message.html
+    pub fn push<T: Into<Message>>(mut self, frame: T) -> Self {
+        self.0.push(frame.into());
+        self
+    }
+
+    /// Append a frame holding a copy of `bytes`.
+    pub fn push_bytes(self, bytes: &[u8]) -> Self {
+        self.push(bytes)
+    }
+
+    /// Append an empty frame, the delimiter between the routing envelope and the body.
+    pub fn push_empty(mut self) -> Self {
+        self.0.push(Message::new());
+        self
+    }
+
+    /// Finish the message.
+    pub fn build(self) -> Multipart {
+        Multipart(self.0)
+    }
+}
+
+/// Socket option applied by [`SocketBuilder`] before binding or connecting.
+type SocketOption<'a> = Box<dyn FnOnce(&mut zmq::Socket) -> Result<(), Error> + 'a>;
 
 /// ZMQ socket builder. It lets user to either bind or connect the socket of their choice.
+///
+/// The `with_*` methods take and return the builder by value, so a socket can be configured in
+/// a single expression ending with [`bind`](#method.bind) or [`connect`](#method.connect).
+/// Options are applied in order when the socket is created, and the first one failing is
+/// returned by the terminal call.
+///
+/// ```no_run
+/// use std::vec::IntoIter;
+///
+/// use async_zmq::{CurveKeyPair, Result};
+///
+/// fn main() -> Result<()> {
+///     let pair = CurveKeyPair::new()?;
+///     let publish = async_zmq::publish::<IntoIter<&str>, &str>("tcp://*:5556")?
+///         .with_curve_server(&pair)
+///         .with_zap_domain("global")
+///         .with_send_hwm(1000)
+///         .bind()?;
+///     # drop(publish);
+///     Ok(())
+/// }
+/// ```
 pub struct SocketBuilder<'a, T> {
-    pub(crate) context: Option<&'a zmq::Context>,
+    pub(crate) context: Option<crate::context::Context>,
     pub(crate) socket_type: zmq::SocketType,
-    pub(crate) endpoint: &'a str,
+    pub(crate) endpoint: Endpoint,
+    pub(crate) resolve_timeout: Option<Duration>,
+    send_queue: Option<SendQueueConfig>,
+    options: Vec<SocketOption<'a>>,
+    monitored: bool,
+    events: EventHub,
     _phantom: std::marker::PhantomData<T>,
 }
 
 impl<'a, T> SocketBuilder<'a, T>
 where
-    T: From<zmq::Socket>,
+    T: FromParts,
 {
-    pub(crate) fn new(socket_type: zmq::SocketType, endpoint: &'a str) -> Self {
+    pub(crate) fn new(socket_type: zmq::SocketType, endpoint: Endpoint) -> Self {
         Self {
             context: None,
             socket_type,
             endpoint,
+            resolve_timeout: None,
+            send_queue: None,
+            options: Vec::new(),
+            monitored: false,
+            events: EventHub::default(),
             _phantom: Default::default(),
         }
     }
 
     /// Get the zmq context to share with
-    pub fn get_context(&self) -> Option<&zmq::Context> {
-        self.context
+    pub fn get_context(&self) -> Option<&crate::context::Context> {
+        self.context.as_ref()
     }
 
     /// Create the zmq socket with given context
-    pub fn with_context(self, context: &'a zmq::Context) -> Self {
+    ///
+    /// Without it, the socket is created with the process-wide default context, see
+    /// [`Context::default`](context/struct.Context.html#impl-Default-for-Context).
+    ///
+    /// `context` is a [`Context`](context/struct.Context.html) or a `zmq::Context`. Every
+    /// `zmq::Context` passed here gets an inproc registry and open socket count of its own, so
+    /// sockets sharing one should share a `Context` converted from it instead.
+    pub fn with_context(self, context: impl Into<crate::context::Context>) -> Self {
         Self {
-            context: Some(context),
-            socket_type: self.socket_type,
-            endpoint: self.endpoint,
-            _phantom: Default::default(),
+            context: Some(context.into()),
+            ..self
+        }
+    }
+
+    /// Bound the time spent resolving the hostname of a `tcp://` endpoint in
+    /// [`connect_async`](#method.connect_async).
+    ///
+    /// The hostname is resolved before handing the endpoint to ØMQ, which then connects to the
+    /// resolved IP address. If resolution takes longer than `timeout`, `connect_async` fails with
+    /// `EAGAIN`; if the hostname cannot be resolved it fails with `EHOSTUNREACH`. Endpoints that
+    /// already use an IP address are passed through unchanged. The blocking [`connect`](#method.connect)
+    /// is not affected by this option.
+    pub fn resolve_timeout(self, timeout: Duration) -> Self {
+        Self {
+            resolve_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Randomize the reconnect interval of the socket between `base` and `base + jitter`.
+    ///
+    /// ØMQ reconnects after a fixed interval, so many peers losing the same server reconnect in
+    /// lockstep. Picking a random interval per socket spreads those reconnections over time.
+    pub fn with_reconnect_jitter(mut self, base: Duration, jitter: Duration) -> Self {
+        self.options.push(Box::new(move |socket| {
+            let jitter_ms = jitter.as_millis() as u64;
+            let offset = match jitter_ms {
+                0 => 0,
+                _ => RandomState::new().build_hasher().finish() % (jitter_ms + 1),
+            };
+            let interval = (base.as_millis() as u64 + offset).min(i32::MAX as u64);
+            socket.set_reconnect_ivl(interval as i32)
+        }));
+        self
+    }
+
+    /// Run `option` against the raw socket before it binds or connects.
+    ///
+    /// This is the escape hatch for options without a dedicated method, such as those ØMQ only
+    /// honours when set before connecting. A single closure can set several options at once.
+    /// Options set here, such as the identity, are in effect for the first connection, which
+    /// isn't the case of the setters of the built socket. Closures run in the order they were
+    /// added, and an error from any of them aborts the bind or connect.
+    pub fn configure<F>(mut self, option: F) -> Self
+    where
+        F: FnOnce(&zmq::Socket) -> Result<(), Error> + 'a,
+    {
+        self.options.push(Box::new(|socket| option(socket)));
+        self
+    }
+
+    /// Set the routing id the socket presents to its peers, before it binds or connects.
+    ///
+    /// A ROUTER peer sees `id` as the first frame of every message from this socket, instead of
+    /// a random id, and keeps seeing the same one after a reconnection. The routing id is
+    /// exchanged when a connection is made, so this is the way to set it on a connecting socket.
+    pub fn with_identity(self, id: &[u8]) -> Self {
+        let id = id.to_vec();
+        self.configure(move |socket| socket.set_identity(&id))
+    }
+
+    /// Enable or disable IPv6 before the socket binds or connects.
+    ///
+    /// ØMQ disables IPv6 by default, so binding or connecting to an IPv6 address such as
+    /// `tcp://[::1]:5555` fails unless this is enabled. With IPv6 enabled, a socket bound to
+    /// `tcp://*:5555` accepts both IPv4 and IPv6 connections.
+    pub fn with_ipv6(self, enabled: bool) -> Self {
+        self.configure(move |socket| socket.set_ipv6(enabled))
+    }
+
+    /// Add a metadata property, which ØMQ sends to the peers of the socket when connecting.
+    ///
+    /// ØMQ requires the `key` to start with `X-`. Peers read the property from the messages
+    /// of the connection with `Message::gets`. Call it once per property.
+    ///
+    /// `ZMQ_METADATA` is a draft option of libzmq 4.3: a libzmq built without
+    /// `ZMQ_BUILD_DRAFT_API` rejects it, and the bind or connect fails with `EINVAL`.
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        let (key, value) = (key.to_owned(), value.to_owned());
+        self.options.push(Box::new(move |socket| {
+            add_metadata(&RawSocket::of(socket), &key, &value)
+        }));
+        self
+    }
+
+    /// Act as a CURVE server with `pair`, before the socket binds or connects.
+    ///
+    /// Clients must know the public key of `pair` to connect, see
+    /// [`with_curve_client`](#method.with_curve_client).
+    pub fn with_curve_server(self, pair: &CurveKeyPair) -> Self {
+        let secret_key = pair.secret_key;
+        self.configure(move |socket| {
+            socket.set_curve_server(true)?;
+            socket.set_curve_secretkey(&secret_key)
+        })
+    }
+
+    /// Act as a CURVE client with `pair`, connecting to the server whose public key is
+    /// `server_key`, before the socket binds or connects.
+    pub fn with_curve_client(self, pair: &CurveKeyPair, server_key: &[u8]) -> Self {
+        let (public_key, secret_key) = (pair.public_key, pair.secret_key);
+        let server_key = server_key.to_vec();
+        self.configure(move |socket| {
+            socket.set_curve_publickey(&public_key)?;
+            socket.set_curve_secretkey(&secret_key)?;
+            socket.set_curve_serverkey(&server_key)
+        })
+    }
+
+    /// Set the ZAP domain of the socket, before it binds or connects.
+    ///
+    /// The domain is passed to the ZAP handler authenticating the peers, which lets a single
+    /// handler apply different policies to different sockets.
+    pub fn with_zap_domain(self, domain: &str) -> Self {
+        let domain = domain.to_owned();
+        self.configure(move |socket| socket.set_zap_domain(&domain))
+    }
+
+    /// Set the send high water mark, before the socket binds or connects.
+    ///
+    /// Unlike the `set_send_hwm` method of the built socket, this is in effect for the first
+    /// connections too.
+    pub fn with_send_hwm(self, value: i32) -> Self {
+        self.configure(move |socket| socket.set_sndhwm(value))
+    }
+
+    /// Set the receive high water mark, before the socket binds or connects.
+    ///
+    /// Unlike the `set_receive_hwm` method of the built socket, this is in effect for the first
+    /// connections too.
+    pub fn with_receive_hwm(self, value: i32) -> Self {
+        self.configure(move |socket| socket.set_rcvhwm(value))
+    }
+
+    /// Keep only the most recent message in the queues of the socket, before it binds or
+    /// connects.
+    ///
+    /// Conflation doesn't support multipart messages, which it would cut short. It applies to
+    /// the SUB, PULL, PUSH, DEALER and PAIR sockets.
+    pub fn with_conflate(self, enabled: bool) -> Self {
+        self.configure(move |socket| socket.set_conflate(enabled))
+    }
+
+    /// Start monitoring the socket before it binds or connects, rather than when its events are
+    /// first needed.
+    ///
+    /// The monitor is shared by everything watching the events of the socket, see
+    /// [`AsRawSocket::monitor`]. Starting it early lets the methods keeping track of the
+    /// connections, such as `Pull::source_stats`, see every one of them.
+    ///
+    /// [`AsRawSocket::monitor`]: trait.AsRawSocket.html#method.monitor
+    pub fn with_monitor(self) -> Self {
+        Self {
+            monitored: true,
+            ..self
+        }
+    }
+
+    /// Set the ØMQ option numbered `option` to the raw bytes `value` before the socket binds or
+    /// connects, as `zmq_setsockopt` does.
+    ///
+    /// This covers options the crate has no method for yet, such as `ZMQ_ROUTER_MANDATORY`.
+    /// Integer options take the native-endian bytes of an `i32`, or of an `i64` for the few
+    /// 64-bit ones. An invalid option or value aborts the bind or connect, usually with
+    /// `EINVAL`. Read options back once the socket is built with
+    /// [`AsRawSocket::get_raw_option`](trait.AsRawSocket.html#method.get_raw_option).
+    pub fn set_raw_option(mut self, option: i32, value: &[u8]) -> Self {
+        let value = value.to_vec();
+        self.options.push(Box::new(move |socket| {
+            RawSocket::of(socket).set_option(option, &value)
+        }));
+        self
+    }
+
+    /// Queue up to `capacity` outgoing messages in the `Sink` of the socket, handling a full
+    /// queue according to `overflow`.
+    ///
+    /// Messages wait in this queue while ØMQ cannot take them, for example when its own high
+    /// water mark is reached. Without this option the socket holds a single message and applies
+    /// backpressure. Messages dropped by the overflow policy are counted by the
+    /// `dropped_messages` method of the socket.
+    ///
+    /// Only sockets sending through a `Sink` have a send queue: `Request` and `Reply`, which send
+    /// in lockstep, and the receiving sockets don't, and building them with this option fails with
+    /// `EINVAL`.
+    pub fn with_send_queue(self, capacity: usize, overflow: SendOverflow) -> Self {
+        Self {
+            send_queue: Some(SendQueueConfig {
+                capacity: capacity.max(1),
+                overflow,
+            }),
+            ..self
         }
     }
 
     /// Connect to the ZMQ endpoint based on given URI
-    pub fn connect(self) -> Result<T, Error> {
-        let socket = match self.context {
-            Some(cx) => cx.socket(self.socket_type)?,
-            None => zmq::Context::new().socket(self.socket_type)?,
-        };
+    pub fn connect(mut self) -> Result<T, SocketError> {
+        let socket = self.socket()?;
 
-        socket.connect(self.endpoint)?;
-        Ok(T::from(socket))
+        socket.connect(self.endpoint.as_str())?;
+        let attachment = Attachment::Connected(vec![self.endpoint.as_str().to_owned()]);
+        Ok(self.build(socket, attachment, None))
+    }
+
+    /// Connect to the ZMQ endpoint of the builder and to each of `endpoints`.
+    ///
+    /// All the endpoints are validated before connecting. If connecting to one of them fails,
+    /// the connections already made are dropped again and the error is returned.
+    pub fn connect_many(mut self, endpoints: &[&str]) -> Result<T, SocketError> {
+        let endpoints = self.with_endpoints(endpoints)?;
+        let socket = self.socket()?;
+
+        let mut connected: Vec<String> = Vec::new();
+        for endpoint in &endpoints {
+            if let Err(error) = socket.connect(endpoint.as_str()) {
+                for endpoint in &connected {
+                    let _ = socket.disconnect(endpoint);
+                }
+                return Err(error.into());
+            }
+            connected.push(endpoint.as_str().to_owned());
+        }
+        Ok(self.build(socket, Attachment::Connected(connected), None))
     }
 
     /// Bind to the ZMQ endpoint based on given URI
-    pub fn bind(self) -> Result<T, Error> {
-        let socket = match self.context {
-            Some(cx) => cx.socket(self.socket_type)?,
-            None => zmq::Context::new().socket(self.socket_type)?,
+    ///
+    /// Binding an `inproc://` name already bound by another socket of the same [`Context`] fails
+    /// with [`SocketError::InprocNameTaken`].
+    ///
+    /// [`Context`]: context/struct.Context.html
+    /// [`SocketError::InprocNameTaken`]: enum.SocketError.html#variant.InprocNameTaken
+    pub fn bind(self) -> Result<T, SocketError> {
+        Ok(self.bind_with_endpoint()?.0)
+    }
+
+    /// Bind to the ZMQ endpoint as [`bind`](#method.bind) does, returning the endpoint the
+    /// socket is bound to along with it.
+    ///
+    /// Wildcards are resolved in the returned endpoint, so binding `tcp://127.0.0.1:*` returns
+    /// the port the system picked, such as `tcp://127.0.0.1:49152`, ready for peers to connect
+    /// to.
+    pub fn bind_with_endpoint(mut self) -> Result<(T, String), SocketError> {
+        let socket = self.socket()?;
+
+        let claim = self.bind_socket(&socket, &self.endpoint)?;
+        let endpoint = self.bound(&socket, &self.endpoint);
+        let attachment = Attachment::Bound(vec![endpoint.clone()]);
+        Ok((self.build(socket, attachment, claim), endpoint))
+    }
+
+    /// Bind to the ZMQ endpoint of the builder and to each of `endpoints`, for example to serve
+    /// both a `tcp://` and an `ipc://` endpoint from the same socket.
+    ///
+    /// All the endpoints are validated before binding. If binding one of them fails, the
+    /// endpoints already bound are unbound again and the error is returned.
+    pub fn bind_many(mut self, endpoints: &[&str]) -> Result<T, SocketError> {
+        let endpoints = self.with_endpoints(endpoints)?;
+        let socket = self.socket()?;
+
+        let mut claims = Vec::new();
+        let mut bound = Vec::new();
+        for endpoint in &endpoints {
+            match self.bind_socket(&socket, endpoint) {
+                Ok(claim) => {
+                    claims.extend(claim);
+                    bound.push(self.bound(&socket, endpoint));
+                }
+                Err(error) => {
+                    for endpoint in &bound {
+                        let _ = socket.unbind(endpoint);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        Ok(self.build(socket, Attachment::Bound(bound), claims))
+    }
+
+    /// Bind to the ZMQ endpoint, retrying while the address is still in use.
+    ///
+    /// If the bind fails with `EADDRINUSE`, it is retried up to `attempts` more times with
+    /// `delay` between each try. This is mostly useful for rebinding a fixed port that a
+    /// previous socket has just released. Address reuse (`SO_REUSEADDR`) is managed by ØMQ
+    /// itself and cannot be configured through this crate.
+    pub async fn bind_retry(mut self, attempts: u32, delay: Duration) -> Result<T, SocketError> {
+        let socket = self.socket()?;
+        let mut remaining = attempts;
+
+        loop {
+            match self.bind_socket(&socket, &self.endpoint) {
+                Ok(claim) => {
+                    let attachment = Attachment::Bound(vec![self.bound(&socket, &self.endpoint)]);
+                    return Ok(self.build(socket, attachment, claim));
+                }
+                Err(SocketError::InprocNameTaken(_))
+                | Err(SocketError::Unexpected(Error::EADDRINUSE))
+                    if remaining > 0 =>
+                {
+                    remaining -= 1;
+                    sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Connect to the ZMQ endpoint without blocking the executor.
+    ///
+    /// The connection is set up on a separate thread. Prefer this over [`connect`](#method.connect)
+    /// when the endpoint needs name resolution (`tcp://hostname:port`) or points to an `ipc://`
+    /// path on a slow filesystem. Use [`resolve_timeout`](#method.resolve_timeout) to bound the
+    /// name resolution.
+    pub async fn connect_async(mut self) -> Result<T, SocketError> {
+        let socket = self.socket()?;
+        let endpoint = match self.resolve_timeout {
+            Some(timeout) => resolve_endpoint(self.endpoint.as_str(), timeout).await?,
+            None => self.endpoint.as_str().to_owned(),
         };
 
-        socket.bind(self.endpoint)?;
-        Ok(T::from(socket))
+        let attachment = Attachment::Connected(vec![endpoint.clone()]);
+        let socket = spawn_blocking(move || socket.connect(&endpoint).map(|_| socket)).await?;
+        Ok(self.build(socket, attachment, None))
+    }
+
+    /// Bind to the ZMQ endpoint without blocking the executor.
+    ///
+    /// The bind happens on a separate thread. Prefer this over [`bind`](#method.bind) when the
+    /// endpoint needs name resolution (`tcp://hostname:port`) or points to an `ipc://` path on a
+    /// slow filesystem.
+    pub async fn bind_async(mut self) -> Result<T, SocketError> {
+        let socket = self.socket()?;
+        let claim = self.claim_inproc(&self.endpoint)?;
+        let endpoint = self.endpoint.as_str().to_owned();
+
+        let socket = spawn_blocking(move || socket.bind(&endpoint).map(|_| socket))
+            .await
+            .map_err(|e| self.bind_error(&self.endpoint, e))?;
+        let attachment = Attachment::Bound(vec![self.bound(&socket, &self.endpoint)]);
+        Ok(self.build(socket, attachment, claim))
+    }
+
+    /// Wrap the socket, keeping its inproc claim, its place in the open socket count of the
+    /// context, and the context itself until the socket is dropped.
+    fn build(
+        &mut self,
+        socket: zmq::Socket,
+        attachment: Attachment,
+        claims: impl IntoIterator<Item = InprocClaim>,
+    ) -> T {
+        let mut socket = ZmqSocket::with_events(socket, std::mem::take(&mut self.events));
+        socket.attach_guard(attachment);
+        for claim in claims {
+            socket.attach_guard(claim);
+        }
+        // The event hub holds the context itself.
+        socket.attach_guard(self.context().track_socket());
+
+        T::from_parts(SocketParts {
+            socket,
+            send_queue: self.send_queue.unwrap_or_default(),
+        })
+    }
+
+    /// Endpoint `socket` has just been bound to, with wildcards such as `tcp://*:*` resolved.
+    fn bound(&self, socket: &zmq::Socket, endpoint: &Endpoint) -> String {
+        match socket.get_last_endpoint() {
+            Ok(Ok(last)) if !last.is_empty() => last,
+            _ => endpoint.as_str().to_owned(),
+        }
+    }
+
+    /// Bind the socket, reserving its inproc name in the registry of the context.
+    fn bind_socket(
+        &self,
+        socket: &zmq::Socket,
+        endpoint: &Endpoint,
+    ) -> Result<Option<InprocClaim>, SocketError> {
+        let claim = self.claim_inproc(endpoint)?;
+        socket
+            .bind(endpoint.as_str())
+            .map_err(|e| self.bind_error(endpoint, e))?;
+        Ok(claim)
+    }
+
+    /// The endpoint of the builder followed by `others`, once they are all validated.
+    fn with_endpoints(&self, others: &[&str]) -> Result<Vec<Endpoint>, SocketError> {
+        let mut endpoints = vec![self.endpoint.clone()];
+        for endpoint in others {
+            endpoints.push(Endpoint::parse(endpoint)?);
+        }
+        Ok(endpoints)
+    }
+
+    fn claim_inproc(&self, endpoint: &Endpoint) -> Result<Option<InprocClaim>, SocketError> {
+        match endpoint.as_str().strip_prefix("inproc://") {
+            Some(name) => self.context().claim_inproc(name).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn bind_error(&self, endpoint: &Endpoint, error: Error) -> SocketError {
+        match (error, endpoint.as_str().strip_prefix("inproc://")) {
+            (Error::EADDRINUSE, Some(name)) => SocketError::InprocNameTaken(name.to_owned()),
+            (error, _) => error.into(),
+        }
+    }
+
+    /// The context set with `with_context`, or else the default context.
+    fn context(&self) -> &crate::context::Context {
+        self.context.as_ref().unwrap_or_else(|| default_context())
+    }
+
+    fn socket(&mut self) -> Result<zmq::Socket, Error> {
+        if self.send_queue.is_some() && !T::SEND_QUEUE {
+            return Err(Error::EINVAL);
+        }
+        let context = self.context().clone();
+        let mut socket = context.socket(self.socket_type)?;
+        context.default_socket_options().apply(&socket)?;
+        for option in self.options.drain(..) {
+            option(&mut socket)?;
+        }
+        self.events = EventHub::new(context);
+        if self.monitored {
+            self.events.start(&socket)?;
+        }
+        Ok(socket)
+    }
+}
+
+/// Endpoints a socket was bound or connected to by its builder.
+#[derive(Clone)]
+enum Attachment {
+    Bound(Vec<String>),
+    Connected(Vec<String>),
+}
+
+/// Number of times a socket is bound again after being unbound, while its address is released.
+const REBIND_ATTEMPTS: u32 = 50;
+
+/// Drop the connections of `socket` and establish them again, so that options only read when a
+/// connection is set up, such as the high water marks, apply to them.
+pub(crate) async fn reset_connections(socket: &ZmqSocket) -> Result<(), Error> {
+    let attachment = socket.guard::<Attachment>().cloned();
+    let socket = socket.as_socket();
+    match attachment {
+        Some(Attachment::Bound(endpoints)) => {
+            for endpoint in &endpoints {
+                socket.unbind(endpoint)?;
+                // The address is released in the background once the listener is closed.
+                let mut remaining = REBIND_ATTEMPTS;
+                loop {
+                    match socket.bind(endpoint) {
+                        Err(Error::EADDRINUSE) if remaining > 0 => {
+                            remaining -= 1;
+                            sleep(Duration::from_millis(10)).await;
+                        }
+                        result => break result?,
+                    }
+                }
+            }
+            Ok(())
+        }
+        Some(Attachment::Connected(endpoints)) => {
+            for endpoint in &endpoints {
+                socket.disconnect(endpoint)?;
+                socket.connect(endpoint)?;
+            }
+            Ok(())
+        }
+        // Not built by a `SocketBuilder`, the endpoints are unknown.
+        None => Err(Error::EINVAL),
+    }
+}
+
+/// Set the routing id of `socket`, unless its builder connected it, in which case the routing
+/// id was already exchanged and setting it would have no effect.
+pub(crate) fn set_identity(socket: &ZmqSocket, id: &[u8]) -> Result<(), Error> {
+    if let Some(Attachment::Connected(_)) = socket.guard() {
+        return Err(Error::EINVAL);
+    }
+    socket.as_socket().set_identity(id)
+}
+
+/// `ZMQ_METADATA`, a draft option the zmq crate does not expose.
+const ZMQ_METADATA: i32 = 95;
+
+/// Add the `key:value` metadata property to `socket`, unless its builder connected it, in which
+/// case the metadata was already exchanged and adding to it would have no effect.
+pub(crate) fn set_metadata(socket: &ZmqSocket, key: &str, value: &str) -> Result<(), Error> {
+    if let Some(Attachment::Connected(_)) = socket.guard() {
+        return Err(Error::EINVAL);
+    }
+    add_metadata(socket.get_ref().raw(), key, value)
+}
+
+/// Add the `key:value` metadata property to `socket`.
+fn add_metadata(socket: &RawSocket, key: &str, value: &str) -> Result<(), Error> {
+    socket.set_option(ZMQ_METADATA, format!("{}:{}", key, value).as_bytes())
+}
+
+/// Close `socket`, completing once ØMQ has destroyed it, which happens when its linger period
+/// is over or every message it holds has been delivered.
+///
+/// ØMQ stops the monitor of a socket as it destroys it, so the end of the monitor stream
+/// signals it.
+pub(crate) async fn close_socket<S: AsRawSocket>(socket: S) -> Result<(), SocketError> {
+    let mut events = socket.monitor(zmq::SocketEvent::MONITOR_STOPPED)?;
+    drop(socket);
+    while let Some(event) = poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await {
+        event.map_err(zmq::Error::from)?;
+    }
+    Ok(())
+}
+
+static MONITOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Start monitoring `events` of `socket`, returning the PAIR socket receiving them, created in
+/// `context`, the context of `socket`.
+///
+/// `MONITOR_STOPPED` is always monitored, so the end of the monitoring can be detected.
+pub(crate) fn monitor_socket(
+    socket: &zmq::Socket,
+    context: &crate::context::Context,
+    events: &[zmq::SocketEvent],
+) -> Result<zmq::Socket, Error> {
+    let endpoint = format!(
+        "inproc://async-zmq-monitor-{}",
+        MONITOR_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let stopped = zmq::SocketEvent::MONITOR_STOPPED.to_raw();
+    let mask = events
+        .iter()
+        .fold(stopped, |mask, event| mask | event.to_raw());
+    socket.monitor(&endpoint, mask as i32)?;
+
+    let monitor = context.socket(zmq::PAIR)?;
+    monitor.connect(&endpoint)?;
+    Ok(monitor)
+}
+
+/// Resolve the hostname of a `tcp://` endpoint into an IP address within `timeout`.
+async fn resolve_endpoint(endpoint: &str, timeout: Duration) -> Result<String, Error> {
+    let address = match endpoint.strip_prefix("tcp://") {
+        Some(address) => address,
+        None => return Ok(endpoint.to_owned()),
+    };
+    // The address may be prefixed by a source address: `tcp://source;host:port`.
+    let (source, address) = match address.rfind(';') {
+        Some(idx) => address.split_at(idx + 1),
+        None => ("", address),
+    };
+    let (host, port) = address.rsplit_once(':').ok_or(Error::EINVAL)?;
+    let bare_host = host.trim_start_matches('[').trim_end_matches(']');
+    if host == "*" || bare_host.parse::<IpAddr>().is_ok() {
+        return Ok(endpoint.to_owned());
+    }
+
+    let lookup = bare_host.to_owned();
+    let resolve = Box::pin(spawn_blocking(move || {
+        (lookup.as_str(), 0)
+            .to_socket_addrs()
+            .map(|mut addrs| addrs.next())
+    }));
+    let ip = match select(resolve, sleep(timeout)).await {
+        Either::Left((Ok(Some(addr)), _)) => addr.ip(),
+        Either::Left(_) => return Err(Error::EHOSTUNREACH),
+        Either::Right(_) => return Err(Error::EAGAIN),
+    };
+
+    Ok(match ip {
+        IpAddr::V4(ip) => format!("tcp://{}{}:{}", source, ip, port),
+        IpAddr::V6(ip) => format!("tcp://{}[{}]:{}", source, ip, port),
+    })
+}
+
+/// What to do with a message sent while the send queue is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendOverflow {
+    /// Wait until the queue has room again. This is the default.
+    Block,
+    /// Drop the oldest queued message to make room for the new one.
+    DropOldest,
+    /// Drop the new message, keeping the queue as it is.
+    DropNewest,
+}
+
+/// Settings of a send queue, given with `SocketBuilder::with_send_queue`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SendQueueConfig {
+    capacity: usize,
+    overflow: SendOverflow,
+}
+
+impl Default for SendQueueConfig {
+    /// A single-message queue applying backpressure.
+    fn default() -> Self {
+        Self {
+            capacity: 1,
+            overflow: SendOverflow::Block,
+        }
+    }
+}
+
+pub(crate) use sealed::{FromParts, SocketParts};
+
+mod sealed {
+    use super::{SendQueueConfig, ZmqSocket};
+
+    /// What a [`SocketBuilder`](../struct.SocketBuilder.html) hands to the socket it builds.
+    pub struct SocketParts {
+        pub(crate) socket: ZmqSocket,
+        pub(crate) send_queue: SendQueueConfig,
+    }
+
+    impl From<zmq::Socket> for SocketParts {
+        /// Parts of a socket built outside of a `SocketBuilder`, with the default send queue.
+        fn from(socket: zmq::Socket) -> Self {
+            Self {
+                socket: ZmqSocket::from(socket),
+                send_queue: SendQueueConfig::default(),
+            }
+        }
+    }
+
+    /// Socket wrapper a [`SocketBuilder`](../struct.SocketBuilder.html) can build.
+    ///
+    /// It is implemented by every socket of this crate and can't be implemented outside of it.
+    pub trait FromParts {
+        /// Whether the socket sends through a send queue, configured by `with_send_queue`.
+        const SEND_QUEUE: bool;
+
+        /// Wrap the socket of `parts`, giving its send queue the settings of `parts`.
+        fn from_parts(parts: SocketParts) -> Self;
+    }
+}
+
+/// Outgoing messages waiting to be taken by ØMQ.
+pub(crate) struct SendQueue<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
+    items: VecDeque<MultipartIter<I, T>>,
+    capacity: usize,
+    overflow: SendOverflow,
+    dropped: u64,
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> SendQueue<I, T> {
+    /// Create an empty queue with the settings of `config`.
+    pub(crate) fn new(config: SendQueueConfig) -> Self {
+        Self {
+            items: VecDeque::with_capacity(config.capacity),
+            capacity: config.capacity,
+            overflow: config.overflow,
+            dropped: 0,
+        }
+    }
+
+    /// Create an empty queue with the same settings as `other`.
+    pub(crate) fn settings_of<J: Iterator<Item = U> + Unpin, U: Into<Message>>(
+        other: &SendQueue<J, U>,
+    ) -> Self {
+        Self::new(SendQueueConfig {
+            capacity: other.capacity,
+            overflow: other.overflow,
+        })
+    }
+
+    /// Number of messages dropped by the overflow policy so far.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn poll_ready(&mut self, socket: &ZmqSocket, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        match self.poll_flush(socket, cx) {
+            Poll::Pending
+                if self.items.len() >= self.capacity && self.overflow == SendOverflow::Block =>
+            {
+                Poll::Pending
+            }
+            Poll::Pending => Poll::Ready(Ok(())),
+            ready => ready,
+        }
+    }
+
+    fn start_send(&mut self, item: MultipartIter<I, T>) {
+        if self.items.len() >= self.capacity {
+            match self.overflow {
+                SendOverflow::Block => {}
+                SendOverflow::DropOldest => {
+                    self.items.pop_front();
+                    self.dropped += 1;
+                }
+                SendOverflow::DropNewest => {
+                    self.dropped += 1;
+                    return;
+                }
+            }
+        }
+        self.items.push_back(item);
+    }
+
+    fn poll_flush(&mut self, socket: &ZmqSocket, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        while let Some(item) = self.items.front_mut() {
+            ready!(socket.send(cx, item))?;
+            self.items.pop_front();
+        }
+        Poll::Ready(Ok(()))
     }
 }
 
 pub(crate) struct Sender<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
     pub(crate) socket: ZmqSocket,
-    pub(crate) buffer: Option<MultipartIter<I, T>>,
+    pub(crate) queue: SendQueue<I, T>,
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Sender<I, T> {
     type Error = Error;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_flush(self, cx)
+        let Self { socket, queue } = self.get_mut();
+        queue.poll_ready(socket, cx)
     }
 
     fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        self.get_mut().buffer = Some(item);
+        self.get_mut().queue.start_send(item);
         Ok(())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let Self { socket, buffer } = self.get_mut();
-        if let Some(buffer) = buffer.as_mut() {
-            socket.send(cx, buffer)
-        } else {
-            Poll::Ready(Ok(()))
-        }
+        let Self { socket, queue } = self.get_mut();
+        queue.poll_flush(socket, cx)
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -140,30 +1183,47 @@ impl Stream for Receiver {
     }
 }
 
+impl Receiver {
+    /// Receive messages until `duration` has elapsed, and return them in the order they came.
+    pub(crate) async fn collect_for(
+        &mut self,
+        duration: Duration,
+    ) -> Result<Vec<Multipart>, Error> {
+        let mut timer = sleep(duration);
+        let mut msgs = Vec::new();
+        poll_fn(|cx| loop {
+            // Check the timer first, so that a busy socket doesn't extend the window.
+            if Pin::new(&mut timer).poll(cx).is_ready() {
+                return Poll::Ready(Ok::<_, Error>(()));
+            }
+            msgs.push(ready!(self.socket.recv(cx))?);
+        })
+        .await?;
+        Ok(msgs)
+    }
+}
+
 pub(crate) struct Broker<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
     pub(crate) socket: ZmqSocket,
-    pub(crate) buffer: Option<MultipartIter<I, T>>,
+    pub(crate) queue: SendQueue<I, T>,
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Broker<I, T> {
     type Error = Error;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_flush(self, cx)
+        let Self { socket, queue } = self.get_mut();
+        queue.poll_ready(socket, cx)
     }
 
     fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        self.get_mut().buffer = Some(item);
+        self.get_mut().queue.start_send(item);
         Ok(())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let Self { socket, buffer } = self.get_mut();
-        if let Some(buffer) = buffer.as_mut() {
-            socket.send(cx, buffer)
-        } else {
-            Poll::Ready(Ok(()))
-        }
+        let Self { socket, queue } = self.get_mut();
+        queue.poll_flush(socket, cx)
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -25,33 +25,85 @@
 //! }
 //! ```
 //!
+//! # Liveness
+//!
+//! ØMQ tells about each TCP connection with a message made of the routing id of the peer and an
+//! empty frame, both when the peer connects and when it disconnects. [`ZmqStream::peer_events`]
+//! turns those into [`PeerEvent`]s. A peer that dies without closing its connection is only
+//! noticed through TCP keepalive, enabled with [`ZmqStream::set_heartbeat`].
+//!
+//! # Raw TCP
+//!
+//! [`ZmqStream::into_io`] turns the socket into a [`StreamIo`], implementing [`AsyncRead`] and
+//! [`AsyncWrite`] over a single connection, so that an existing byte protocol can be piped
+//! through the socket. The routing id framing each message is handled by the adapter.
+//!
 //! [`stream`]: fn.stream.html
+//! [`ZmqStream::into_io`]: struct.ZmqStream.html#method.into_io
+//! [`StreamIo`]: struct.StreamIo.html
+//! [`AsyncRead`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncRead.html
+//! [`AsyncWrite`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncWrite.html
+//! [`ZmqStream::peer_events`]: struct.ZmqStream.html#method.peer_events
+//! [`ZmqStream::set_heartbeat`]: struct.ZmqStream.html#method.set_heartbeat
+//! [`PeerEvent`]: enum.PeerEvent.html
 //! [`Stream`]: ../trait.Stream.html
 //! [`StreamExt`]: ../trait.StreamExt.html
 
+use std::collections::HashSet;
+use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use zmq::SocketType;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::ready;
+use zmq::{Message, SocketType};
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, Receiver, SocketBuilder},
+    endpoint::Endpoint,
+    reactor::AsRawSocket,
+    socket::{
+        close_socket, FromParts, Multipart, MultipartIter, Receiver, SocketBuilder, SocketParts,
+    },
     RecvError, SocketError, Stream,
 };
 
 /// Create a ZMQ socket with STREAM type
-pub fn stream(endpoint: &str) -> Result<SocketBuilder<'_, ZmqStream>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn stream<'a>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, ZmqStream>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::STREAM, endpoint))
 }
 
+/// Create a ZMQ socket with STREAM type, built with `context`
+///
+/// This is [`stream`](fn.stream.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn stream_in<'a>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, ZmqStream>, SocketError> {
+    Ok(stream(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with STREAM type
-pub struct ZmqStream(Receiver);
+pub struct ZmqStream(pub(crate) Receiver);
 
 impl From<zmq::Socket> for ZmqStream {
     fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl FromParts for ZmqStream {
+    const SEND_QUEUE: bool = false;
+
+    fn from_parts(parts: SocketParts) -> Self {
         Self(Receiver {
-            socket: ZmqSocket::from(socket),
+            socket: parts.socket,
         })
     }
 }
@@ -71,4 +123,269 @@ impl ZmqStream {
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    /// Close the socket, completing once ØMQ has destroyed it.
+    ///
+    /// Messages received by ØMQ and not read yet are discarded.
+    pub async fn close(self) -> Result<(), SocketError> {
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Enable TCP keepalive, so that peers dying without closing their connection are detected.
+    ///
+    /// STREAM peers speak raw TCP, without the heartbeats of the ØMQ protocol. With keepalive,
+    /// the operating system probes a connection once it has been idle for `idle`, then every
+    /// `interval`, and drops it after `probes` unanswered probes, which shows up as a
+    /// [`PeerEvent::Disconnected`]. Durations are rounded down to whole seconds, with a minimum
+    /// of one. Only connections established afterwards are affected, so call it right after
+    /// `bind` or `connect`, or use the `configure` method of the builder.
+    ///
+    /// [`PeerEvent::Disconnected`]: enum.PeerEvent.html#variant.Disconnected
+    pub fn set_heartbeat(
+        &mut self,
+        idle: Duration,
+        interval: Duration,
+        probes: i32,
+    ) -> Result<&mut Self, zmq::Error> {
+        let socket = self.as_raw_socket();
+        socket.set_tcp_keepalive(1)?;
+        socket.set_tcp_keepalive_idle(whole_seconds(idle))?;
+        socket.set_tcp_keepalive_intvl(whole_seconds(interval))?;
+        socket.set_tcp_keepalive_cnt(probes)?;
+        Ok(self)
+    }
+
+    /// Turn the socket into a stream telling peers connecting and disconnecting apart from the
+    /// data they send. The socket can be taken back with [`PeerEvents::into_inner`].
+    ///
+    /// This relies on the notifications ØMQ sends by default, which `ZMQ_STREAM_NOTIFY` turns
+    /// off.
+    ///
+    /// [`PeerEvents::into_inner`]: struct.PeerEvents.html#method.into_inner
+    pub fn peer_events(self) -> PeerEvents {
+        PeerEvents {
+            socket: self,
+            peers: HashSet::new(),
+        }
+    }
+
+    /// Turn the socket into an adapter reading and writing the bytes of a single TCP connection.
+    /// The socket can be taken back with [`StreamIo::into_inner`].
+    ///
+    /// The adapter sticks to the first connection ØMQ tells about, which is the one to the
+    /// endpoint of a connected socket, or the first peer of a bound one. Data from other
+    /// connections is discarded.
+    ///
+    /// ```no_run
+    /// use futures::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut io = async_zmq::stream("tcp://127.0.0.1:7")?.connect()?.into_io();
+    ///     io.write_all(b"ping").await?;
+    ///     let mut echo = [0; 4];
+    ///     io.read_exact(&mut echo).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`StreamIo::into_inner`]: struct.StreamIo.html#method.into_inner
+    pub fn into_io(self) -> StreamIo {
+        StreamIo {
+            socket: self,
+            peer: None,
+            unread: Vec::new(),
+            closed: false,
+        }
+    }
+}
+
+fn whole_seconds(duration: Duration) -> i32 {
+    duration.as_secs().clamp(1, i32::MAX as u64) as i32
+}
+
+/// What happened on a connection of a STREAM socket, reported by [`ZmqStream::peer_events`].
+///
+/// [`ZmqStream::peer_events`]: struct.ZmqStream.html#method.peer_events
+#[derive(Debug)]
+pub enum PeerEvent {
+    /// A peer connected, with the given routing id.
+    Connected(Vec<u8>),
+    /// The peer with the given routing id sent data.
+    Data(Vec<u8>, Multipart),
+    /// The connection of the peer with the given routing id was closed or found dead.
+    Disconnected(Vec<u8>),
+}
+
+/// Stream of the events of the connections of a STREAM socket, created by
+/// [`ZmqStream::peer_events`].
+///
+/// [`ZmqStream::peer_events`]: struct.ZmqStream.html#method.peer_events
+pub struct PeerEvents {
+    socket: ZmqStream,
+    /// Routing ids of the peers currently connected.
+    peers: HashSet<Vec<u8>>,
+}
+
+impl PeerEvents {
+    /// Routing ids of the peers currently connected.
+    pub fn peers(&self) -> impl Iterator<Item = &[u8]> {
+        self.peers.iter().map(Vec::as_slice)
+    }
+
+    /// Get the stream socket back.
+    pub fn into_inner(self) -> ZmqStream {
+        self.socket
+    }
+}
+
+impl Stream for PeerEvents {
+    type Item = Result<PeerEvent, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let mut msg = match futures::ready!(Pin::new(&mut this.socket).poll_next(cx)) {
+                Some(Ok(msg)) => msg,
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => return Poll::Ready(None),
+            };
+            let id = match msg.remove(0) {
+                Some(id) => id.to_vec(),
+                None => continue,
+            };
+            let event = if !msg.frame_is_empty(0) {
+                PeerEvent::Data(id, msg)
+            } else if this.peers.insert(id.clone()) {
+                PeerEvent::Connected(id)
+            } else {
+                this.peers.remove(&id);
+                PeerEvent::Disconnected(id)
+            };
+            return Poll::Ready(Some(Ok(event)));
+        }
+    }
+}
+
+/// [`AsyncRead`] and [`AsyncWrite`] adapter over a single connection of a STREAM socket, created
+/// by [`ZmqStream::into_io`].
+///
+/// Reading returns end of file once the connection is closed by the peer, and closing the writer
+/// closes the connection.
+///
+/// [`AsyncRead`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncRead.html
+/// [`AsyncWrite`]: https://docs.rs/futures/0.3/futures/io/trait.AsyncWrite.html
+/// [`ZmqStream::into_io`]: struct.ZmqStream.html#method.into_io
+pub struct StreamIo {
+    socket: ZmqStream,
+    /// Routing id of the connection, once ØMQ has told about it.
+    peer: Option<Vec<u8>>,
+    /// Data received and not read yet.
+    unread: Vec<u8>,
+    /// Whether the connection is closed.
+    closed: bool,
+}
+
+impl StreamIo {
+    /// Routing id of the connection, if ØMQ has told about it yet.
+    pub fn peer(&self) -> Option<&[u8]> {
+        self.peer.as_deref()
+    }
+
+    /// Get the stream socket back. Data received and not read yet is lost.
+    pub fn into_inner(self) -> ZmqStream {
+        self.socket
+    }
+
+    /// Receive one message and account for it: the connection notification sets the routing id,
+    /// data is buffered, and the disconnection notification closes the adapter.
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut msg = ready!(self.socket.0.socket.recv(cx))?;
+        let id = match msg.remove(0) {
+            Some(id) => id,
+            None => return Poll::Ready(Ok(())),
+        };
+        let connected = match &self.peer {
+            None => {
+                self.peer = Some(id.to_vec());
+                true
+            }
+            Some(peer) if peer[..] != id[..] => return Poll::Ready(Ok(())),
+            Some(_) => false,
+        };
+        match msg.frame_bytes(0) {
+            Some(data) if !data.is_empty() => self.unread.extend_from_slice(data),
+            _ if connected => {}
+            _ => self.closed = true,
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for StreamIo {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        while this.unread.is_empty() && !this.closed {
+            ready!(this.poll_recv(cx))?;
+        }
+        let len = buf.len().min(this.unread.len());
+        buf[..len].copy_from_slice(&this.unread[..len]);
+        this.unread.drain(..len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncWrite for StreamIo {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        // A connecting socket learns the routing id of its connection from the notification.
+        while this.peer.is_none() {
+            ready!(this.poll_recv(cx))?;
+        }
+        if this.closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let peer = this.peer.as_deref().unwrap_or_default();
+        let mut frames = MultipartIter(vec![Message::from(peer), Message::from(buf)].into_iter());
+        ready!(this.socket.0.socket.send(cx, &mut frames))?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Frames are handed to ØMQ as they are written.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if let (Some(peer), false) = (&this.peer, this.closed) {
+            // Sending an empty frame to a connection closes it.
+            let mut frames =
+                MultipartIter(vec![Message::from(&peer[..]), Message::new()].into_iter());
+            ready!(this.socket.0.socket.send(cx, &mut frames))?;
+            this.closed = true;
+        }
+        Poll::Ready(Ok(()))
+    }
 }
@@ -34,30 +34,70 @@
 //! [`Stream`]: ../trait.Stream.html
 //! [`StreamExt`]: ../trait.StreamExt.html
 
+use std::collections::VecDeque;
+use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use zmq::SocketType;
+use futures::channel::mpsc;
+use futures::{future, future::poll_fn, TryStreamExt};
+use futures_timer::Delay;
+use zmq::{Message, SocketType};
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, Receiver, SocketBuilder},
-    RecvError, SocketError, Stream, SubscribeError,
+    endpoint::Endpoint,
+    reactor::{sleep, within, AsRawSocket},
+    socket::{
+        close_socket, set_metadata, FromParts, Multipart, Receiver, SocketBuilder, SocketParts,
+    },
+    ttl, ReassembleError, RecvError, SocketError, Stream, SubscribeError,
 };
 
 /// Create a ZMQ socket with SUB type
-pub fn subscribe(endpoint: &str) -> Result<SocketBuilder<'_, Subscribe>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn subscribe<'a>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Subscribe>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::SUB, endpoint))
 }
 
+/// Create a ZMQ socket with SUB type, built with `context`
+///
+/// This is [`subscribe`](fn.subscribe.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn subscribe_in<'a>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, Subscribe>, SocketError> {
+    Ok(subscribe(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with SUB type
-pub struct Subscribe(Receiver);
+pub struct Subscribe {
+    pub(crate) inner: Receiver,
+    /// Topics subscribed through `set_subscribe`, once per call, as ØMQ counts them.
+    topics: Vec<String>,
+}
 
 impl From<zmq::Socket> for Subscribe {
     fn from(socket: zmq::Socket) -> Self {
-        Self(Receiver {
-            socket: ZmqSocket::from(socket),
-        })
+        Self::from_parts(socket.into())
+    }
+}
+
+impl FromParts for Subscribe {
+    const SEND_QUEUE: bool = false;
+
+    fn from_parts(parts: SocketParts) -> Self {
+        Self {
+            inner: Receiver {
+                socket: parts.socket,
+            },
+            topics: Vec::new(),
+        }
     }
 }
 
@@ -65,7 +105,7 @@ impl Stream for Subscribe {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().0)
+        Pin::new(&mut self.get_mut().inner)
             .poll_next(cx)
             .map(|poll| poll.map(|result| result.map_err(Into::into)))
     }
@@ -73,23 +113,416 @@ impl Stream for Subscribe {
 
 impl Subscribe {
     /// Subscribe a topic to the socket
+    ///
+    /// Topics are matched as prefixes and a message is received if any subscription matches it,
+    /// so subscribing to `""` receives everything whatever other topics are subscribed. See
+    /// [`subscriptions_overlap`](#method.subscriptions_overlap) to spot such redundant topics;
+    /// with the `tracing` feature, subscribing one also logs a warning.
+    ///
+    /// Fails with `SubscribeError::ContextTerminated` once the context of the socket has been
+    /// shut down or terminated.
     pub fn set_subscribe(&mut self, topic: &str) -> Result<&mut Self, SubscribeError> {
+        // Process pending commands first, so that a terminated context is reported.
+        self.as_raw_socket().get_events()?;
         self.as_raw_socket().set_subscribe(topic.as_bytes())?;
+        #[cfg(feature = "tracing")]
+        for other in self.topics.iter().filter(|other| *other != topic) {
+            if topic.starts_with(other.as_str()) || other.starts_with(topic) {
+                tracing::warn!(topic, other, "SUB socket subscriptions overlap");
+            }
+        }
+        self.topics.push(topic.to_owned());
         Ok(self)
     }
 
     /// Remove a topic from the socket
     pub fn set_unsubscribe(&mut self, topic: &str) -> Result<&mut Self, SubscribeError> {
+        // Process pending commands first, so that a terminated context is reported.
+        self.as_raw_socket().get_events()?;
         self.as_raw_socket().set_unsubscribe(topic.as_bytes())?;
+        if let Some(idx) = self.topics.iter().position(|other| other == topic) {
+            self.topics.remove(idx);
+        }
         Ok(self)
     }
 
+    /// List the subscriptions made redundant by a broader one, as `(broader, redundant)` pairs.
+    ///
+    /// A topic is redundant when another subscribed topic is a prefix of it, since every message
+    /// it matches is already received; `""` makes every other topic redundant. Only topics
+    /// subscribed through [`set_subscribe`](#method.set_subscribe) are considered.
+    pub fn subscriptions_overlap(&self) -> Vec<(String, String)> {
+        let mut topics = self.topics.clone();
+        topics.sort();
+        topics.dedup();
+
+        let mut overlaps = Vec::new();
+        for broader in &topics {
+            for topic in &topics {
+                if topic != broader && topic.starts_with(broader.as_str()) {
+                    overlaps.push((broader.clone(), topic.clone()));
+                }
+            }
+        }
+        overlaps
+    }
+
+    /// Decode every received message with `f`, passing errors through untouched.
+    ///
+    /// ```no_run
+    /// use async_zmq::{Result, StreamExt};
+    ///
+    /// struct Quote {
+    ///     symbol: String,
+    ///     price: f64,
+    /// }
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut zmq = async_zmq::subscribe("tcp://127.0.0.1:5555")?.connect()?;
+    ///     zmq.set_subscribe("")?;
+    ///
+    ///     let mut quotes = zmq.map_ok(|msg| Quote {
+    ///         symbol: msg[0].as_str().unwrap_or_default().to_string(),
+    ///         price: msg[1].as_str().and_then(|p| p.parse().ok()).unwrap_or_default(),
+    ///     });
+    ///     while let Some(quote) = quotes.next().await {
+    ///         let quote = quote?;
+    ///         println!("{}: {}", quote.symbol, quote.price);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn map_ok<F, O>(self, f: F) -> impl Stream<Item = Result<O, RecvError>>
+    where
+        F: FnMut(Multipart) -> O,
+    {
+        TryStreamExt::map_ok(self, f)
+    }
+
+    /// Turn the socket into a stream that skips every message byte-identical to the one yielded
+    /// right before it, such as unchanged sensor readings.
+    ///
+    /// The stream holds a copy of the frames of the last yielded message for the comparison.
+    /// Errors are passed through and don't reset it.
+    pub fn dedup_consecutive(self) -> impl Stream<Item = Result<Multipart, RecvError>> {
+        let mut last: Option<Vec<Vec<u8>>> = None;
+        TryStreamExt::try_filter(self, move |msg| {
+            let frames: Vec<Vec<u8>> = msg.iter().map(|frame| frame.to_vec()).collect();
+            let duplicate = last.as_ref() == Some(&frames);
+            if !duplicate {
+                last = Some(frames);
+            }
+            future::ready(!duplicate)
+        })
+    }
+
+    /// Turn the socket into a stream that drops the messages whose time to live has elapsed.
+    ///
+    /// Messages must be sent with [`Publish::send_with_ttl`]: the timestamp frame following the
+    /// topic is checked and removed, so the stream yields the topic followed by the body.
+    /// Messages without a well-formed timestamp frame are dropped as well. Expiry compares the
+    /// clock of the publisher with the local one, so both must be roughly in sync.
+    ///
+    /// [`Publish::send_with_ttl`]: ../publish/struct.Publish.html#method.send_with_ttl
+    pub fn with_ttl_check(self) -> impl Stream<Item = Result<Multipart, RecvError>> {
+        TryStreamExt::try_filter_map(self, |mut msg| {
+            let expired = msg.frame_bytes(1).and_then(ttl::is_expired);
+            future::ready(Ok(match expired {
+                Some(false) => {
+                    msg.remove(1);
+                    Some(msg)
+                }
+                _ => None,
+            }))
+        })
+    }
+
+    /// Turn the socket into a stream decompressing the frames compressed by
+    /// [`Publish::send_compressed`].
+    ///
+    /// Frames without the compression marker are passed through untouched, and messages with a
+    /// marked frame that cannot be decompressed are dropped. The publisher and the subscriber
+    /// must agree on using compression: an uncompressed body that happens to start with the
+    /// marker is taken as compressed.
+    ///
+    /// Only available with the `compress` feature.
+    ///
+    /// [`Publish::send_compressed`]: ../publish/struct.Publish.html#method.send_compressed
+    #[cfg(feature = "compress")]
+    pub fn decompress(self) -> impl Stream<Item = Result<Multipart, RecvError>> {
+        use crate::compress;
+
+        TryStreamExt::try_filter_map(self, |msg| {
+            let frames = msg
+                .into_iter()
+                .map(|frame| match compress::is_compressed(&frame) {
+                    true => compress::decompress(&frame),
+                    false => Some(frame),
+                })
+                .collect::<Option<Multipart>>();
+            future::ready(Ok(frames))
+        })
+    }
+
+    /// Report the losses of messages ØMQ can observe on a channel, alongside the socket.
+    ///
+    /// The socket is monitored from a background thread, which forwards a [`DropEvent`] when a
+    /// connection is lost, along with the messages queued on it, or when the security handshake
+    /// with a publisher fails. Reporting is best-effort and depends on the transport: `inproc://`
+    /// connections report nothing, and ØMQ gives no feedback at all on messages dropped because
+    /// a high water mark was reached, which happens on the publisher side. Publishers can tag
+    /// messages with a sequence number to detect those gaps.
+    ///
+    /// The events come from the monitor the socket shares with [`AsRawSocket::monitor`], so
+    /// both can be used together. Monitoring `as_raw_socket()` directly replaces that monitor,
+    /// which ends the notifications.
+    ///
+    /// [`DropEvent`]: enum.DropEvent.html
+    /// [`AsRawSocket::monitor`]: ../trait.AsRawSocket.html#method.monitor
+    pub fn with_drop_notifications(
+        self,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<DropEvent>), zmq::Error> {
+        let (sender, receiver) = mpsc::unbounded();
+        let disconnected = zmq::SocketEvent::DISCONNECTED;
+        let handshake_failures = [
+            zmq::SocketEvent::HANDSHAKE_FAILED_NO_DETAIL,
+            zmq::SocketEvent::HANDSHAKE_FAILED_PROTOCOL,
+            zmq::SocketEvent::HANDSHAKE_FAILED_AUTH,
+        ];
+        let mut events = handshake_failures.to_vec();
+        events.push(disconnected);
+
+        self.inner.socket.on_events(&events, move |event| {
+            let endpoint = event.address.clone();
+            let event = if event.event == disconnected {
+                DropEvent::Disconnected(endpoint)
+            } else {
+                DropEvent::HandshakeFailed(endpoint)
+            };
+            let _ = sender.unbounded_send(event);
+        })?;
+
+        Ok((self, receiver))
+    }
+
+    /// Receive the next message, waiting at most `duration` for it.
+    ///
+    /// Unlike a receive timeout set on the socket, running out of time is not an error: it
+    /// returns `Ok(None)`, and the socket stays ready for the next receive. The deadline is
+    /// measured with the timer of the reactor, so this works on any runtime.
+    pub async fn recv_timeout(
+        &mut self,
+        duration: Duration,
+    ) -> Result<Option<Multipart>, RecvError> {
+        let next = within(duration, poll_fn(|cx| self.inner.socket.recv(cx))).await;
+        Ok(next.transpose()?)
+    }
+
+    /// Receive messages for `duration` and return all of them, in the order they came.
+    ///
+    /// This samples whatever arrives during the window, which may be nothing at all, so the
+    /// returned `Vec` can be empty. The window is measured with the reactor timer and isn't
+    /// extended by a busy socket. A receive error ends the window early and discards the
+    /// messages collected so far.
+    pub async fn collect_for(&mut self, duration: Duration) -> Result<Vec<Multipart>, RecvError> {
+        Ok(self.inner.collect_for(duration).await?)
+    }
+
+    /// Turn the socket into a stream that ends once no message has arrived for `timeout`.
+    ///
+    /// The timer restarts on every received message. Ending the stream doesn't close the
+    /// socket, which can be taken back with [`IdleTimeout::into_inner`].
+    ///
+    /// [`IdleTimeout::into_inner`]: struct.IdleTimeout.html#method.into_inner
+    pub fn idle_timeout(self, timeout: Duration) -> IdleTimeout {
+        IdleTimeout {
+            socket: self,
+            timeout,
+            timer: sleep(timeout),
+            idle: false,
+        }
+    }
+
+    /// Turn the socket into a stream joining the parts of messages chunked by the application.
+    ///
+    /// Received messages are buffered until one for which `is_final` returns `true`, and the
+    /// frames of all the buffered messages are then yielded as a single [`Multipart`], in the
+    /// order they were received. ØMQ keeps the order of the messages of a publisher, so parts
+    /// only need a sequence number to detect gaps, not to be reordered.
+    ///
+    /// If `max_parts` messages are buffered and the last one is still not final, for example
+    /// because the final part was dropped at the high water mark, they are discarded and the
+    /// stream yields [`ReassembleError::Overflow`]. Reassembly then starts over with the next
+    /// message. The socket can be taken back with [`Reassemble::into_inner`], discarding the
+    /// buffered parts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_parts` is zero.
+    ///
+    /// [`Multipart`]: ../struct.Multipart.html
+    /// [`ReassembleError::Overflow`]: ../enum.ReassembleError.html#variant.Overflow
+    /// [`Reassemble::into_inner`]: struct.Reassemble.html#method.into_inner
+    pub fn reassemble<F>(self, max_parts: usize, is_final: F) -> Reassemble<F>
+    where
+        F: Fn(&Multipart) -> bool,
+    {
+        assert!(max_parts > 0, "max_parts must be positive");
+        Reassemble {
+            socket: self,
+            is_final,
+            max_parts,
+            parts: 0,
+            buffer: Multipart::default(),
+        }
+    }
+
+    /// Receive a snapshot of the current state followed by the live updates, as the Clone
+    /// pattern of the ØMQ guide does for late-joining subscribers.
+    ///
+    /// `snapshot` fetches the state, usually over a separate [`Request`] socket, and returns its
+    /// messages along with the sequence number of the last update they include. Messages
+    /// received while it runs are buffered, so the socket should be connected and subscribed
+    /// before calling this: updates published during the fetch are then not lost. The returned
+    /// stream yields the snapshot messages, then the buffered and live updates newer than the
+    /// snapshot. The socket can be taken back with [`SnapshotThenSubscribe::into_inner`].
+    ///
+    /// Every update must carry a sequence number, increasing with each message of the
+    /// publisher, which `sequence` reads out of the message. Updates whose number is not greater
+    /// than the last one yielded are already covered and skipped, as are the messages for which
+    /// `sequence` returns `None`. Errors returned by `snapshot` are returned as is, and receive
+    /// errors during the fetch are yielded after the snapshot messages.
+    ///
+    /// [`Request`]: ../request/struct.Request.html
+    /// [`SnapshotThenSubscribe::into_inner`]: struct.SnapshotThenSubscribe.html#method.into_inner
+    pub async fn snapshot_then_subscribe<F, Fut, E, S>(
+        mut self,
+        snapshot: F,
+        sequence: S,
+    ) -> Result<SnapshotThenSubscribe<S>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(Vec<Multipart>, u64), E>>,
+        S: Fn(&Multipart) -> Option<u64>,
+    {
+        let mut fetch = Box::pin(snapshot());
+        let mut buffered = Vec::new();
+        let (state, last) = poll_fn(|cx| {
+            // Drain the socket before checking the snapshot, so that whatever isn't buffered
+            // arrives after the snapshot completes.
+            while let Poll::Ready(Some(msg)) = Pin::new(&mut self).poll_next(cx) {
+                buffered.push(msg);
+            }
+            fetch.as_mut().poll(cx)
+        })
+        .await?;
+
+        let mut stream = SnapshotThenSubscribe {
+            socket: self,
+            sequence,
+            last,
+            queue: state.into_iter().map(Ok).collect(),
+        };
+        for msg in buffered {
+            if msg.as_ref().map_or(true, |msg| stream.is_new(msg)) {
+                stream.queue.push_back(msg);
+            }
+        }
+        Ok(stream)
+    }
+
+    /// Turn the socket into a stream yielding at most `per_sec` messages per second.
+    ///
+    /// Messages are spaced evenly, one every `1 / per_sec` second, using the reactor timer. The
+    /// socket isn't read while the stream waits, so excess messages queue in ØMQ up to the
+    /// receive high water mark, beyond which the publisher drops them for this subscriber. The
+    /// socket can be taken back with [`RateLimited::into_inner`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_sec` is zero.
+    ///
+    /// [`RateLimited::into_inner`]: struct.RateLimited.html#method.into_inner
+    pub fn rate_limited(self, per_sec: u32) -> RateLimited {
+        assert!(
+            per_sec > 0,
+            "rate limit must be at least one message per second"
+        );
+        RateLimited {
+            socket: self,
+            interval: Duration::from_secs(1) / per_sec,
+            timer: None,
+        }
+    }
+
+    /// Turn the socket into a stream yielding each message along with whether more messages are
+    /// ready to be received right away. The socket can be taken back with
+    /// [`WithReadiness::into_inner`].
+    ///
+    /// The flag is ØMQ's `POLLIN` event, read just after receiving the message. A consumer
+    /// draining a backlog can keep going while it is `true`, and yield to other work once it
+    /// turns `false`, without an extra poll of the socket.
+    pub fn with_readiness(self) -> WithReadiness {
+        WithReadiness { socket: self }
+    }
+
+    /// Receive only the messages whose first frame starts with `prefix`.
+    ///
+    /// This is a client-side filter on top of the socket's subscriptions, which are left
+    /// untouched. Messages of other subscribed topics are received and discarded; they are
+    /// lost rather than re-queued.
+    pub fn only_topic<'a>(&'a mut self, prefix: &'a str) -> OnlyTopic<'a> {
+        OnlyTopic {
+            socket: self,
+            prefix,
+        }
+    }
+
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
-        self.0.socket.as_socket()
+        self.inner.socket.as_socket()
+    }
+
+    /// Close the socket, completing once ØMQ has destroyed it.
+    ///
+    /// Messages received by ØMQ and not read yet are discarded.
+    pub async fn close(self) -> Result<(), SocketError> {
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Receive a message if one is available right away, or return `Ok(None)` without waiting.
+    ///
+    /// This is a building block for hand-written poll loops, for example to drain the messages
+    /// already queued before awaiting the next one.
+    pub fn try_recv(&self) -> Result<Option<Multipart>, RecvError> {
+        Ok(self.inner.socket.try_recv()?)
+    }
+
+    /// Receive a single-frame message as a plain `Message`, without allocating a [`Multipart`].
+    ///
+    /// Only the first frame is returned; the frames following it in a multipart message are
+    /// received and discarded, so use the `Stream` for sockets carrying multipart messages.
+    ///
+    /// [`Multipart`]: ../struct.Multipart.html
+    pub async fn recv_msg(&self) -> Result<Message, RecvError> {
+        Ok(poll_fn(|cx| self.inner.socket.recv_msg(cx)).await?)
     }
 
     /// Set the CURVE server flag on the socket.
+    ///
+    /// See [switching roles](../curve/index.html#switching-roles) for a socket which was a
+    /// client before.
     pub fn set_curve_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_curve_server(enabled)?;
         Ok(self)
@@ -108,6 +541,8 @@ impl Subscribe {
     }
 
     /// Set the CURVE server key on the socket.
+    ///
+    /// This makes the socket a CURVE client, resetting the server flag if it was set.
     pub fn set_curve_serverkey(&mut self, key: &[u8]) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_curve_serverkey(key)?;
         Ok(self)
@@ -119,9 +554,30 @@ impl Subscribe {
         Ok(self)
     }
 
+    /// Set the PLAIN server flag on the socket, so that it authenticates its peers with the
+    /// username and password they present, through the ZAP handler of the context.
+    pub fn set_plain_server(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_server(enabled)?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN username presented by the socket, making it a PLAIN client.
+    pub fn set_plain_username(&mut self, username: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_username(Some(username))?;
+        Ok(self)
+    }
+
+    /// Set the PLAIN password presented by the socket, making it a PLAIN client.
+    pub fn set_plain_password(&mut self, password: &str) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_plain_password(Some(password))?;
+        Ok(self)
+    }
+
     /// Set the receive high water mark for the socket.
     /// The high water mark is a hard limit on the maximum number of outstanding messages
     /// ØMQ shall queue in memory for any single peer that the specified socket is communicating with.
+    /// It only applies to connections established afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
     pub fn set_receive_hwm(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
         self.as_raw_socket().set_rcvhwm(value)?;
         Ok(self)
@@ -131,4 +587,418 @@ impl Subscribe {
     pub fn get_receive_hwm(&self) -> Result<i32, zmq::Error> {
         self.as_raw_socket().get_rcvhwm()
     }
+
+    /// Set the largest message in bytes the socket accepts from its peers, or `-1`, the
+    /// default, for no limit.
+    /// This protects against peers sending messages too large to hold in memory. ØMQ doesn't
+    /// deliver an oversized message nor report it to the receiver: it drops the connection of
+    /// the peer which sent it, which shows as a `DISCONNECTED` event on the
+    /// [monitor](../trait.AsRawSocket.html#method.monitor) of the socket. The peer may then
+    /// reconnect. It only applies to connections established afterwards, and not to `inproc`
+    /// connections.
+    pub fn set_max_message_size(&mut self, value: i64) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_maxmsgsize(value)?;
+        Ok(self)
+    }
+
+    /// Get the largest message in bytes the socket accepts from its peers.
+    pub fn get_max_message_size(&self) -> Result<i64, zmq::Error> {
+        self.as_raw_socket().get_maxmsgsize()
+    }
+
+    /// Keep only the most recent message in the inbound queue, dropping the older ones that
+    /// haven't been received yet, which suits feeds where only the latest value matters.
+    ///
+    /// Conflation doesn't support multipart messages: only single-frame messages should be
+    /// sent to a conflating socket. ØMQ reads the option when a connection is set up, so it
+    /// only applies to the connections made afterwards; set it before connecting with
+    /// [`SocketBuilder::with_conflate`].
+    ///
+    /// [`SocketBuilder::with_conflate`]: ../struct.SocketBuilder.html#method.with_conflate
+    pub fn set_conflate(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_conflate(enabled)?;
+        Ok(self)
+    }
+
+    /// Check whether the socket keeps only the most recent message.
+    pub fn get_conflate(&self) -> Result<bool, zmq::Error> {
+        self.as_raw_socket().is_conflate()
+    }
+
+    /// Set the initial reconnection interval in milliseconds, `100` by default, or `-1` to
+    /// disable reconnecting.
+    /// After a peer goes away, ØMQ waits this long before trying to connect to it again. The
+    /// wait doubles after each failed attempt, up to the
+    /// [maximum interval](#method.set_reconnect_interval_max) if one is set.
+    pub fn set_reconnect_interval(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_reconnect_ivl(value)?;
+        Ok(self)
+    }
+
+    /// Get the initial reconnection interval in milliseconds.
+    pub fn get_reconnect_interval(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_reconnect_ivl()
+    }
+
+    /// Set the maximum reconnection interval in milliseconds, the limit of the exponential
+    /// backoff between attempts. The default `0` keeps the initial interval for every attempt.
+    pub fn set_reconnect_interval_max(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_reconnect_ivl_max(value)?;
+        Ok(self)
+    }
+
+    /// Get the maximum reconnection interval in milliseconds.
+    pub fn get_reconnect_interval_max(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_reconnect_ivl_max()
+    }
+
+    /// Set the linger period of the socket, in milliseconds.
+    /// It bounds how long messages still queued when the socket is dropped are kept trying to
+    /// be delivered, during which terminating its context blocks. `-1` waits forever, which is
+    /// ØMQ's default, and `0` discards them right away.
+    pub fn set_linger(&mut self, millis: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_linger(millis)?;
+        Ok(self)
+    }
+
+    /// Get the linger period of the socket, in milliseconds.
+    pub fn get_linger(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_linger()
+    }
+
+    /// Add a metadata property, which ØMQ sends to the peers of the socket when connecting.
+    ///
+    /// ØMQ requires the `key` to start with `X-`. Peers read the property from the messages
+    /// of the connection with `Message::gets`. The metadata is exchanged when a connection is
+    /// made, so this fails with `EINVAL` on a socket its builder connected: add the property
+    /// before connecting with [`SocketBuilder::with_metadata`] instead.
+    ///
+    /// [`SocketBuilder::with_metadata`]: ../struct.SocketBuilder.html#method.with_metadata
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<&mut Self, zmq::Error> {
+        set_metadata(&self.inner.socket, key, value)?;
+        Ok(self)
+    }
+
+    /// Enable or disable IPv6 on the socket, which ØMQ disables by default.
+    /// The option is read when binding or connecting, so it only applies to the endpoints bound
+    /// or connected afterwards, see [`reset_connections`](#method.reset_connections). Prefer
+    /// [`SocketBuilder::with_ipv6`](../struct.SocketBuilder.html#method.with_ipv6) to have it
+    /// apply to the endpoint of the builder.
+    pub fn set_ipv6(&mut self, enabled: bool) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_ipv6(enabled)?;
+        Ok(self)
+    }
+
+    /// Check whether IPv6 is enabled on the socket.
+    pub fn get_ipv6(&self) -> Result<bool, zmq::Error> {
+        self.as_raw_socket().is_ipv6()
+    }
+
+    /// Set the TCP keepalive of the socket: `1` to enable it, `0` to disable it, or `-1`, the
+    /// default, to leave the setting of the operating system.
+    ///
+    /// Keepalives keep idle connections through NATs and firewalls alive and detect dead
+    /// peers. Like the other TCP options, it applies to the connections set up afterwards, see
+    /// [`reset_connections`](#method.reset_connections).
+    pub fn set_tcp_keepalive(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive(value)?;
+        Ok(self)
+    }
+
+    /// Get the TCP keepalive of the socket.
+    pub fn get_tcp_keepalive(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive()
+    }
+
+    /// Set the idle time in seconds before the first keepalive probe, or `-1` for the default
+    /// of the operating system.
+    pub fn set_tcp_keepalive_idle(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_idle(value)?;
+        Ok(self)
+    }
+
+    /// Get the idle time in seconds before the first keepalive probe.
+    pub fn get_tcp_keepalive_idle(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_idle()
+    }
+
+    /// Set the number of unanswered keepalive probes before the connection is dropped, or `-1`
+    /// for the default of the operating system.
+    pub fn set_tcp_keepalive_cnt(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_cnt(value)?;
+        Ok(self)
+    }
+
+    /// Get the number of unanswered keepalive probes before the connection is dropped.
+    pub fn get_tcp_keepalive_cnt(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_cnt()
+    }
+
+    /// Set the interval in seconds between keepalive probes, or `-1` for the default of the
+    /// operating system.
+    pub fn set_tcp_keepalive_intvl(&mut self, value: i32) -> Result<&mut Self, zmq::Error> {
+        self.as_raw_socket().set_tcp_keepalive_intvl(value)?;
+        Ok(self)
+    }
+
+    /// Get the interval in seconds between keepalive probes.
+    pub fn get_tcp_keepalive_intvl(&self) -> Result<i32, zmq::Error> {
+        self.as_raw_socket().get_tcp_keepalive_intvl()
+    }
+
+    /// Drop the connections of the socket and establish them again.
+    ///
+    /// Options such as the high water marks are read when a connection is set up, so setting
+    /// them after `bind` or `connect` leaves the connections already established unchanged.
+    /// This applies them by disconnecting and reconnecting the endpoint of a connected socket,
+    /// or by unbinding and binding again that of a bound socket, whose peers then reconnect.
+    /// Messages queued for the dropped connections are lost.
+    ///
+    /// Only the endpoints given to the builder of the socket are reset: those bound or connected
+    /// through `as_raw_socket` are left as they are. A socket not built by a `SocketBuilder`
+    /// fails with `EINVAL`.
+    pub async fn reset_connections(&mut self) -> Result<&mut Self, zmq::Error> {
+        crate::socket::reset_connections(&self.inner.socket).await?;
+        Ok(self)
+    }
+}
+
+/// Stream returned by [`Subscribe::only_topic`], yielding messages of a single topic.
+///
+/// [`Subscribe::only_topic`]: struct.Subscribe.html#method.only_topic
+pub struct OnlyTopic<'a> {
+    socket: &'a mut Subscribe,
+    prefix: &'a str,
+}
+
+impl Stream for OnlyTopic<'_> {
+    type Item = Result<Multipart, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match futures::ready!(Pin::new(&mut *this.socket).poll_next(cx)) {
+                Some(Ok(msg)) => {
+                    if msg.frame_starts_with(0, this.prefix.as_bytes()) {
+                        return Poll::Ready(Some(Ok(msg)));
+                    }
+                }
+                other => return Poll::Ready(other),
+            }
+        }
+    }
+}
+
+/// Loss of messages reported by [`Subscribe::with_drop_notifications`].
+///
+/// [`Subscribe::with_drop_notifications`]: struct.Subscribe.html#method.with_drop_notifications
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DropEvent {
+    /// The connection to the endpoint was lost, dropping the messages queued on it. The
+    /// subscriber reconnects on its own, missing what is published in the meantime.
+    Disconnected(String),
+    /// The security handshake with the endpoint failed, no message is received from it.
+    HandshakeFailed(String),
+}
+
+/// Stream returned by [`Subscribe::idle_timeout`], ending after a period without messages.
+///
+/// [`Subscribe::idle_timeout`]: struct.Subscribe.html#method.idle_timeout
+pub struct IdleTimeout {
+    socket: Subscribe,
+    timeout: Duration,
+    timer: Delay,
+    idle: bool,
+}
+
+impl IdleTimeout {
+    /// Get the subscribe socket back, still open and subscribed.
+    pub fn into_inner(self) -> Subscribe {
+        self.socket
+    }
+}
+
+impl Stream for IdleTimeout {
+    type Item = Result<Multipart, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.idle {
+            return Poll::Ready(None);
+        }
+
+        if let Poll::Ready(msg) = Pin::new(&mut this.socket).poll_next(cx) {
+            this.timer.reset(this.timeout);
+            return Poll::Ready(msg);
+        }
+
+        futures::ready!(Pin::new(&mut this.timer).poll(cx));
+        this.idle = true;
+        Poll::Ready(None)
+    }
+}
+
+/// Stream returned by [`Subscribe::rate_limited`], pacing the messages it yields.
+///
+/// [`Subscribe::rate_limited`]: struct.Subscribe.html#method.rate_limited
+pub struct RateLimited {
+    socket: Subscribe,
+    interval: Duration,
+    /// Runs between two messages, `None` once the next one may be yielded.
+    timer: Option<Delay>,
+}
+
+impl RateLimited {
+    /// Get the subscribe socket back, still open and subscribed.
+    pub fn into_inner(self) -> Subscribe {
+        self.socket
+    }
+}
+
+impl Stream for RateLimited {
+    type Item = Result<Multipart, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(timer) = &mut this.timer {
+            futures::ready!(Pin::new(timer).poll(cx));
+            this.timer = None;
+        }
+
+        let msg = futures::ready!(Pin::new(&mut this.socket).poll_next(cx));
+        if msg.is_some() {
+            this.timer = Some(sleep(this.interval));
+        }
+        Poll::Ready(msg)
+    }
+}
+
+/// Stream joining the parts of chunked messages, created by [`Subscribe::reassemble`].
+///
+/// [`Subscribe::reassemble`]: struct.Subscribe.html#method.reassemble
+pub struct Reassemble<F> {
+    socket: Subscribe,
+    is_final: F,
+    max_parts: usize,
+    /// Number of messages whose frames are in `buffer`.
+    parts: usize,
+    buffer: Multipart,
+}
+
+impl<F> Reassemble<F> {
+    /// Get the subscribe socket back, still open and subscribed.
+    pub fn into_inner(self) -> Subscribe {
+        self.socket
+    }
+}
+
+impl<F: Fn(&Multipart) -> bool + Unpin> Stream for Reassemble<F> {
+    type Item = Result<Multipart, ReassembleError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let part = match futures::ready!(Pin::new(&mut this.socket).poll_next(cx)) {
+                Some(Ok(part)) => part,
+                Some(Err(error)) => return Poll::Ready(Some(Err(error.into()))),
+                None => return Poll::Ready(None),
+            };
+            let is_final = (this.is_final)(&part);
+            this.buffer.extend(part);
+            this.parts += 1;
+
+            if is_final {
+                this.parts = 0;
+                return Poll::Ready(Some(Ok(std::mem::take(&mut this.buffer))));
+            }
+            if this.parts == this.max_parts {
+                this.parts = 0;
+                this.buffer = Multipart::default();
+                let limit = this.max_parts;
+                return Poll::Ready(Some(Err(ReassembleError::Overflow { limit })));
+            }
+        }
+    }
+}
+
+/// Stream of a snapshot followed by the updates newer than it, created by
+/// [`Subscribe::snapshot_then_subscribe`].
+///
+/// [`Subscribe::snapshot_then_subscribe`]: struct.Subscribe.html#method.snapshot_then_subscribe
+pub struct SnapshotThenSubscribe<S> {
+    socket: Subscribe,
+    sequence: S,
+    /// Sequence number of the last update yielded, or of the snapshot.
+    last: u64,
+    /// Snapshot messages and updates buffered during the snapshot, not yielded yet.
+    queue: VecDeque<Result<Multipart, RecvError>>,
+}
+
+impl<S: Fn(&Multipart) -> Option<u64>> SnapshotThenSubscribe<S> {
+    /// Get the subscribe socket back, still open and subscribed, discarding the messages not
+    /// yielded yet.
+    pub fn into_inner(self) -> Subscribe {
+        self.socket
+    }
+
+    /// Check whether `msg` is newer than the last yielded update, recording it if so.
+    fn is_new(&mut self, msg: &Multipart) -> bool {
+        match (self.sequence)(msg) {
+            Some(sequence) if sequence > self.last => {
+                self.last = sequence;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<S: Fn(&Multipart) -> Option<u64> + Unpin> Stream for SnapshotThenSubscribe<S> {
+    type Item = Result<Multipart, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(msg) = this.queue.pop_front() {
+            return Poll::Ready(Some(msg));
+        }
+        loop {
+            match futures::ready!(Pin::new(&mut this.socket).poll_next(cx)) {
+                Some(Ok(msg)) if !this.is_new(&msg) => continue,
+                msg => return Poll::Ready(msg),
+            }
+        }
+    }
+}
+
+/// Stream of messages paired with the readiness of the socket, created by
+/// [`Subscribe::with_readiness`].
+///
+/// [`Subscribe::with_readiness`]: struct.Subscribe.html#method.with_readiness
+pub struct WithReadiness {
+    socket: Subscribe,
+}
+
+impl WithReadiness {
+    /// Get the subscribe socket back, still open and subscribed.
+    pub fn into_inner(self) -> Subscribe {
+        self.socket
+    }
+}
+
+impl Stream for WithReadiness {
+    type Item = Result<(Multipart, bool), RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let socket = &mut self.get_mut().socket;
+        let msg = match futures::ready!(Pin::new(&mut *socket).poll_next(cx)) {
+            Some(Ok(msg)) => msg,
+            Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+            None => return Poll::Ready(None),
+        };
+        let more = socket
+            .as_raw_socket()
+            .get_events()
+            .map(|events| events.contains(zmq::POLLIN));
+        Poll::Ready(Some(more.map(|more| (msg, more)).map_err(Into::into)))
+    }
 }
@@ -0,0 +1,215 @@
+//! Calls into libzmq the zmq crate has no safe wrapper for, made through zmq-sys.
+
+#![allow(unsafe_code)]
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::os::raw::{c_char, c_int, c_void};
+
+use zmq::Error;
+
+extern "C" {
+    // Part of libzmq since 4.2, but not re-exported by zmq-sys.
+    fn zmq_curve_public(z85_public_key: *mut c_char, z85_secret_key: *const c_char) -> c_int;
+}
+
+/// `ZMQ_SRCFD`, the message property holding the file descriptor of the connection it came
+/// from, which zmq-sys does not re-export.
+const ZMQ_SRCFD: c_int = 2;
+
+/// The error ØMQ reported for the last call of this thread.
+fn last_error() -> Error {
+    Error::from_raw(unsafe { zmq_sys::zmq_errno() })
+}
+
+/// Pointer to the ØMQ socket of a `zmq::Socket`, taken while it is owned, so that options the
+/// zmq crate has no method for can be reached from a shared reference.
+///
+/// It is only valid as long as the `zmq::Socket` it was taken from, and is always kept next to
+/// it.
+pub(crate) struct RawSocket(*mut c_void);
+
+// SAFETY: ØMQ sockets can move between threads, as `zmq::Socket` does, as long as a single
+// thread uses them at a time, which the borrow of the socket they come with ensures.
+unsafe impl Send for RawSocket {}
+
+impl RawSocket {
+    /// Pointer to the ØMQ socket of `socket`.
+    pub(crate) fn of(socket: &mut zmq::Socket) -> Self {
+        Self(socket.as_mut_ptr())
+    }
+
+    /// Set the option numbered `option` to the raw bytes `value`, as `zmq_setsockopt` does.
+    pub(crate) fn set_option(&self, option: i32, value: &[u8]) -> Result<(), Error> {
+        // SAFETY: the socket outlives `self`, and `value` is valid for `value.len()` bytes.
+        let rc = unsafe {
+            zmq_sys::zmq_setsockopt(self.0, option, value.as_ptr() as *const c_void, value.len())
+        };
+        if rc == -1 {
+            return Err(last_error());
+        }
+        Ok(())
+    }
+
+    /// Read at most `len` raw bytes of the option numbered `option`, as `zmq_getsockopt` does.
+    pub(crate) fn get_option(&self, option: i32, len: usize) -> Result<Vec<u8>, Error> {
+        let mut value = vec![0u8; len];
+        let mut size = len;
+        // SAFETY: the socket outlives `self`, and `value` is valid for `size` bytes, the length
+        // ØMQ updates to the one it wrote.
+        let rc = unsafe {
+            zmq_sys::zmq_getsockopt(self.0, option, value.as_mut_ptr() as *mut c_void, &mut size)
+        };
+        if rc == -1 {
+            return Err(last_error());
+        }
+        value.truncate(size);
+        Ok(value)
+    }
+
+    /// Receive a frame, along with the file descriptor of the connection it came from, or
+    /// `None` for connections without one, such as `inproc` ones.
+    pub(crate) fn recv_with_source(
+        &self,
+        flags: i32,
+    ) -> Result<(zmq::Message, Option<u32>), Error> {
+        let mut msg = MaybeUninit::<zmq_sys::zmq_msg_t>::uninit();
+        // SAFETY: `msg` is initialized by `zmq_msg_init`, which can't fail, before being used, and
+        // closed on failure; on success, the `zmq::Message` owns and closes it.
+        unsafe {
+            zmq_sys::zmq_msg_init(msg.as_mut_ptr());
+            let mut msg = msg.assume_init();
+            if zmq_sys::zmq_msg_recv(&mut msg, self.0, flags) == -1 {
+                let error = last_error();
+                zmq_sys::zmq_msg_close(&mut msg);
+                return Err(error);
+            }
+            let fd = zmq_sys::zmq_msg_get(&msg, ZMQ_SRCFD);
+            Ok((zmq::Message::from_msg(msg), u32::try_from(fd).ok()))
+        }
+    }
+}
+
+/// Derive the Z85-encoded public key of the Z85-encoded CURVE secret key `secret`.
+pub(crate) fn curve_public(secret: &str) -> Result<String, Error> {
+    if secret.len() != 40 {
+        return Err(Error::EINVAL);
+    }
+    let secret = CString::new(secret).map_err(|_| Error::EINVAL)?;
+    // 40 characters and the terminating NUL.
+    let mut public = [0u8; 41];
+    // SAFETY: `secret` is a 40 character key followed by NUL, and `public` has room for the 40
+    // characters of the public key and its NUL.
+    let rc = unsafe { zmq_curve_public(public.as_mut_ptr() as *mut c_char, secret.as_ptr()) };
+    if rc == -1 {
+        return Err(last_error());
+    }
+    Ok(String::from_utf8_lossy(&public[..40]).into_owned())
+}
+
+/// Decode the Z85 text `text`, whose length is a multiple of 5, failing with `EINVAL` on
+/// characters outside of the Z85 alphabet.
+pub(crate) fn z85_decode(text: &str) -> Result<Vec<u8>, Error> {
+    let text = CString::new(text).map_err(|_| Error::EINVAL)?;
+    let mut data = vec![0u8; text.as_bytes().len() / 5 * 4];
+    // SAFETY: `text` is NUL-terminated, and `data` has room for the 4 bytes of every 5
+    // characters.
+    let decoded = unsafe { zmq_sys::zmq_z85_decode(data.as_mut_ptr(), text.as_ptr()) };
+    if decoded.is_null() {
+        return Err(Error::EINVAL);
+    }
+    Ok(data)
+}
+
+/// ØMQ context created through zmq-sys, so that the options the zmq crate has no method for can
+/// be reached. It is terminated on drop, which waits for its sockets to be closed.
+pub(crate) struct RawContext(*mut c_void);
+
+// SAFETY: ØMQ contexts are thread safe.
+unsafe impl Send for RawContext {}
+unsafe impl Sync for RawContext {}
+
+impl RawContext {
+    /// Create a new ØMQ context.
+    pub(crate) fn new() -> Self {
+        // SAFETY: `zmq_ctx_new` takes no argument, and only fails when out of memory.
+        let ctx = unsafe { zmq_sys::zmq_ctx_new() };
+        assert!(!ctx.is_null(), "cannot create a ØMQ context");
+        Self(ctx)
+    }
+
+    /// Read the context option numbered `option`, as `zmq_ctx_get` does.
+    pub(crate) fn get(&self, option: u32) -> Result<i32, Error> {
+        // SAFETY: the context is alive as long as `self`.
+        let value = unsafe { zmq_sys::zmq_ctx_get(self.0, option as c_int) };
+        if value == -1 {
+            return Err(last_error());
+        }
+        Ok(value)
+    }
+
+    /// Set the context option numbered `option` to `value`, as `zmq_ctx_set` does.
+    pub(crate) fn set(&self, option: u32, value: i32) -> Result<(), Error> {
+        // SAFETY: the context is alive as long as `self`.
+        if unsafe { zmq_sys::zmq_ctx_set(self.0, option as c_int, value) } == -1 {
+            return Err(last_error());
+        }
+        Ok(())
+    }
+
+    /// Shut the context down without terminating it, as `zmq_ctx_shutdown` does.
+    pub(crate) fn shutdown(&self) -> Result<(), Error> {
+        // SAFETY: the context is alive as long as `self`.
+        if unsafe { zmq_sys::zmq_ctx_shutdown(self.0) } == -1 {
+            return Err(last_error());
+        }
+        Ok(())
+    }
+
+    /// Create a socket of type `socket_type` in the context.
+    ///
+    /// The socket doesn't keep the context alive: dropping the context before the socket blocks
+    /// until the socket is closed.
+    pub(crate) fn socket(&self, socket_type: zmq::SocketType) -> Result<zmq::Socket, Error> {
+        // SAFETY: the context is alive as long as `self`.
+        let socket = unsafe { zmq_sys::zmq_socket(self.0, socket_type_to_raw(socket_type)) };
+        if socket.is_null() {
+            return Err(last_error());
+        }
+        // SAFETY: `socket` was just created, and is owned by the `zmq::Socket` alone.
+        Ok(unsafe { zmq::Socket::from_raw(socket) })
+    }
+}
+
+impl Drop for RawContext {
+    fn drop(&mut self) {
+        // SAFETY: the context is only terminated here, once. Terminating it is interrupted by
+        // signals, and is then attempted again.
+        while unsafe { zmq_sys::zmq_ctx_term(self.0) } == -1 {
+            if last_error() != Error::EINTR {
+                break;
+            }
+        }
+    }
+}
+
+/// The ØMQ constant of `socket_type`, which the zmq crate keeps private.
+fn socket_type_to_raw(socket_type: zmq::SocketType) -> c_int {
+    use zmq::SocketType::*;
+
+    let raw = match socket_type {
+        PAIR => zmq_sys::ZMQ_PAIR,
+        PUB => zmq_sys::ZMQ_PUB,
+        SUB => zmq_sys::ZMQ_SUB,
+        REQ => zmq_sys::ZMQ_REQ,
+        REP => zmq_sys::ZMQ_REP,
+        DEALER => zmq_sys::ZMQ_DEALER,
+        ROUTER => zmq_sys::ZMQ_ROUTER,
+        PULL => zmq_sys::ZMQ_PULL,
+        PUSH => zmq_sys::ZMQ_PUSH,
+        XPUB => zmq_sys::ZMQ_XPUB,
+        XSUB => zmq_sys::ZMQ_XSUB,
+        STREAM => zmq_sys::ZMQ_STREAM,
+    };
+    raw as c_int
+}
@@ -0,0 +1,35 @@
+//! Timestamp frame of the TTL envelope used by `Publish::send_with_ttl`.
+//!
+//! The frame holds 16 bytes: the send time in milliseconds since the Unix epoch, then the time
+//! to live in milliseconds, both as big-endian `u64`.
+
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use zmq::Message;
+
+/// Size of the timestamp frame.
+const TTL_FRAME_LEN: usize = 16;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Build the timestamp frame of a message sent now and living for `ttl`.
+pub(crate) fn timestamp_frame(ttl: Duration) -> Message {
+    let ttl = ttl.as_millis().min(u64::MAX as u128) as u64;
+    let mut frame = [0; TTL_FRAME_LEN];
+    frame[..8].copy_from_slice(&now_millis().to_be_bytes());
+    frame[8..].copy_from_slice(&ttl.to_be_bytes());
+    Message::from(&frame[..])
+}
+
+/// Check whether the message holding `frame` has expired. Returns `None` for a malformed frame.
+pub(crate) fn is_expired(frame: &[u8]) -> Option<bool> {
+    let frame: &[u8; TTL_FRAME_LEN] = frame.try_into().ok()?;
+    let sent_at = u64::from_be_bytes(frame[..8].try_into().ok()?);
+    let ttl = u64::from_be_bytes(frame[8..].try_into().ok()?);
+    Some(sent_at.saturating_add(ttl) < now_millis())
+}
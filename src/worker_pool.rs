@@ -0,0 +1,75 @@
+//! ROUTER/DEALER broker spreading requests over a pool of workers
+//!
+//! A [`WorkerPool`] binds a ROUTER frontend, which clients ([`request`] or [`dealer`] sockets)
+//! connect to, and a DEALER backend, which workers ([`reply`] sockets) connect to. Requests are
+//! load-balanced over the connected workers and replies are routed back to the right client,
+//! with the envelope handled by the ROUTER and DEALER sockets themselves.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{context::Context, Result, WorkerPool};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let context = Context::new();
+//!     let pool = WorkerPool::bind(&context, "tcp://127.0.0.1:5555", "inproc://workers")?;
+//!
+//!     // Workers connect to `inproc://workers` with a reply socket of the same context.
+//!     pool.run().await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`WorkerPool`]: struct.WorkerPool.html
+//! [`request`]: ../request/index.html
+//! [`dealer`]: ../dealer/index.html
+//! [`reply`]: ../reply/index.html
+
+use std::vec::IntoIter;
+
+use zmq::Message;
+
+use crate::{
+    context::Context, dealer::dealer_in, proxy::proxy, router::router_in, Dealer, RecvError,
+    Router, SocketError,
+};
+
+/// A ROUTER frontend and a DEALER backend proxied to each other.
+pub struct WorkerPool {
+    frontend: Router<IntoIter<Message>, Message>,
+    backend: Dealer<IntoIter<Message>, Message>,
+}
+
+impl WorkerPool {
+    /// Bind the ROUTER frontend for clients and the DEALER backend for workers.
+    pub fn bind(context: &Context, frontend: &str, backend: &str) -> Result<Self, SocketError> {
+        Ok(Self {
+            frontend: router_in(context, frontend)?.bind()?,
+            backend: dealer_in(context, backend)?.bind()?,
+        })
+    }
+
+    /// Represent the ROUTER frontend as `Socket` from zmq crate.
+    pub fn frontend(&self) -> &zmq::Socket {
+        self.frontend.as_raw_socket()
+    }
+
+    /// Represent the DEALER backend as `Socket` from zmq crate.
+    pub fn backend(&self) -> &zmq::Socket {
+        self.backend.as_raw_socket()
+    }
+
+    /// Forward requests to the workers and replies to the clients until the context is shut down.
+    ///
+    /// The forwarding runs on the reactor with [`proxy`], so it doesn't block the executor.
+    /// Dropping the returned future stops the forwarding and closes both sockets.
+    ///
+    /// [`proxy`]: ../fn.proxy.html
+    pub async fn run(self) -> Result<(), zmq::Error> {
+        match proxy(self.frontend, self.backend).await {
+            Err(RecvError::ContextTerminated) => Ok(()),
+            result => result.map_err(Into::into),
+        }
+    }
+}
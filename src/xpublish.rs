@@ -30,28 +30,79 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use futures::future::poll_fn;
+
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Broker, Multipart, MultipartIter, SocketBuilder},
+    endpoint::Endpoint,
+    reactor::AsRawSocket,
+    socket::{
+        close_socket, Broker, FromParts, Multipart, MultipartIter, SendQueue, SocketBuilder,
+        SocketParts,
+    },
     SendError, Sink, SocketError, Stream,
 };
 use zmq::{Message, SocketType};
 
 /// Create a ZMQ socket with XPUB type
-pub fn xpublish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, XPublish<I, T>>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn xpublish<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, XPublish<I, T>>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::XPUB, endpoint))
 }
 
+/// Create a ZMQ socket with XPUB type, built with `context`
+///
+/// This is [`xpublish`](fn.xpublish.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn xpublish_in<'a, I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, XPublish<I, T>>, SocketError> {
+    Ok(xpublish(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with XPUB type
-pub struct XPublish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Broker<I, T>);
+pub struct XPublish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(pub(crate) Broker<I, T>);
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> XPublish<I, T> {
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    /// Close the socket gracefully, completing once ØMQ is done with it.
+    ///
+    /// Messages waiting in the send queue are flushed first. ØMQ then keeps delivering the
+    /// messages it holds for the linger period of the socket, see `set_linger` on the raw
+    /// socket, and the returned future completes once they are all delivered or the linger
+    /// period is over, when ØMQ destroys the socket. With the default infinite linger period,
+    /// this waits until a peer receives the messages, so set a finite one to bound the wait.
+    pub async fn close(mut self) -> Result<(), SocketError> {
+        poll_fn(|cx| Pin::new(&mut self.0).poll_flush(cx)).await?;
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+
+    /// Number of messages dropped so far because the send queue was full.
+    ///
+    /// This stays at zero unless the socket was built with a dropping [`SendOverflow`] policy.
+    ///
+    /// [`SendOverflow`]: ../enum.SendOverflow.html
+    pub fn dropped_messages(&self) -> u64 {
+        self.0.queue.dropped()
+    }
 }
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for XPublish<I, T> {
@@ -91,9 +142,17 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for XPublish<I, T>
 
 impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for XPublish<I, T> {
     fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> FromParts for XPublish<I, T> {
+    const SEND_QUEUE: bool = true;
+
+    fn from_parts(parts: SocketParts) -> Self {
         Self(Broker {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
+            socket: parts.socket,
+            queue: SendQueue::new(parts.send_queue),
         })
     }
 }
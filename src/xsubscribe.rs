@@ -37,26 +37,54 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use zmq::SocketType;
+use futures::future::poll_fn;
+use zmq::{Message, SocketType};
 
 use crate::{
-    reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, Receiver, SocketBuilder},
-    RecvError, SocketError, Stream, SubscribeError,
+    endpoint::Endpoint,
+    reactor::AsRawSocket,
+    socket::{
+        close_socket, FromParts, Multipart, MultipartIter, Receiver, SocketBuilder, SocketParts,
+    },
+    RecvError, SendError, SocketError, Stream, SubscribeError,
 };
 
 /// Create a ZMQ socket with XSUB type
-pub fn xsubscribe(endpoint: &str) -> Result<SocketBuilder<'_, XSubscribe>, SocketError> {
+///
+/// The endpoint is validated right away, see [`Endpoint`](../endpoint/struct.Endpoint.html).
+pub fn xsubscribe<'a>(
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, XSubscribe>, SocketError> {
+    let endpoint = endpoint.try_into().map_err(Into::into)?;
     Ok(SocketBuilder::new(SocketType::XSUB, endpoint))
 }
 
+/// Create a ZMQ socket with XSUB type, built with `context`
+///
+/// This is [`xsubscribe`](fn.xsubscribe.html) followed by
+/// [`with_context`](../struct.SocketBuilder.html#method.with_context).
+pub fn xsubscribe_in<'a>(
+    context: &'a crate::context::Context,
+    endpoint: impl TryInto<Endpoint, Error = impl Into<SocketError>>,
+) -> Result<SocketBuilder<'a, XSubscribe>, SocketError> {
+    Ok(xsubscribe(endpoint)?.with_context(context))
+}
+
 /// The async wrapper of ZMQ socket with XSUB type
-pub struct XSubscribe(Receiver);
+pub struct XSubscribe(pub(crate) Receiver);
 
 impl From<zmq::Socket> for XSubscribe {
     fn from(socket: zmq::Socket) -> Self {
+        Self::from_parts(socket.into())
+    }
+}
+
+impl FromParts for XSubscribe {
+    const SEND_QUEUE: bool = false;
+
+    fn from_parts(parts: SocketParts) -> Self {
         Self(Receiver {
-            socket: ZmqSocket::from(socket),
+            socket: parts.socket,
         })
     }
 }
@@ -73,17 +101,74 @@ impl Stream for XSubscribe {
 
 impl XSubscribe {
     /// Subscribe a topic to the socket
+    ///
+    /// XSUB sockets have no subscription option, so this sends the subscription message right
+    /// away, as [`send_subscription`](#method.send_subscription) does. It never waits: XSUB
+    /// sockets drop nothing upstream, and they send their subscriptions again to the publishers
+    /// they connect to later.
+    ///
+    /// Fails with `SubscribeError::ContextTerminated` once the context of the socket has been
+    /// shut down or terminated.
     pub fn set_subscribe(&self, topic: &str) -> Result<(), SubscribeError> {
-        Ok(self.as_raw_socket().set_subscribe(topic.as_bytes())?)
+        // Process pending commands first, so that a terminated context is reported.
+        self.as_raw_socket().get_events()?;
+        let frame = subscription_frame(1, topic);
+        Ok(self.as_raw_socket().send(frame, zmq::DONTWAIT)?)
     }
 
-    /// Remove a topic from the socket
+    /// Remove a topic from the socket, sending the unsubscription message right away.
     pub fn set_unsubscribe(&self, topic: &str) -> Result<(), SubscribeError> {
-        Ok(self.as_raw_socket().set_unsubscribe(topic.as_bytes())?)
+        // Process pending commands first, so that a terminated context is reported.
+        self.as_raw_socket().get_events()?;
+        let frame = subscription_frame(0, topic);
+        Ok(self.as_raw_socket().send(frame, zmq::DONTWAIT)?)
+    }
+
+    /// Send a subscription message (`\x01` followed by the topic) upstream.
+    ///
+    /// This lets the XSUB half of a proxy forward subscriptions to the publishers explicitly.
+    pub async fn send_subscription(&self, topic: &str) -> Result<(), SendError> {
+        self.send_subscription_frame(1, topic).await
+    }
+
+    /// Send an unsubscription message (`\x00` followed by the topic) upstream.
+    pub async fn send_unsubscription(&self, topic: &str) -> Result<(), SendError> {
+        self.send_subscription_frame(0, topic).await
+    }
+
+    async fn send_subscription_frame(&self, flag: u8, topic: &str) -> Result<(), SendError> {
+        let mut msg: MultipartIter<_, _> = subscription_frame(flag, topic).into();
+        poll_fn(|cx| self.0.socket.send(cx, &mut msg)).await?;
+        Ok(())
     }
 
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    /// Close the socket, completing once ØMQ has destroyed it.
+    ///
+    /// Messages received by ØMQ and not read yet are discarded.
+    pub async fn close(self) -> Result<(), SocketError> {
+        close_socket(self).await
+    }
+
+    /// Close the socket right away, discarding the messages it has not sent yet.
+    ///
+    /// The linger period is set to zero before the socket is dropped, so neither this nor
+    /// terminating its context waits for pending messages to be delivered, which suits test
+    /// teardown and crash recovery. The socket is deregistered from the reactor as it is dropped.
+    pub fn close_now(self) {
+        // A failure means the context is terminated already, and nothing can linger anyway.
+        let _ = self.as_raw_socket().set_linger(0);
+    }
+}
+
+/// Build a subscription message: `flag`, 1 to subscribe or 0 to unsubscribe, then the topic.
+fn subscription_frame(flag: u8, topic: &str) -> Message {
+    let mut frame = Vec::with_capacity(topic.len() + 1);
+    frame.push(flag);
+    frame.extend_from_slice(topic.as_bytes());
+    Message::from(frame)
 }
@@ -0,0 +1,172 @@
+use std::vec::IntoIter;
+
+use async_zmq::{
+    context::Context, pair, pull, reply_in, request_in, AsRawSocket, Result, SinkExt, SocketError,
+    SocketOptions, StreamExt,
+};
+
+#[async_std::test]
+async fn sockets_remaining_decrements() -> Result<()> {
+    let context = Context::new();
+    context.set_max_sockets(3)?;
+    assert_eq!(context.sockets_remaining(), 3);
+
+    let first = pull("inproc://remaining_first")?
+        .with_context(&context)
+        .bind()?;
+    assert_eq!(context.sockets_remaining(), 2);
+    let _second = pull("inproc://remaining_second")?
+        .with_context(&context)
+        .bind()?;
+    let _third = pull("inproc://remaining_third")?
+        .with_context(&context)
+        .bind()?;
+    assert_eq!(context.sockets_remaining(), 0);
+
+    let fourth = pull("inproc://remaining_fourth")?
+        .with_context(&context)
+        .bind();
+    assert!(matches!(fourth, Err(SocketError::SocketLimitReached)));
+
+    drop(first);
+    assert_eq!(context.sockets_remaining(), 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn sockets_remaining_counts_monitors() -> Result<()> {
+    let context = Context::new();
+    context.set_max_sockets(10)?;
+
+    let pull = pull("inproc://remaining_monitored")?
+        .with_context(&context)
+        .bind()?;
+    assert_eq!(context.sockets_remaining(), 9);
+    let _events = pull.monitor(zmq::SocketEvent::ALL)?;
+    assert_eq!(context.sockets_remaining(), 7);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn ref_count_tracks_clones_and_sockets() -> Result<()> {
+    let context = Context::new();
+    assert_eq!(context.ref_count(), 1);
+
+    let clone = context.clone();
+    assert_eq!(context.ref_count(), 2);
+
+    let pull = pull("inproc://ref_count")?.with_context(&context).bind()?;
+    assert_eq!(clone.ref_count(), 3);
+
+    drop(pull);
+    drop(clone);
+    assert_eq!(context.ref_count(), 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn default_context_shares_inproc() -> Result<()> {
+    let endpoint = "inproc://default-context-pair";
+    let mut bound = pair::<IntoIter<&str>, &str>(endpoint)?.bind()?;
+    let mut connected = pair::<IntoIter<&str>, &str>(endpoint)?.connect()?;
+
+    connected.send(vec!["hello"].into()).await?;
+    let msg = bound.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("hello"));
+
+    let name = "default-context-pair".to_string();
+    assert!(Context::default().inproc_endpoints().contains(&name));
+    drop(bound);
+    assert!(!Context::default().inproc_endpoints().contains(&name));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn sockets_inherit_default_options() -> Result<()> {
+    let context = Context::new();
+    context.set_default_socket_options(SocketOptions {
+        linger: Some(0),
+        ipv6: Some(true),
+        ..Default::default()
+    });
+
+    let inherited = pull("inproc://default_options_inherited")?
+        .with_context(&context)
+        .bind()?;
+    assert_eq!(inherited.as_raw_socket().get_linger()?, 0);
+    assert!(inherited.as_raw_socket().is_ipv6()?);
+
+    let overridden = pull("inproc://default_options_overridden")?
+        .with_context(&context)
+        .configure(|socket| socket.set_linger(250))
+        .bind()?;
+    assert_eq!(overridden.as_raw_socket().get_linger()?, 250);
+    assert!(overridden.as_raw_socket().is_ipv6()?);
+
+    // Other contexts keep the ØMQ defaults.
+    let unrelated = pull("inproc://default_options_unrelated")?
+        .with_context(Context::new())
+        .bind()?;
+    assert_eq!(unrelated.as_raw_socket().get_linger()?, -1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn constructors_in_context() -> Result<()> {
+    let context = Context::new();
+    let reply =
+        reply_in::<IntoIter<&str>, &str>(&context, "inproc://constructors_in_context")?.bind()?;
+    let request = request_in::<IntoIter<&str>, &str>(&context, "inproc://constructors_in_context")?
+        .connect()?;
+    assert_eq!(context.inproc_endpoints(), vec!["constructors_in_context"]);
+
+    request.send(vec!["ping"]).await?;
+    assert_eq!(reply.recv().await?[0].as_str(), Some("ping"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn zmq_context_builds_sockets() -> Result<()> {
+    let context = zmq::Context::new();
+    let reply = async_zmq::reply::<IntoIter<&str>, &str>("inproc://zmq_context")?
+        .with_context(&context)
+        .bind()?;
+    let request = async_zmq::request::<IntoIter<&str>, &str>("inproc://zmq_context")?
+        .with_context(&context)
+        .connect()?;
+
+    request.send(vec!["ping"]).await?;
+    assert_eq!(reply.recv().await?[0].as_str(), Some("ping"));
+
+    Ok(())
+}
+
+#[test]
+fn balanced_affinity_spreads_sockets() -> Result<()> {
+    let context = Context::new();
+    context.set_io_threads(4)?;
+
+    // More sockets than threads: one thread each, in turn.
+    let masks: Vec<_> = (0..8).map(|i| context.balanced_affinity(i, 8)).collect();
+    assert_eq!(masks, [1, 2, 4, 8, 1, 2, 4, 8]);
+
+    // Fewer sockets than threads: disjoint shares covering every thread.
+    let masks: Vec<_> = (0..3).map(|i| context.balanced_affinity(i, 3)).collect();
+    assert_eq!(masks.iter().fold(0, |all, mask| all | mask), 0b1111);
+    assert!(masks.iter().all(|mask| *mask != 0));
+    assert_eq!(masks.iter().map(|mask| mask.count_ones()).sum::<u32>(), 4);
+
+    let pull = pull("inproc://balanced_affinity")?
+        .with_context(&context)
+        .configure(|socket| socket.set_affinity(context.balanced_affinity(1, 2)))
+        .bind()?;
+    assert_eq!(pull.as_raw_socket().get_affinity()?, 0b1100);
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::time::Duration;
 use std::thread;
-use async_zmq::{Result, Context, CurveKeyPair, Message, StreamExt, SinkExt};
+use async_zmq::{Result, context::Context, CurveKeyPair, Message, StreamExt, SinkExt};
 use std::vec::IntoIter;
 
 // Helper function to check if CURVE is supported
@@ -28,6 +28,62 @@ fn test_curve_key_pair_generation() -> Result<()> {
     Ok(())
 }
 
+// Test rebuilding a CURVE key pair from its Z85-encoded keys
+#[test]
+fn test_curve_key_pair_from_z85() -> Result<()> {
+    if !check_curve_support() {
+        println!("Skipping test: CURVE security not supported");
+        return Ok(());
+    }
+
+    let pair = CurveKeyPair::new()?;
+    let public = zmq::z85_encode(&pair.public_key).unwrap();
+    let secret = zmq::z85_encode(&pair.secret_key).unwrap();
+
+    let derived = CurveKeyPair::from_secret_key(&secret)?;
+    assert_eq!(derived.public_key, pair.public_key);
+    assert_eq!(derived.secret_key, pair.secret_key);
+
+    let loaded = CurveKeyPair::from_z85(&public, &secret)?;
+    assert_eq!(loaded.public_key, pair.public_key);
+
+    // Malformed keys and mismatched pairs are rejected
+    assert_eq!(CurveKeyPair::from_secret_key("too short").unwrap_err(), zmq::Error::EINVAL);
+    let other = zmq::z85_encode(&CurveKeyPair::new()?.public_key).unwrap();
+    assert_eq!(CurveKeyPair::from_z85(&other, &secret).unwrap_err(), zmq::Error::EINVAL);
+
+    Ok(())
+}
+
+// Test the Z85 helpers of the curve module
+#[test]
+fn test_z85_round_trip() -> Result<()> {
+    let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+    let text = async_zmq::curve::z85_encode(&data)?;
+    assert_eq!(text, "HelloWorld");
+    assert_eq!(async_zmq::curve::z85_decode(&text)?, data);
+    assert_eq!(async_zmq::curve::z85_decode("\"\"\"\"\"").unwrap_err(), zmq::Error::EINVAL);
+    assert_eq!(async_zmq::curve::z85_decode("Hello Wor~").unwrap_err(), zmq::Error::EINVAL);
+
+    if !check_curve_support() {
+        return Ok(());
+    }
+    let pair = CurveKeyPair::new()?;
+    let public = async_zmq::curve::z85_encode(&pair.public_key)?;
+    assert_eq!(public.len(), 40);
+    assert_eq!(async_zmq::curve::z85_decode(&public)?, pair.public_key);
+
+    Ok(())
+}
+
+// Test that invalid lengths are reported as errors
+#[test]
+fn test_z85_invalid_length() {
+    assert_eq!(async_zmq::curve::z85_encode(&[1, 2, 3]).unwrap_err(), zmq::Error::EINVAL);
+    assert_eq!(async_zmq::curve::z85_decode("Hello!").unwrap_err(), zmq::Error::EINVAL);
+    assert_eq!(async_zmq::curve::z85_decode("Hell\0").unwrap_err(), zmq::Error::EINVAL);
+}
+
 // Test CURVE properties - setting and verifying values
 #[test]
 fn test_curve_socket_properties() -> Result<()> {
@@ -42,7 +98,7 @@ fn test_curve_socket_properties() -> Result<()> {
     // Publisher - use the correct generic types
     {
         // Use explicit type annotation to help the compiler
-        let socket: async_zmq::Publish<std::vec::IntoIter<Message>, Message> = 
+        let mut socket: async_zmq::Publish<std::vec::IntoIter<Message>, Message> = 
             async_zmq::publish("tcp://127.0.0.1:0")?.with_context(&ctx).bind()?;
         let pair = CurveKeyPair::new()?;
         
@@ -57,7 +113,7 @@ fn test_curve_socket_properties() -> Result<()> {
     
     // Subscriber
     {
-        let socket = async_zmq::subscribe("tcp://127.0.0.1:0")?.with_context(&ctx).connect()?;
+        let mut socket = async_zmq::subscribe("tcp://127.0.0.1:0")?.with_context(&ctx).connect()?;
         let pair = CurveKeyPair::new()?;
         let server_pair = CurveKeyPair::new()?;
         
@@ -71,7 +127,8 @@ fn test_curve_socket_properties() -> Result<()> {
     
     // Request
     {
-        let socket = async_zmq::request("tcp://127.0.0.1:0")?.with_context(&ctx).connect()?;
+        let mut socket: async_zmq::Request<IntoIter<Message>, Message> =
+            async_zmq::request("tcp://127.0.0.1:0")?.with_context(&ctx).connect()?;
         let pair = CurveKeyPair::new()?;
         let server_pair = CurveKeyPair::new()?;
         
@@ -83,7 +140,8 @@ fn test_curve_socket_properties() -> Result<()> {
     
     // Reply
     {
-        let socket = async_zmq::reply("tcp://127.0.0.1:0")?.with_context(&ctx).bind()?;
+        let mut socket: async_zmq::Reply<IntoIter<Message>, Message> =
+            async_zmq::reply("tcp://127.0.0.1:0")?.with_context(&ctx).bind()?;
         let pair = CurveKeyPair::new()?;
         
         // Server mode
@@ -96,6 +154,7 @@ fn test_curve_socket_properties() -> Result<()> {
 }
 
 // Helper function to set ZAP domain safely
+#[allow(dead_code)]
 fn set_zap_domain(socket: &zmq::Socket, domain: &str) -> std::result::Result<(), zmq::Error> {
     socket.set_zap_domain(domain)
 }
@@ -188,13 +247,13 @@ async fn test_req_rep_curve() -> Result<()> {
     let client_pair = CurveKeyPair::new()?;
     
     // Create server socket (REP)
-    let replier = async_zmq::reply(uri)?.with_context(&ctx).bind()?;
+    let mut replier = async_zmq::reply(uri)?.with_context(&ctx).bind()?;
     replier.set_curve_server(true)?;
     replier.set_curve_secretkey(&server_pair.secret_key)?;
     replier.set_curve_publickey(&server_pair.public_key)?;
     
     // Create client socket (REQ)
-    let requester = async_zmq::request(uri)?.with_context(&ctx).connect()?;
+    let mut requester = async_zmq::request(uri)?.with_context(&ctx).connect()?;
     requester.set_curve_serverkey(&server_pair.public_key)?;
     requester.set_curve_publickey(&client_pair.public_key)?;
     requester.set_curve_secretkey(&client_pair.secret_key)?;
@@ -370,7 +429,7 @@ async fn test_zap_req_rep() -> Result<()> {
     let client_pair = CurveKeyPair::new()?;
     
     // Create server socket (REP) with ZAP domain - use explicit type
-    let replier: async_zmq::Reply<std::vec::IntoIter<Message>, Message> = 
+    let mut replier: async_zmq::Reply<std::vec::IntoIter<Message>, Message> = 
         async_zmq::reply(uri)?.with_context(&ctx).bind()?;
     replier.set_curve_server(true)?;
     replier.set_curve_secretkey(&server_pair.secret_key)?;
@@ -381,7 +440,7 @@ async fn test_zap_req_rep() -> Result<()> {
     raw_socket.set_zap_domain("global")?;
     
     // Create client socket (REQ) - use explicit type
-    let requester: async_zmq::Request<std::vec::IntoIter<Message>, Message> = 
+    let mut requester: async_zmq::Request<std::vec::IntoIter<Message>, Message> = 
         async_zmq::request(uri)?.with_context(&ctx).connect()?;
     requester.set_curve_serverkey(&server_pair.public_key)?;
     requester.set_curve_publickey(&client_pair.public_key)?;
@@ -0,0 +1,27 @@
+#![cfg(feature = "serde")]
+
+use async_zmq::{CurveKeyPair, Result};
+
+#[test]
+fn key_pair_round_trips_through_json() -> Result<()> {
+    if !zmq::has("curve").unwrap_or(false) {
+        return Ok(());
+    }
+
+    let pair = CurveKeyPair::new()?;
+    let json = serde_json::to_string(&pair).unwrap();
+    assert!(json.contains(&async_zmq::curve::z85_encode(&pair.secret_key)?));
+
+    let loaded: CurveKeyPair = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.public_key, pair.public_key);
+    assert_eq!(loaded.secret_key, pair.secret_key);
+
+    Ok(())
+}
+
+#[test]
+fn malformed_key_pair_is_rejected() {
+    let json = r#"{"public_key":"short","secret_key":"short"}"#;
+    assert!(serde_json::from_str::<CurveKeyPair>(json).is_err());
+    assert!(serde_json::from_str::<CurveKeyPair>(r#"{"public_key":"x"}"#).is_err());
+}
@@ -0,0 +1,53 @@
+use std::vec::IntoIter;
+
+use async_zmq::{context::Context, Duplex, Error, Message, Multipart, Result};
+
+/// Send `request` on any bidirectional socket and wait for the answer.
+async fn round_trip<S: Duplex>(socket: &mut S, request: Multipart) -> Result<Multipart>
+where
+    Error: From<S::SendError> + From<S::RecvError>,
+{
+    socket.send(request).await?;
+    Ok(socket.recv().await?)
+}
+
+/// Send back every message received, up to `count` of them.
+async fn echo<S: Duplex>(socket: &mut S, count: usize) -> Result<()>
+where
+    Error: From<S::SendError> + From<S::RecvError>,
+{
+    for _ in 0..count {
+        let msg = socket.recv().await?;
+        socket.send(msg).await?;
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn generic_over_request_and_dealer() -> Result<()> {
+    let context = Context::new();
+
+    let (mut request, mut reply) = async_zmq::req_rep_pair::<IntoIter<Message>, Message>(&context)?;
+    let (answer, served) = futures::join!(
+        round_trip(&mut request, Multipart::of(["ping"])),
+        echo(&mut reply, 1),
+    );
+    served?;
+    assert_eq!(answer?[0].as_str(), Some("ping"));
+
+    let endpoint = "inproc://duplex-router";
+    let mut router = async_zmq::router::<IntoIter<Message>, Message>(endpoint)?
+        .with_context(&context)
+        .bind()?;
+    let mut dealer = async_zmq::dealer::<IntoIter<Message>, Message>(endpoint)?
+        .with_context(&context)
+        .connect()?;
+    let (answer, served) = futures::join!(
+        round_trip(&mut dealer, Multipart::of(["ping"])),
+        echo(&mut router, 1),
+    );
+    served?;
+    assert_eq!(answer?[0].as_str(), Some("ping"));
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use async_zmq::{pull, Endpoint, EndpointError, Result, SocketError, Transport};
+
+#[test]
+fn parse_valid_endpoints() {
+    let cases = [
+        ("tcp://127.0.0.1:5555", Transport::Tcp, "127.0.0.1:5555"),
+        ("tcp://*:*", Transport::Tcp, "*:*"),
+        ("tcp://[::1]:5555", Transport::Tcp, "[::1]:5555"),
+        (
+            "tcp://eth0;example.com:80",
+            Transport::Tcp,
+            "eth0;example.com:80",
+        ),
+        ("ipc:///tmp/feed", Transport::Ipc, "/tmp/feed"),
+        ("inproc://events", Transport::Inproc, "events"),
+        (
+            "pgm://eth0;239.192.1.1:5555",
+            Transport::Pgm,
+            "eth0;239.192.1.1:5555",
+        ),
+        (
+            "epgm://eth0;239.192.1.1:5555",
+            Transport::Epgm,
+            "eth0;239.192.1.1:5555",
+        ),
+        ("tipc://{5560,0,0}", Transport::Tipc, "{5560,0,0}"),
+        ("vmci://1:5555", Transport::Vmci, "1:5555"),
+    ];
+    for (input, transport, address) in cases {
+        let endpoint = Endpoint::parse(input).unwrap();
+        assert_eq!(endpoint.transport(), transport);
+        assert_eq!(endpoint.address(), address);
+        assert_eq!(endpoint.to_string(), input);
+    }
+}
+
+#[test]
+fn parse_invalid_endpoints() {
+    assert_eq!(
+        Endpoint::parse("127.0.0.1:5555"),
+        Err(EndpointError::MissingTransport("127.0.0.1:5555".into()))
+    );
+    assert_eq!(
+        Endpoint::parse("tpc://127.0.0.1:5555"),
+        Err(EndpointError::UnknownTransport("tpc".into()))
+    );
+    for input in [
+        "tcp://127.0.0.1",
+        "tcp://127.0.0.1:port",
+        "tcp://127.0.0.1:70000",
+        "tcp://:5555",
+        "pgm://239.192.1.1:5555",
+        "inproc://",
+        "ipc://",
+    ] {
+        assert_eq!(
+            Endpoint::parse(input),
+            Err(EndpointError::InvalidAddress(input.into()))
+        );
+    }
+}
+
+#[test]
+fn constructor_rejects_invalid_endpoint() -> Result<()> {
+    assert!(matches!(
+        pull("tpc://127.0.0.1:5555"),
+        Err(SocketError::InvalidEndpoint(
+            EndpointError::UnknownTransport(_)
+        ))
+    ));
+
+    let endpoint: Endpoint = "inproc://parsed".parse()?;
+    let _pull = pull(endpoint)?.bind()?;
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use async_zmq::{EndpointError, Error, RecvError, SendError, SocketError};
+
+#[test]
+fn recv_error_is_terminal() {
+    assert!(RecvError::ContextTerminated.is_terminal());
+    assert!(RecvError::Unexpected(Error::ENOTSOCK).is_terminal());
+    assert!(!RecvError::Interrupted.is_terminal());
+    assert!(!RecvError::Unexpected(Error::EAGAIN).is_terminal());
+    assert!(RecvError::from(Error::ETERM).is_terminal());
+}
+
+#[test]
+fn send_error_is_terminal() {
+    assert!(SendError::ContextTerminated.is_terminal());
+    assert!(SendError::Unexpected(Error::ENOTSOCK).is_terminal());
+    assert!(!SendError::HostUnreachable.is_terminal());
+    assert!(!SendError::InvalidMessage.is_terminal());
+    assert!(!SendError::Interrupted.is_terminal());
+    assert!(!SendError::Unexpected(Error::EAGAIN).is_terminal());
+    assert!(SendError::from(Error::ETERM).is_terminal());
+}
+
+#[test]
+fn socket_error_converts_to_zmq_error() {
+    let error = SocketError::InvalidEndpoint(EndpointError::UnknownTransport("tpc".into()));
+    assert_eq!(error.to_string(), "the transport `tpc` is not supported");
+    assert_eq!(Error::from(error), Error::EINVAL);
+
+    let taken = SocketError::InprocNameTaken("events".into());
+    assert_eq!(Error::from(taken), Error::EADDRINUSE);
+}
@@ -0,0 +1,132 @@
+use async_zmq::{msg, Message, Multipart, MultipartBuilder};
+
+#[test]
+fn find_envelope_delimiter() {
+    let multipart: Multipart = vec![
+        Message::from("identity"),
+        Message::new(),
+        Message::from("body"),
+    ]
+    .into();
+
+    assert_eq!(multipart.find_delimiter(), Some(1));
+    assert!(!multipart.frame_is_empty(0));
+    assert!(multipart.frame_is_empty(1));
+    assert!(!multipart.frame_is_empty(2));
+    assert!(!multipart.frame_is_empty(3));
+}
+
+#[test]
+fn no_delimiter() {
+    let multipart: Multipart = vec![Message::from("topic"), Message::from("body")].into();
+
+    assert_eq!(multipart.find_delimiter(), None);
+}
+
+#[test]
+fn debug_truncates_large_frames() {
+    let multipart: Multipart = vec![
+        Message::from("topic"),
+        Message::from(&vec![b'a'; 1024 * 1024][..]),
+        Message::from(&[0u8, 255][..]),
+    ]
+    .into();
+
+    let debug = format!("{:?}", multipart);
+    assert!(debug.starts_with("Multipart(3 frames) "));
+    assert!(debug.contains("5 bytes \"topic\""));
+    assert!(debug.contains(&format!("1048576 bytes \"{}\"...", "a".repeat(32))));
+    assert!(debug.contains("2 bytes 0x00ff"));
+    assert!(debug.len() < 200);
+}
+
+#[test]
+fn frame_bytes_borrows_message_buffer() {
+    let multipart: Multipart = vec![Message::from("topic"), Message::from("body")].into();
+
+    let bytes = multipart.frame_bytes(1).unwrap();
+    assert_eq!(bytes, &multipart[1].to_vec()[..]);
+    assert_eq!(bytes.as_ptr(), multipart[1].as_ptr());
+    assert_eq!(multipart.frame_bytes(2), None);
+}
+
+#[test]
+fn build_with_constructors() {
+    let built = Multipart::of(vec!["topic".as_bytes().to_vec(), b"body".to_vec()]);
+    let expected: Multipart = vec![msg("topic"), msg(&b"body"[..])].into();
+    assert_eq!(built, expected);
+
+    assert_eq!(msg(vec![1u8, 2, 3]), Message::from(&[1u8, 2, 3][..]));
+    assert!(Multipart::of(Vec::<Message>::new()).is_empty());
+}
+
+#[test]
+fn frame_prefix() {
+    let multipart = Multipart::of(["weather.paris", "21"]);
+
+    assert!(multipart.frame_starts_with(0, b"weather"));
+    assert!(multipart.frame_starts_with(0, b"weather.paris"));
+    assert!(multipart.frame_starts_with(0, b""));
+    assert!(!multipart.frame_starts_with(0, b"news"));
+    assert!(!multipart.frame_starts_with(0, b"weather.paris.today"));
+    assert!(multipart.frame_starts_with(1, b"2"));
+    assert!(!multipart.frame_starts_with(2, b""));
+}
+
+#[test]
+fn mutate_frames_in_place() {
+    let mut multipart = Multipart::of(["header", "topic", "body"]);
+
+    assert_eq!(multipart.remove(0).unwrap().as_str(), Some("header"));
+    assert!(multipart.remove(5).is_none());
+    let old = multipart.replace(0, "renamed").unwrap();
+    assert_eq!(old.as_str(), Some("topic"));
+    assert!(multipart.replace(2, "missing").is_none());
+    multipart.insert(2, "trailer");
+
+    assert_eq!(multipart, Multipart::of(["renamed", "body", "trailer"]));
+}
+
+#[test]
+fn compare_frames() {
+    let multipart = Multipart::of([&b"topic"[..], &[0xff, 0x00][..]]);
+
+    assert!(multipart.frame_eq_str(0, "topic"));
+    assert!(multipart.frame_eq(0, b"topic"));
+    assert!(!multipart.frame_eq_str(0, "top"));
+    assert!(multipart.frame_eq(1, &[0xff, 0x00]));
+    assert!(!multipart.frame_eq_str(1, "\u{ff}"));
+    assert!(!multipart.frame_eq(2, b""));
+    assert!(!multipart.frame_eq_str(2, ""));
+}
+
+#[test]
+fn build_router_envelope() {
+    let multipart = MultipartBuilder::new()
+        .push_bytes(b"client-1")
+        .push_empty()
+        .push("hello")
+        .build();
+
+    assert_eq!(multipart.len(), 3);
+    assert!(multipart.frame_eq(0, b"client-1"));
+    assert!(multipart.frame_is_empty(1));
+    assert!(multipart.frame_eq_str(2, "hello"));
+    assert_eq!(multipart.find_delimiter(), Some(1));
+}
+
+#[test]
+fn dump_text_and_binary_frames() {
+    let multipart: Multipart = vec![
+        Message::from("topic"),
+        Message::new(),
+        Message::from(&[0x00, 0xff, 0x10][..]),
+        Message::from("a \"quoted\" body"),
+    ]
+    .into();
+
+    let expected = "[0] \"topic\"\n[1] \"\"\n[2] 0x00ff10\n[3] \"a \\\"quoted\\\" body\"";
+    assert_eq!(multipart.dump(), expected);
+    assert_eq!(multipart.to_string(), expected);
+    assert_eq!(Multipart::default().dump(), "");
+}
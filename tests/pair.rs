@@ -0,0 +1,50 @@
+use std::vec::IntoIter;
+
+use async_zmq::{context::Context, pair, Result, SinkExt, StreamExt};
+
+#[async_std::test]
+async fn pair_exchanges_both_ways() -> Result<()> {
+    let context = Context::new();
+    let mut bound = pair::<IntoIter<&str>, &str>("inproc://test")?
+        .with_context(&context)
+        .bind()?;
+    let mut connected = pair::<IntoIter<&str>, &str>("inproc://test")?
+        .with_context(&context)
+        .connect()?;
+
+    let left = async {
+        for i in 0..10 {
+            let frame = if i % 2 == 0 { "ping" } else { "pong" };
+            bound.send(vec!["from bound", frame].into()).await?;
+        }
+        let mut received = Vec::new();
+        for _ in 0..10 {
+            received.push(bound.next().await.unwrap()?);
+        }
+        Result::Ok(received)
+    };
+    let right = async {
+        for _ in 0..10 {
+            connected.send(vec!["from connected"].into()).await?;
+        }
+        let mut received = Vec::new();
+        for _ in 0..10 {
+            received.push(connected.next().await.unwrap()?);
+        }
+        Result::Ok(received)
+    };
+
+    let (left, right) = futures::join!(left, right);
+    for msg in left? {
+        assert_eq!(msg.len(), 1);
+        assert_eq!(msg[0].as_str(), Some("from connected"));
+    }
+    for (i, msg) in right?.iter().enumerate() {
+        assert_eq!(msg.len(), 2);
+        assert_eq!(msg[0].as_str(), Some("from bound"));
+        let frame = if i % 2 == 0 { "ping" } else { "pong" };
+        assert_eq!(msg[1].as_str(), Some(frame));
+    }
+
+    Ok(())
+}
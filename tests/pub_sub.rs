@@ -1,7 +1,14 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::vec::IntoIter;
+
 use async_std::sync::{Arc, Mutex};
 use async_std::task::spawn;
-
-use async_zmq::{publish, subscribe, Multipart, Result, SinkExt, StreamExt};
+use async_zmq::subscribe::DropEvent;
+use async_zmq::{
+    context::Context, pub_sub_pair, publish, subscribe, AsRawSocket, Message, Multipart,
+    ReassembleError, Result, SendError, SinkExt, StreamExt, SubscribeError,
+};
+use futures::stream;
 
 #[async_std::test]
 async fn publish_subscribe_message() -> Result<()> {
@@ -38,3 +45,758 @@ async fn publish_subscribe_message() -> Result<()> {
     receive_handle.await;
     Ok(())
 }
+
+#[async_std::test]
+async fn only_topic_filters_other_topics() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5572";
+    let mut publish = publish(uri)?.bind()?;
+    let mut subscribe = subscribe(uri)?.connect()?;
+    subscribe.set_subscribe("")?;
+    let running = Arc::new(Mutex::new(true));
+    let notify = running.clone();
+
+    let send_handle = spawn(async move {
+        while *running.lock().await {
+            let _ = publish.send(vec!["weather", "rain"].into()).await;
+            let _ = publish.send(vec!["sports", "goal"].into()).await;
+        }
+    });
+
+    let mut sports = subscribe.only_topic("sports");
+    for _ in 0..5 {
+        let recv = sports.next().await.unwrap()?;
+        assert_eq!(recv[0].as_str(), Some("sports"));
+        assert_eq!(recv[1].as_str(), Some("goal"));
+    }
+    *notify.lock().await = false;
+
+    send_handle.await;
+    Ok(())
+}
+
+#[async_std::test]
+async fn forward_bytes_into_byte_sink() -> Result<()> {
+    let context = Context::new();
+    let (publish, mut subscribe) = pub_sub_pair::<IntoIter<Message>, Message>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let chunks = vec![b"first".to_vec(), b"second".to_vec()];
+    let mut byte_sink = publish.into_byte_sink();
+    byte_sink
+        .send_all(&mut stream::iter(chunks.clone()).map(Ok))
+        .await?;
+
+    for chunk in chunks {
+        let recv = subscribe.next().await.unwrap()?;
+        assert_eq!(recv.len(), 1);
+        assert_eq!(&recv[0][..], &chunk[..]);
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn map_ok_decodes_messages() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    publish.send(vec!["temperature", "21"].into()).await?;
+
+    let mut readings = subscribe.map_ok(|msg| {
+        let value: i32 = msg[1].as_str().unwrap().parse().unwrap();
+        (msg[0].as_str().unwrap().to_string(), value)
+    });
+    let reading = readings.next().await.unwrap()?;
+    assert_eq!(reading, ("temperature".to_string(), 21));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn dedup_consecutive_skips_repeats() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    for reading in ["A", "A", "B", "B", "A"] {
+        publish.send(vec![reading].into()).await?;
+    }
+
+    let mut readings = subscribe.dedup_consecutive();
+    for expected in ["A", "B", "A"] {
+        let msg = readings.next().await.unwrap()?;
+        assert_eq!(msg[0].as_str(), Some(expected));
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn set_subscribe_after_context_shutdown() -> Result<()> {
+    let context = Context::new();
+    let mut subscribe = subscribe("inproc://shutdown")?
+        .with_context(&context)
+        .connect()?;
+    context.shutdown()?;
+
+    assert!(matches!(
+        subscribe.set_subscribe("topic"),
+        Err(SubscribeError::ContextTerminated)
+    ));
+    assert!(matches!(
+        subscribe.set_unsubscribe("topic"),
+        Err(SubscribeError::ContextTerminated)
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn idle_timeout_ends_stream() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    publish.send(vec!["first"].into()).await?;
+    publish.send(vec!["second"].into()).await?;
+
+    let mut stream = subscribe.idle_timeout(Duration::from_millis(200));
+    assert_eq!(stream.next().await.unwrap()?[0].as_str(), Some("first"));
+    assert_eq!(stream.next().await.unwrap()?[0].as_str(), Some("second"));
+    assert!(stream.next().await.is_none());
+    assert!(stream.next().await.is_none());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn connected_subscriber_estimate() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5576";
+    let publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_monitor()
+        .bind()?;
+    let _first = subscribe(uri)?.connect()?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    // Monitored from before binding, the subscriber connected before the first call counts.
+    let _second = subscribe(uri)?.connect()?;
+    let mut attempts = 0;
+    while publish.connected_subscriber_estimate() < 2 && attempts < 50 {
+        async_std::task::sleep(Duration::from_millis(20)).await;
+        attempts += 1;
+    }
+    assert_eq!(publish.connected_subscriber_estimate(), 2);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn subscriber_estimate_ignores_connected_peers() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5599";
+    let bound = "tcp://127.0.0.1:5600";
+    let publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_monitor()
+        .bind()?;
+    let _subscriber = subscribe(uri)?.connect()?;
+
+    // A subscriber the publisher connects to, then loses, isn't subtracted from the estimate.
+    let reached = subscribe(bound)?.bind()?;
+    publish.as_raw_socket().connect(bound)?;
+    async_std::task::sleep(Duration::from_millis(200)).await;
+    drop(reached);
+    async_std::task::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(publish.connected_subscriber_estimate(), 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn subscriber_estimate_shares_the_monitor() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5596";
+    let publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_monitor()
+        .bind()?;
+    let _first = subscribe(uri)?.connect()?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    // Neither the subscriber connected before the first call nor the stream started afterwards
+    // get in the way of the estimate.
+    let mut events = publish.monitor(async_zmq::zmq::SocketEvent::ACCEPTED)?;
+    let _second = subscribe(uri)?.connect()?;
+    let event = events.next().await.unwrap()?;
+    assert_eq!(event.event, async_zmq::zmq::SocketEvent::ACCEPTED);
+
+    let mut attempts = 0;
+    while publish.connected_subscriber_estimate() < 2 && attempts < 50 {
+        async_std::task::sleep(Duration::from_millis(20)).await;
+        attempts += 1;
+    }
+    assert_eq!(publish.connected_subscriber_estimate(), 2);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_borrowed_frames() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, mut subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let buffer = b"topicpayload".to_vec();
+    let (topic, payload) = buffer.split_at(5);
+    publish.send_borrowed(&[topic, payload]).await?;
+
+    let recv = subscribe.next().await.unwrap()?;
+    assert_eq!(recv.frame_bytes(0), Some(&b"topic"[..]));
+    assert_eq!(recv.frame_bytes(1), Some(&b"payload"[..]));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_cached_frame() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, mut subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let heartbeat = vec![7u8; 256];
+    publish.with_cached_frame("heartbeat", heartbeat.clone());
+    for _ in 0..3 {
+        publish.send_cached("heartbeat").await?;
+        let recv = subscribe.next().await.unwrap()?;
+        assert_eq!(recv.frame_bytes(0), Some(&heartbeat[..]));
+    }
+
+    publish.clear_cached_frames();
+    let result = publish.send_cached("heartbeat").await;
+    assert!(matches!(result, Err(SendError::InvalidMessage)));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn subscriptions_overlap_flags_redundant_topics() -> Result<()> {
+    let context = Context::new();
+    let mut subscribe = subscribe("inproc://subscriptions_overlap")?
+        .with_context(&context)
+        .connect()?;
+
+    subscribe
+        .set_subscribe("weather")?
+        .set_subscribe("sports")?;
+    assert!(subscribe.subscriptions_overlap().is_empty());
+
+    subscribe.set_subscribe("weather/paris")?;
+    assert_eq!(
+        subscribe.subscriptions_overlap(),
+        vec![("weather".to_string(), "weather/paris".to_string())]
+    );
+
+    subscribe.set_subscribe("")?;
+    assert_eq!(subscribe.subscriptions_overlap().len(), 4);
+
+    subscribe.set_unsubscribe("")?.set_unsubscribe("weather")?;
+    assert!(subscribe.subscriptions_overlap().is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn ttl_check_drops_expired_messages() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, subscribe) = pub_sub_pair::<IntoIter<Message>, Message>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    // Sent ten seconds ago with a one second time to live.
+    let sent_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap() - Duration::from_secs(10);
+    let mut timestamp = (sent_at.as_millis() as u64).to_be_bytes().to_vec();
+    timestamp.extend_from_slice(&1000u64.to_be_bytes());
+    let stale = vec![
+        Message::from("alerts"),
+        Message::from(timestamp),
+        Message::from("stale"),
+    ];
+    publish.send(stale.into()).await?;
+    publish
+        .send_with_ttl("alerts", vec!["fresh"], Duration::from_secs(10))
+        .await?;
+
+    let mut alerts = subscribe.with_ttl_check();
+    let msg = alerts.next().await.unwrap()?;
+    assert_eq!(msg.len(), 2);
+    assert_eq!(msg[0].as_str(), Some("alerts"));
+    assert_eq!(msg[1].as_str(), Some("fresh"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn with_readiness_reports_backlog() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    for _ in 0..10 {
+        publish.send(vec!["reading"].into()).await?;
+    }
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let mut readings = subscribe.with_readiness();
+    for _ in 0..9 {
+        let (_, more) = readings.next().await.unwrap()?;
+        assert!(more);
+    }
+    let (_, more) = readings.next().await.unwrap()?;
+    assert!(!more);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_to_topics_fans_out() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, mut subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    subscribe.set_unsubscribe("")?;
+    subscribe.set_subscribe("alpha")?.set_subscribe("beta")?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    publish
+        .send_to_topics(&["alpha", "beta", "gamma"], "payload")
+        .await?;
+    publish.send(vec!["beta", "done"].into()).await?;
+
+    let mut topics = Vec::new();
+    loop {
+        let msg = subscribe.next().await.unwrap()?;
+        if msg[1].as_str() == Some("done") {
+            break;
+        }
+        assert_eq!(msg[1].as_str(), Some("payload"));
+        topics.push(msg[0].as_str().unwrap().to_owned());
+    }
+    assert_eq!(topics, vec!["alpha", "beta"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn rate_limited_paces_messages() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    for _ in 0..10 {
+        publish.send(vec!["tick"].into()).await?;
+    }
+
+    // Ten messages at twenty per second are spaced by nine intervals of 50 ms.
+    let mut stream = subscribe.rate_limited(20);
+    let start = std::time::Instant::now();
+    for _ in 0..10 {
+        assert_eq!(stream.next().await.unwrap()?[0].as_str(), Some("tick"));
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(450), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn drop_notifications_report_lost_connection() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5580";
+    let publish = publish::<IntoIter<&str>, &str>(uri)?.bind()?;
+    let (subscribe, mut drops) = subscribe(uri)?.connect()?.with_drop_notifications()?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    drop(publish);
+    let event = async_std::future::timeout(Duration::from_secs(5), drops.next())
+        .await
+        .expect("no drop notification");
+    assert_eq!(event, Some(DropEvent::Disconnected(uri.to_string())));
+    drop(subscribe);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn compressed_round_trip() -> Result<()> {
+    let uri = "inproc://compressed_round_trip";
+    let context = Context::new();
+    let mut publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut plain = subscribe(uri)?.with_context(&context).connect()?;
+    plain.set_subscribe("logs")?;
+    let mut decompressing = subscribe(uri)?.with_context(&context).connect()?;
+    decompressing.set_subscribe("logs")?;
+    let mut decompressing = decompressing.decompress();
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let body = "GET /index.html 200\n".repeat(50_000).into_bytes();
+    publish.send_compressed("logs", &body).await?;
+
+    let msg = decompressing.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("logs"));
+    assert_eq!(msg.frame_bytes(1), Some(&body[..]));
+
+    let raw = plain.next().await.unwrap()?;
+    assert_eq!(raw[0].as_str(), Some("logs"));
+    assert!(raw[1].len() < body.len() / 10);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reassemble_joins_chunks() -> Result<()> {
+    let uri = "inproc://reassemble_joins_chunks";
+    let context = Context::new();
+    let mut publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut subscribe = subscribe(uri)?.with_context(&context).connect()?;
+    subscribe.set_subscribe("chunk")?;
+    let mut chunks = subscribe.reassemble(3, |part| part.frame_bytes(2) == Some(b"end"));
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    publish
+        .send(vec!["chunk", "0", "more", "alpha"].into())
+        .await?;
+    publish
+        .send(vec!["chunk", "1", "more", "beta"].into())
+        .await?;
+    publish
+        .send(vec!["chunk", "2", "end", "gamma"].into())
+        .await?;
+    let msg = chunks.next().await.unwrap()?;
+    assert_eq!(msg.len(), 12);
+    let bodies: Vec<_> = msg.chunks(4).map(|part| part[3].as_str()).collect();
+    assert_eq!(bodies, [Some("alpha"), Some("beta"), Some("gamma")]);
+
+    // A logical message whose final part never comes is dropped past the limit.
+    for seq in ["0", "1", "2"] {
+        publish
+            .send(vec!["chunk", seq, "more", "lost"].into())
+            .await?;
+    }
+    let error = chunks.next().await.unwrap().unwrap_err();
+    assert!(matches!(error, ReassembleError::Overflow { limit: 3 }));
+
+    publish
+        .send(vec!["chunk", "0", "end", "single"].into())
+        .await?;
+    let msg = chunks.next().await.unwrap()?;
+    assert_eq!(msg.len(), 4);
+    assert_eq!(msg[3].as_str(), Some("single"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn snapshot_then_subscribe_has_no_gaps() -> Result<()> {
+    let context = Context::new();
+    let (publish, subscribe) = pub_sub_pair::<IntoIter<Message>, Message>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+    let publish = Arc::new(Mutex::new(publish));
+
+    fn update(seq: u64) -> Vec<Message> {
+        vec![
+            Message::from("update"),
+            Message::from(&seq.to_be_bytes()[..]),
+        ]
+    }
+    let sequence = |msg: &Multipart| Some(u64::from_be_bytes(msg.frame_bytes(1)?.try_into().ok()?));
+
+    // Updates 1 to 5 are published while the snapshot is fetched, which covers up to 3.
+    let fetching = publish.clone();
+    let mut updates = subscribe
+        .snapshot_then_subscribe(
+            || async move {
+                let mut publish = fetching.lock().await;
+                for seq in 1..=5 {
+                    publish.send(update(seq).into()).await?;
+                }
+                async_std::task::sleep(Duration::from_millis(100)).await;
+                Ok::<_, SendError>((vec![Multipart::of(["state", "3"])], 3))
+            },
+            sequence,
+        )
+        .await?;
+
+    let mut publish = publish.lock().await;
+    for seq in [6, 7, 7, 8] {
+        publish.send(update(seq).into()).await?;
+    }
+
+    let state = updates.next().await.unwrap()?;
+    assert!(state.frame_eq_str(1, "3"));
+    for expected in 4..=8 {
+        let msg = updates.next().await.unwrap()?;
+        assert_eq!(sequence(&msg), Some(expected));
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn collect_for_gathers_published_messages() -> Result<()> {
+    let context = Context::new();
+    let (mut publish, mut subscribe) = pub_sub_pair::<IntoIter<&str>, &str>(&context)?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let sampling = subscribe.collect_for(Duration::from_millis(300));
+    let publishing = async {
+        for reading in ["1", "2", "3", "4"] {
+            publish.send(vec!["temperature", reading].into()).await?;
+        }
+        Ok::<_, SendError>(())
+    };
+    let (msgs, published) = futures::join!(sampling, publishing);
+    published?;
+    assert_eq!(msgs?.len(), 4);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn bind_ipv6_endpoint() -> Result<()> {
+    let context = Context::new();
+    let publish = publish::<IntoIter<&str>, &str>("tcp://[::1]:*")?
+        .with_context(&context)
+        .with_ipv6(true)
+        .bind()?;
+    assert!(publish.get_ipv6()?);
+    let endpoint = publish.as_raw_socket().get_last_endpoint()?.unwrap();
+    assert!(endpoint.starts_with("tcp://[::1]:"), "{}", endpoint);
+
+    let mut subscribe = subscribe(endpoint.as_str())?
+        .with_context(&context)
+        .connect()?;
+    assert!(!subscribe.get_ipv6()?);
+    // Connect again to the IPv6 endpoint now that the option is set.
+    subscribe.set_ipv6(true)?.reset_connections().await?;
+    assert!(subscribe.get_ipv6()?);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn tcp_keepalive_options_stick() -> Result<()> {
+    let mut publish = publish::<IntoIter<&str>, &str>("tcp://127.0.0.1:*")?.bind()?;
+    assert_eq!(publish.get_tcp_keepalive()?, -1);
+
+    publish
+        .set_tcp_keepalive(1)?
+        .set_tcp_keepalive_idle(60)?
+        .set_tcp_keepalive_cnt(5)?
+        .set_tcp_keepalive_intvl(10)?;
+    assert_eq!(publish.get_tcp_keepalive()?, 1);
+    assert_eq!(publish.get_tcp_keepalive_idle()?, 60);
+    assert_eq!(publish.get_tcp_keepalive_cnt()?, 5);
+    assert_eq!(publish.get_tcp_keepalive_intvl()?, 10);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reconnect_interval_options_stick() -> Result<()> {
+    let mut subscribe = subscribe("tcp://127.0.0.1:5589")?.connect()?;
+    assert_eq!(subscribe.get_reconnect_interval()?, 100);
+
+    subscribe
+        .set_reconnect_interval(5000)?
+        .set_reconnect_interval_max(30000)?;
+    assert_eq!(subscribe.get_reconnect_interval()?, 5000);
+    assert_eq!(subscribe.get_reconnect_interval_max()?, 30000);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn recv_timeout_returns_none_when_idle() -> Result<()> {
+    let uri = "inproc://recv_timeout_returns_none_when_idle";
+    let context = Context::new();
+    let mut publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut subscribe = subscribe(uri)?.with_context(&context).connect()?;
+    subscribe.set_subscribe("")?;
+
+    let started = Instant::now();
+    assert!(subscribe
+        .recv_timeout(Duration::from_millis(100))
+        .await?
+        .is_none());
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+
+    publish.send(vec!["update"].into()).await?;
+    let msg = subscribe.recv_timeout(Duration::from_secs(5)).await?;
+    assert!(msg.unwrap().frame_eq_str(0, "update"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn conflate_keeps_latest_message() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5591";
+    let mut publish = publish::<IntoIter<Message>, Message>(uri)?.bind()?;
+    let mut subscribe = subscribe(uri)?.with_conflate(true).connect()?;
+    assert!(subscribe.get_conflate()?);
+    subscribe.set_subscribe("")?;
+    // Let the subscription reach the publisher.
+    async_std::task::sleep(Duration::from_millis(200)).await;
+
+    for value in 0..100 {
+        publish
+            .send(vec![Message::from(&value.to_string())].into())
+            .await?;
+    }
+    async_std::task::sleep(Duration::from_millis(200)).await;
+
+    let latest = subscribe.recv_timeout(Duration::from_secs(5)).await?;
+    assert!(latest.unwrap().frame_eq_str(0, "99"));
+    let next = subscribe.recv_timeout(Duration::from_millis(100)).await?;
+    assert!(next.is_none());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_all_batched_delivers_every_message() -> Result<()> {
+    const COUNT: usize = 10_000;
+    let uri = "inproc://send_all_batched_delivers_every_message";
+    let context = Context::new();
+    // No high water mark, so that nothing is dropped while the batch is in flight.
+    let mut publish = publish::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .with_send_hwm(0)
+        .bind()?;
+    let mut subscribe = subscribe(uri)?
+        .with_context(&context)
+        .with_receive_hwm(0)
+        .connect()?;
+    subscribe.set_subscribe("")?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let mut batch = (0..COUNT).map(|idx| vec![Message::from(&idx.to_be_bytes()[..])].into());
+    assert_eq!(publish.send_all_batched(&mut batch).await?, COUNT);
+    assert!(batch.next().is_none());
+
+    for idx in 0..COUNT {
+        let msg = subscribe.next().await.unwrap()?;
+        assert!(msg.frame_eq(0, &idx.to_be_bytes()));
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_all_batched_keeps_messages_after_the_refused_one() -> Result<()> {
+    const COUNT: usize = 10_000;
+    const ZMQ_XPUB_NODROP: i32 = 69;
+    let uri = "inproc://send_all_batched_keeps_messages_after_the_refused_one";
+    let context = Context::new();
+    let mut publish = publish::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .with_send_hwm(10)
+        .set_raw_option(ZMQ_XPUB_NODROP, &1i32.to_ne_bytes())
+        .bind()?;
+    // The subscriber never reads, so the publisher runs out of room.
+    let mut subscribe = subscribe(uri)?
+        .with_context(&context)
+        .with_receive_hwm(10)
+        .connect()?;
+    subscribe.set_subscribe("")?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let mut batch = (0..COUNT).map(|idx| vec![Message::from(&idx.to_be_bytes()[..])].into());
+    let sent = publish.send_all_batched(&mut batch).await?;
+    assert!(sent < COUNT);
+    // The refused message is dropped, the ones after it are left to send.
+    assert_eq!(batch.count(), COUNT - sent - 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn try_recv_returns_available_message() -> Result<()> {
+    let uri = "inproc://try_recv_returns_available_message";
+    let context = Context::new();
+    let mut publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut subscribe = subscribe(uri)?.with_context(&context).connect()?;
+    subscribe.set_subscribe("")?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    assert!(subscribe.try_recv()?.is_none());
+    publish.send(vec!["update"].into()).await?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let msg = loop {
+        if let Some(msg) = subscribe.try_recv()? {
+            break msg;
+        }
+        assert!(Instant::now() < deadline, "no message within the deadline");
+        async_std::task::sleep(Duration::from_millis(10)).await;
+    };
+    assert!(msg.frame_eq_str(0, "update"));
+    assert!(subscribe.try_recv()?.is_none());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn get_events_reports_readable_subscriber() -> Result<()> {
+    let uri = "inproc://get_events_reports_readable_subscriber";
+    let context = Context::new();
+    let mut publish = publish::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut subscribe = subscribe(uri)?.with_context(&context).connect()?;
+    subscribe.set_subscribe("")?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+    assert!(!subscribe.get_events()?.contains(zmq::POLLIN));
+
+    publish.send(vec!["update"].into()).await?;
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !subscribe.get_events()?.contains(zmq::POLLIN) {
+        assert!(Instant::now() < deadline, "no message within the deadline");
+        async_std::task::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(subscribe.try_recv()?.is_some());
+    assert!(!subscribe.get_events()?.contains(zmq::POLLIN));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn oversized_message_drops_connection() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5594";
+    let mut publish = publish::<IntoIter<Message>, Message>(uri)?.bind()?;
+    let mut subscribe = subscribe(uri)?.connect()?;
+    subscribe.set_max_message_size(64)?;
+    assert_eq!(subscribe.get_max_message_size()?, 64);
+    // The limit is read when a connection is set up.
+    subscribe.reset_connections().await?;
+    subscribe.set_subscribe("")?;
+    let mut events = subscribe.monitor(zmq::SocketEvent::DISCONNECTED)?;
+
+    async_std::task::sleep(Duration::from_millis(200)).await;
+    publish
+        .send(vec![Message::from(&[0; 1024][..])].into())
+        .await?;
+
+    let event = async_std::future::timeout(Duration::from_secs(5), events.next())
+        .await
+        .expect("no DISCONNECTED event within the deadline")
+        .expect("monitor ended")?;
+    assert_eq!(event.event, zmq::SocketEvent::DISCONNECTED);
+    assert!(subscribe.try_recv()?.is_none());
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::time::Duration;
 use std::thread;
-use async_zmq::{Result, Context, CurveKeyPair, StreamExt, SinkExt, Message};
+use async_zmq::{Result, context::Context, CurveKeyPair, StreamExt, SinkExt, Message};
 
 // Helper function to check if CURVE is supported
 fn check_curve_support() -> bool {
@@ -109,14 +109,14 @@ async fn test_incorrect_server_key() -> Result<()> {
     let wrong_server_pair = CurveKeyPair::new()?;
     
     // Create and configure server socket with explicit type annotation
-    let publisher: async_zmq::Publish<std::vec::IntoIter<Message>, Message> = 
+    let mut publisher: async_zmq::Publish<std::vec::IntoIter<Message>, Message> = 
         async_zmq::publish(uri)?.with_context(&ctx).bind()?;
     publisher.set_curve_server(true)?;
     publisher.set_curve_secretkey(&server_pair.secret_key)?;
     publisher.set_curve_publickey(&server_pair.public_key)?;
     
     // Create and configure client socket with WRONG server key
-    let subscriber = async_zmq::subscribe(uri)?.with_context(&ctx).connect()?;
+    let mut subscriber = async_zmq::subscribe(uri)?.with_context(&ctx).connect()?;
     subscriber.set_curve_serverkey(&wrong_server_pair.public_key)?; // Wrong key
     subscriber.set_curve_publickey(&client_pair.public_key)?;
     subscriber.set_curve_secretkey(&client_pair.secret_key)?;
@@ -0,0 +1,89 @@
+use std::thread;
+use std::time::Duration;
+use std::vec::IntoIter;
+
+use async_zmq::{context::Context, publish, subscribe, Message, Result, SinkExt, StreamExt};
+use futures::future::{select, Either};
+
+/// Answer ZAP requests of `context`, accepting PLAIN peers presenting `admin`/`secret`.
+fn spawn_zap_handler(context: &Context) -> Result<()> {
+    let zap = context.socket(zmq::REP)?;
+    zap.bind("inproc://zeromq.zap.01")?;
+    thread::spawn(move || {
+        // Version, request id, domain, address, identity, mechanism, username, password.
+        while let Ok(request) = zap.recv_multipart(0) {
+            let accepted = request.len() == 8
+                && request[5] == b"PLAIN"
+                && request[6] == b"admin"
+                && request[7] == b"secret";
+            let (status, text) = if accepted {
+                ("200", "OK")
+            } else {
+                ("400", "Denied")
+            };
+            let response: [&[u8]; 6] = [
+                b"1.0",
+                &request[1],
+                status.as_bytes(),
+                text.as_bytes(),
+                b"admin",
+                b"",
+            ];
+            if zap.send_multipart(response, 0).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+#[async_std::test]
+async fn plain_pub_sub() -> Result<()> {
+    let context = Context::new();
+    spawn_zap_handler(&context)?;
+    let uri = "tcp://127.0.0.1:5584";
+
+    let mut publisher = publish::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    publisher
+        .set_plain_server(true)?
+        .set_zap_domain("global")?
+        .reset_connections()
+        .await?;
+
+    let mut subscriber = subscribe(uri)?.with_context(&context).connect()?;
+    subscriber
+        .set_plain_username("admin")?
+        .set_plain_password("secret")?
+        .reset_connections()
+        .await?;
+    subscriber.set_subscribe("topic")?;
+
+    let mut intruder = subscribe(uri)?.with_context(&context).connect()?;
+    intruder
+        .set_plain_username("admin")?
+        .set_plain_password("guess")?
+        .reset_connections()
+        .await?;
+    intruder.set_subscribe("topic")?;
+
+    // Publish until the authenticated subscriber is connected.
+    let received = loop {
+        publisher
+            .send(vec![Message::from("topic"), Message::from("secret news")].into())
+            .await?;
+        let timeout = Box::pin(async_std::task::sleep(Duration::from_millis(100)));
+        if let Either::Left((msg, _)) = select(subscriber.next(), timeout).await {
+            break msg.unwrap()?;
+        }
+    };
+    assert_eq!(received[1].as_str(), Some("secret news"));
+
+    let timeout = Box::pin(async_std::task::sleep(Duration::from_millis(200)));
+    if let Either::Left(_) = select(intruder.next(), timeout).await {
+        panic!("a peer with the wrong password received a message");
+    }
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
-use async_std::sync::{Arc, Mutex};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use std::vec::IntoIter;
+
 use async_std::task::spawn;
 
-use async_zmq::{pull, push, Multipart, Result, SinkExt, StreamExt};
+use async_zmq::{
+    context::Context, pull, push, Message, Multipart, Result, SinkExt, SocketError, StreamExt,
+};
 
 #[async_std::test]
 async fn push_pull_message() -> Result<()> {
@@ -10,29 +15,327 @@ async fn push_pull_message() -> Result<()> {
     let mut pull = pull(uri)?.connect()?;
     let message = vec!["Hello", "World"];
     let expected = message.clone();
-    let running = Arc::new(Mutex::new(true));
-    let notify = running.clone();
 
+    // PUSH never drops messages, so a single send is delivered once the peer is connected.
     let send_handle = spawn(async move {
-        while *running.lock().await {
-            let _ = push.send(message.clone().into()).await;
-        }
+        push.send(message.into()).await.unwrap();
+        push
     });
 
     let receive_handle = spawn(async move {
-        while let Some(recv) = pull.next().await {
-            if let Ok(recv) = recv {
-                assert_eq!(
-                    recv,
-                    expected.iter().map(|i| i.into()).collect::<Multipart>()
-                );
-                *notify.lock().await = false;
-                break;
-            }
-        }
+        let recv = pull.next().await.unwrap().unwrap();
+        assert_eq!(
+            recv,
+            expected.iter().map(|i| i.into()).collect::<Multipart>()
+        );
     });
 
-    send_handle.await;
+    let _push = send_handle.await;
     receive_handle.await;
     Ok(())
 }
+
+#[async_std::test]
+async fn single_message_round_trip() -> Result<()> {
+    let uri = "inproc://single_message_round_trip";
+    let context = Context::new();
+    let pull = pull(uri)?.with_context(&context).bind()?;
+    let mut push = push::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+
+    push.send_msg(Message::from("Hello")).await?;
+    let recv = pull.recv_msg().await?;
+    assert_eq!(recv.as_str(), Some("Hello"));
+    assert!(!recv.get_more());
+
+    // Only the first frame of a multipart message is kept.
+    push.send(vec!["first", "second"].into()).await?;
+    push.send_msg(Message::from("next")).await?;
+    assert_eq!(pull.recv_msg().await?.as_str(), Some("first"));
+    assert_eq!(pull.recv_msg().await?.as_str(), Some("next"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn close_now_discards_queued_messages() -> Result<()> {
+    let context = Context::new();
+    // Nothing listens on this port, so messages stay queued in the pipe of the connection.
+    let mut push = push::<IntoIter<&str>, &str>("tcp://127.0.0.1:5578")?
+        .with_context(&context)
+        .connect()?;
+    for _ in 0..10 {
+        push.send(vec!["queued"].into()).await?;
+    }
+    push.close_now();
+
+    // Terminating the context would wait forever for the queued messages with the default linger.
+    let (done, finished) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        drop(context);
+        let _ = done.send(());
+    });
+    assert!(finished.recv_timeout(Duration::from_secs(5)).is_ok());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_from_array() -> Result<()> {
+    let uri = "inproc://send_from_array";
+    let context = Context::new();
+    let mut pull = pull(uri)?.with_context(&context).bind()?;
+    let mut push = push::<std::array::IntoIter<&str, 2>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+
+    push.send(["topic", "body"].into()).await?;
+    let msg = pull.next().await.unwrap()?;
+    assert_eq!(msg.len(), 2);
+    assert_eq!(msg[0].as_str(), Some("topic"));
+    assert_eq!(msg[1].as_str(), Some("body"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn push_balances_across_pull_workers() -> Result<()> {
+    let uri = "inproc://push_balances_across_pull_workers";
+    let context = Context::new();
+    let mut push = push::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    push.set_send_hwm(1000)?;
+    assert_eq!(push.get_send_hwm()?, 1000);
+
+    let mut workers = Vec::new();
+    for _ in 0..2 {
+        let mut worker = pull(uri)?.with_context(&context).connect()?;
+        worker.set_receive_hwm(1000)?;
+        assert_eq!(worker.get_receive_hwm()?, 1000);
+        workers.push(worker);
+    }
+
+    for i in 0..100 {
+        push.send(vec![Message::from(&i.to_string())].into())
+            .await?;
+    }
+
+    let mut received = BTreeSet::new();
+    let mut per_worker = [0; 2];
+    let second = workers.pop().unwrap().map(|msg| (1, msg));
+    let first = workers.pop().unwrap().map(|msg| (0, msg));
+    let mut merged = futures::stream::select(first, second).take(100);
+    while let Some((worker, msg)) = merged.next().await {
+        let value: u32 = msg?[0].as_str().unwrap().parse().unwrap();
+        assert!(received.insert(value), "message {} received twice", value);
+        per_worker[worker] += 1;
+    }
+    assert_eq!(received, (0..100).collect());
+    // Round-robin is per connection, which may not be up for both workers right away.
+    assert!(per_worker.iter().all(|&count| count > 0), "{:?}", per_worker);
+    assert_eq!(per_worker.iter().sum::<u32>(), 100);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn forward_without_header() -> Result<()> {
+    let context = Context::new();
+    let mut producer = push::<IntoIter<&str>, &str>("inproc://forward_without_header_in")?
+        .with_context(&context)
+        .bind()?;
+    let mut forwarder_in = pull("inproc://forward_without_header_in")?
+        .with_context(&context)
+        .connect()?;
+    let mut forwarder_out =
+        push::<IntoIter<Message>, Message>("inproc://forward_without_header_out")?
+            .with_context(&context)
+            .bind()?;
+    let mut consumer = pull("inproc://forward_without_header_out")?
+        .with_context(&context)
+        .connect()?;
+
+    producer
+        .send(vec!["hop-1", "weather", "sunny"].into())
+        .await?;
+    let mut msg = forwarder_in.next().await.unwrap()?;
+    msg.remove(0);
+    msg.replace(0, "forecast");
+    forwarder_out.send(msg.into()).await?;
+
+    let msg = consumer.next().await.unwrap()?;
+    assert_eq!(msg, Multipart::of(["forecast", "sunny"]));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn collect_for_samples_a_window() -> Result<()> {
+    let uri = "inproc://collect_for_samples_a_window";
+    let context = Context::new();
+    let mut pull = pull(uri)?.with_context(&context).bind()?;
+    let mut push = push::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+
+    assert!(pull
+        .collect_for(Duration::from_millis(50))
+        .await?
+        .is_empty());
+
+    for body in ["a", "b", "c"] {
+        push.send(vec![body].into()).await?;
+    }
+    let msgs = pull.collect_for(Duration::from_millis(200)).await?;
+    assert_eq!(msgs.len(), 3);
+    assert!(msgs[2].frame_eq_str(0, "c"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn bind_many_receives_from_every_endpoint() -> Result<()> {
+    let context = Context::new();
+    let mut pull = pull("tcp://127.0.0.1:5585")?
+        .with_context(&context)
+        .bind_many(&["tcp://127.0.0.1:5586"])?;
+
+    let mut pushes = Vec::new();
+    for (port, body) in [(5585, "first"), (5586, "second")] {
+        let mut push = push::<IntoIter<&str>, &str>(format!("tcp://127.0.0.1:{}", port))?
+            .with_context(&context)
+            .connect()?;
+        push.send(vec![body].into()).await?;
+        pushes.push(push);
+    }
+
+    let mut bodies = BTreeSet::new();
+    for _ in 0..2 {
+        let msg = pull.next().await.unwrap()?;
+        bodies.insert(msg[0].as_str().unwrap().to_owned());
+    }
+    assert_eq!(
+        bodies,
+        BTreeSet::from(["first".to_owned(), "second".to_owned()])
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn bind_many_rolls_back_on_failure() -> Result<()> {
+    let context = Context::new();
+    let _taken = pull("inproc://bind_many_taken")?
+        .with_context(&context)
+        .bind()?;
+
+    let failed = pull("inproc://bind_many_free")?
+        .with_context(&context)
+        .bind_many(&["inproc://bind_many_taken"]);
+    assert!(matches!(failed, Err(SocketError::InprocNameTaken(_))));
+
+    // The endpoint bound before the failure was released.
+    pull("inproc://bind_many_free")?
+        .with_context(&context)
+        .bind()?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn source_stats_count_each_push_peer() -> Result<()> {
+    let fast_uri = "tcp://127.0.0.1:5588";
+    let slow_uri = "tcp://127.0.0.1:5598";
+    let mut fast = push(fast_uri)?.bind()?;
+    let mut slow = push(slow_uri)?.bind()?;
+    let mut pull = pull(fast_uri)?
+        .with_monitor()
+        .connect_many(&[slow_uri])?
+        .with_source_stats()?;
+    assert!(pull.source_stats().is_empty());
+
+    let send_fast = async {
+        for _ in 0..20 {
+            fast.send(vec!["fast"].into()).await?;
+        }
+        Ok::<_, async_zmq::Error>(())
+    };
+    let send_slow = async {
+        for _ in 0..5 {
+            slow.send(vec!["slow"].into()).await?;
+            async_std::task::sleep(Duration::from_millis(10)).await;
+        }
+        Ok::<_, async_zmq::Error>(())
+    };
+    let receive = async {
+        for _ in 0..25 {
+            pull.next().await.unwrap()?;
+        }
+        Ok::<_, async_zmq::Error>(())
+    };
+    let (sent_fast, sent_slow, received) = futures::join!(send_fast, send_slow, receive);
+    sent_fast?;
+    sent_slow?;
+    received?;
+
+    let stats = pull.source_stats();
+    assert_eq!(stats.get(fast_uri), Some(&20));
+    assert_eq!(stats.get(slow_uri), Some(&5));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn source_stats_forget_closed_connections() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5602";
+    let mut pull = pull(uri)?.bind()?.with_source_stats()?;
+    let mut client = push(uri)?.connect()?;
+
+    client.send(vec!["counted"].into()).await?;
+    pull.next().await.unwrap()?;
+    assert_eq!(pull.source_stats().values().sum::<u64>(), 1);
+
+    client.close_now();
+    for _ in 0..50 {
+        if pull.source_stats().is_empty() {
+            break;
+        }
+        async_std::task::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(pull.source_stats().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn source_stats_need_a_monitor() -> Result<()> {
+    // Sockets converted from the zmq crate can't be monitored.
+    let socket = zmq::Context::new().socket(zmq::PULL)?;
+    let pull = async_zmq::Pull::from(socket).with_source_stats();
+    assert!(matches!(pull, Err(async_zmq::Error::EFAULT)));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn close_delivers_pending_messages() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5592";
+    let mut pull = pull(uri)?.bind()?;
+    let mut client = push(uri)?.connect()?;
+
+    client.send(vec!["last words"].into()).await?;
+    client.close().await?;
+    assert!(pull.next().await.unwrap()?.frame_eq_str(0, "last words"));
+
+    // Without any peer, the message is held for the whole linger period.
+    let mut lonely = push::<IntoIter<&str>, &str>("tcp://127.0.0.1:5593")?.connect()?;
+    lonely.as_raw_socket().set_linger(200)?;
+    lonely.send(vec!["unheard"].into()).await?;
+    let started = std::time::Instant::now();
+    lonely.close().await?;
+    assert!(started.elapsed() >= Duration::from_millis(150));
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use async_zmq::{reactor_registered, Result};
+
+#[async_std::test]
+async fn bound_socket_is_registered() -> Result<()> {
+    let pull = async_zmq::pull("tcp://127.0.0.1:5579")?.bind()?;
+    let push = async_zmq::push::<std::vec::IntoIter<Vec<u8>>, Vec<u8>>("tcp://127.0.0.1:5579")?
+        .connect()?;
+
+    assert!(reactor_registered(pull.as_raw_socket()));
+    assert!(reactor_registered(push.as_raw_socket()));
+    Ok(())
+}
+
+#[test]
+fn unwrapped_socket_is_not_registered() {
+    let context = zmq::Context::new();
+    let socket = context.socket(zmq::PULL).unwrap();
+    assert!(!reactor_registered(&socket));
+}
+
+#[async_std::test]
+async fn round_trip_under_async_std() -> Result<()> {
+    let context = async_zmq::context::Context::new();
+    let (request, reply) = async_zmq::req_rep_pair::<std::vec::IntoIter<&str>, &str>(&context)?;
+
+    request.send(vec!["ping"]).await?;
+    assert!(reply.recv().await?.frame_eq_str(0, "ping"));
+    reply.send(vec!["pong"]).await?;
+    assert!(request.recv().await?.frame_eq_str(0, "pong"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[async_std::test]
+async fn raw_fd_is_the_zmq_fd() -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let pull = async_zmq::pull("inproc://raw_fd_is_the_zmq_fd")?.bind()?;
+    let fd = pull.as_raw_fd();
+    assert!(fd >= 0);
+    assert_eq!(fd, pull.as_raw_socket().get_fd()?);
+    #[cfg(target_os = "linux")]
+    assert!(std::path::Path::new(&format!("/proc/self/fd/{}", fd)).exists());
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::vec::IntoIter;
+
+use async_zmq::{context::Context, req_rep_pair, Result};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn round_trip_under_tokio() -> Result<()> {
+    let context = Context::new();
+    let (request, reply) = req_rep_pair::<IntoIter<&str>, &str>(&context)?;
+
+    let server = async {
+        assert!(reply.recv().await?.frame_eq_str(0, "ping"));
+        reply.send(vec!["pong"]).await
+    };
+    let client = async {
+        request.send(vec!["ping"]).await?;
+        request.recv().await
+    };
+    let (served, reply) = tokio::join!(server, client);
+    served?;
+    assert!(reply?.frame_eq_str(0, "pong"));
+    Ok(())
+}
+
+#[test]
+fn socket_moves_between_runtimes() -> Result<()> {
+    let context = Context::new();
+    let (request, reply) = req_rep_pair::<IntoIter<&str>, &str>(&context)?;
+    let tokio = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    // The request is sent from async-std and its reply received from tokio.
+    async_std::task::block_on(request.send(vec!["ping"]))?;
+    tokio.block_on(async {
+        assert!(reply.recv().await?.frame_eq_str(0, "ping"));
+        reply.send(vec!["pong"]).await
+    })?;
+    let msg = tokio.block_on(request.recv())?;
+    assert!(msg.frame_eq_str(0, "pong"));
+    Ok(())
+}
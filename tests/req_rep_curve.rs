@@ -1,6 +1,6 @@
 use std::time::Duration;
 use std::thread;
-use async_zmq::{Result, Context, CurveKeyPair, Message};
+use async_zmq::{Result, context::Context, CurveKeyPair, Message};
 
 // Helper function to check if CURVE is supported
 fn check_curve_support() -> bool {
@@ -45,13 +45,13 @@ async fn test_secure_req_rep() -> Result<()> {
     let client_pair = CurveKeyPair::new()?;
     
     // Create and configure replier as CURVE server
-    let replier = async_zmq::reply(uri)?.with_context(&ctx).bind()?;
+    let mut replier = async_zmq::reply(uri)?.with_context(&ctx).bind()?;
     replier.set_curve_server(true)?;
     replier.set_curve_secretkey(&server_pair.secret_key)?;
     replier.set_curve_publickey(&server_pair.public_key)?;
     
     // Create and configure requester as CURVE client
-    let requester = async_zmq::request(uri)?.with_context(&ctx).connect()?;
+    let mut requester = async_zmq::request(uri)?.with_context(&ctx).connect()?;
     requester.set_curve_serverkey(&server_pair.public_key)?;
     requester.set_curve_publickey(&client_pair.public_key)?;
     requester.set_curve_secretkey(&client_pair.secret_key)?;
@@ -104,17 +104,18 @@ async fn test_incorrect_server_key() -> Result<()> {
     // Generate a different server key pair (wrong keys)
     let wrong_server_pair = CurveKeyPair::new()?;
     
-    // Create and configure replier as CURVE server
-    let replier = async_zmq::reply(uri)?.with_context(&ctx).bind()?;
-    replier.set_curve_server(true)?;
-    replier.set_curve_secretkey(&server_pair.secret_key)?;
-    replier.set_curve_publickey(&server_pair.public_key)?;
+    // Create replier as CURVE server, configured before it binds
+    let replier: async_zmq::Reply<std::vec::IntoIter<Message>, Message> =
+        async_zmq::reply(uri)?
+            .with_context(&ctx)
+            .with_curve_server(&server_pair)
+            .bind()?;
     
-    // Create and configure requester with WRONG server key
-    let requester = async_zmq::request(uri)?.with_context(&ctx).connect()?;
-    requester.set_curve_serverkey(&wrong_server_pair.public_key)?; // Wrong key
-    requester.set_curve_publickey(&client_pair.public_key)?;
-    requester.set_curve_secretkey(&client_pair.secret_key)?;
+    // Create requester with WRONG server key, configured before it connects
+    let requester = async_zmq::request(uri)?
+        .with_context(&ctx)
+        .with_curve_client(&client_pair, &wrong_server_pair.public_key)
+        .connect()?;
     
     // Allow time for connection attempt
     async_std::task::sleep(Duration::from_millis(500)).await;
@@ -187,7 +188,7 @@ async fn test_zap_authentication() -> Result<()> {
     let client_pair = CurveKeyPair::new()?;
     
     // Create and configure replier as CURVE server with ZAP domain
-    let replier = async_zmq::reply(uri)?.with_context(&ctx).bind()?;
+    let mut replier = async_zmq::reply(uri)?.with_context(&ctx).bind()?;
     replier.set_curve_server(true)?;
     replier.set_curve_secretkey(&server_pair.secret_key)?;
     replier.set_curve_publickey(&server_pair.public_key)?;
@@ -196,7 +197,7 @@ async fn test_zap_authentication() -> Result<()> {
     replier.as_raw_socket().set_zap_domain("global")?;
     
     // Create and configure requester as CURVE client
-    let requester = async_zmq::request(uri)?.with_context(&ctx).connect()?;
+    let mut requester = async_zmq::request(uri)?.with_context(&ctx).connect()?;
     requester.set_curve_serverkey(&server_pair.public_key)?;
     requester.set_curve_publickey(&client_pair.public_key)?;
     requester.set_curve_secretkey(&client_pair.secret_key)?;
@@ -247,13 +248,13 @@ async fn test_custom_context() -> Result<()> {
     let client_pair = CurveKeyPair::new()?;
     
     // Create and configure replier as CURVE server with custom context
-    let replier = async_zmq::reply(uri)?.with_context(&custom_ctx).bind()?;
+    let mut replier = async_zmq::reply(uri)?.with_context(&custom_ctx).bind()?;
     replier.set_curve_server(true)?;
     replier.set_curve_secretkey(&server_pair.secret_key)?;
     replier.set_curve_publickey(&server_pair.public_key)?;
     
     // Create and configure requester as CURVE client with custom context
-    let requester = async_zmq::request(uri)?.with_context(&custom_ctx).connect()?;
+    let mut requester = async_zmq::request(uri)?.with_context(&custom_ctx).connect()?;
     requester.set_curve_serverkey(&server_pair.public_key)?;
     requester.set_curve_publickey(&client_pair.public_key)?;
     requester.set_curve_secretkey(&client_pair.secret_key)?;
@@ -284,4 +285,51 @@ async fn test_custom_context() -> Result<()> {
     assert_eq!(reply[0].as_str().unwrap(), "secure reply message");
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+// Test a socket flipped from client to server mode handshakes as a server
+#[async_std::test]
+async fn test_server_mode_after_server_key() -> Result<()> {
+    if !check_curve_support() {
+        println!("Skipping test: CURVE security not supported");
+        return Ok(());
+    }
+
+    let ctx = Context::new();
+    let uri = "tcp://127.0.0.1:5577";
+
+    let server_pair = CurveKeyPair::new()?;
+    let client_pair = CurveKeyPair::new()?;
+    let stale_pair = CurveKeyPair::new()?;
+
+    // Configure the replier as a client first, then switch it to server mode, before it binds
+    let replier = async_zmq::reply(uri)?
+        .with_context(&ctx)
+        .configure(|socket| {
+            socket.set_curve_serverkey(&stale_pair.public_key)?;
+            assert!(!socket.is_curve_server()?);
+            socket.set_curve_server(true)?;
+            socket.set_curve_secretkey(&server_pair.secret_key)?;
+            socket.set_curve_publickey(&server_pair.public_key)?;
+            assert!(socket.is_curve_server()?);
+            Ok(())
+        })
+        .bind()?;
+
+    let requester = async_zmq::request(uri)?
+        .with_context(&ctx)
+        .with_curve_client(&client_pair, &server_pair.public_key)
+        .connect()?;
+
+    async_std::task::sleep(Duration::from_millis(500)).await;
+
+    requester.send(vec![Message::from("hello")]).await?;
+    let msg = replier.recv().await?;
+    assert_eq!(msg[0].as_str().unwrap(), "hello");
+
+    replier.send(vec![Message::from("world")]).await?;
+    let reply = requester.recv().await?;
+    assert_eq!(reply[0].as_str().unwrap(), "world");
+
+    Ok(())
+}
@@ -1,6 +1,14 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
 use std::time::Duration;
+use std::vec::IntoIter;
 
-use async_zmq::{reply, request, Message, Result};
+use async_zmq::{
+    context::Context, dealer, reply, req_rep_pair, request, AsRawSocket, Message, Multipart,
+    MultipartIter, Request, RequestReplyError, Result, StreamExt,
+};
+use futures::future::poll_fn;
 
 #[async_std::test]
 async fn publish_subscribe_message() -> Result<()> {
@@ -25,3 +33,342 @@ async fn publish_subscribe_message() -> Result<()> {
 
     Ok(())
 }
+
+#[async_std::test]
+async fn req_rep_pair_round_trip() -> Result<()> {
+    let context = Context::new();
+    let (request, reply) = req_rep_pair(&context)?;
+
+    request.send(Message::from("Hello")).await?;
+    let recv = reply.recv().await?;
+    assert_eq!(recv[0].as_str(), Some("Hello"));
+
+    reply.send(Message::from("World")).await?;
+    let recv = request.recv().await?;
+    assert_eq!(recv[0].as_str(), Some("World"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reply_routes_back_to_dealer_peers() -> Result<()> {
+    let uri = "inproc://reply_routes_back_to_dealer_peers";
+    let context = Context::new();
+    let reply = reply::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+
+    let mut dealers = Vec::new();
+    for name in ["first", "second"] {
+        let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+            .with_context(&context)
+            .connect()?;
+        dealer.send(vec!["", name].into()).await?;
+        dealers.push(dealer);
+    }
+
+    for _ in 0..2 {
+        let request = reply.recv().await?;
+        let name = request[0].as_str().unwrap().to_owned();
+        reply.send(vec![Message::from(name.as_str())]).await?;
+    }
+
+    for (dealer, name) in dealers.iter_mut().zip(["first", "second"]) {
+        let msg = dealer.next().await.unwrap()?;
+        assert_eq!(msg.len(), 2);
+        assert_eq!(msg[0].len(), 0);
+        assert_eq!(msg[1].as_str(), Some(name));
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reply_without_request_has_no_peer() -> Result<()> {
+    let context = Context::new();
+    let (_request, reply) = req_rep_pair(&context)?;
+
+    let result = reply.send(Message::from("orphan")).await;
+    assert!(matches!(result, Err(RequestReplyError::NoPeerCaptured)));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reply_send_stream_as_one_multipart() -> Result<()> {
+    let context = Context::new();
+    let (request, reply) = req_rep_pair::<IntoIter<Message>, Message>(&context)?;
+
+    request.send(vec![Message::from("page")]).await?;
+    reply.recv().await?;
+
+    let pages = futures::stream::iter(0..3).then(|page| async move {
+        async_std::task::sleep(Duration::from_millis(20)).await;
+        Message::from(format!("page-{}", page).as_str())
+    });
+    let (sent, received) = futures::future::join(reply.send_stream(pages), request.recv()).await;
+    sent?;
+
+    let received = received?;
+    assert_eq!(received.len(), 3);
+    for (page, frame) in received.iter().enumerate() {
+        assert_eq!(frame.as_str(), Some(format!("page-{}", page).as_str()));
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn timeouts_end_waits() -> Result<()> {
+    let context = Context::new();
+    let (mut client, reply) = req_rep_pair::<IntoIter<&str>, &str>(&context)?;
+    client.set_receive_timeout(100)?;
+    assert_eq!(client.get_receive_timeout()?, 100);
+
+    client.send(vec!["unanswered"]).await?;
+    let started = std::time::Instant::now();
+    let error = client.recv().await.unwrap_err();
+    assert!(matches!(error, RequestReplyError::TimedOut));
+    assert_eq!(zmq::Error::from(error), zmq::Error::EAGAIN);
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    drop(reply);
+
+    // Nobody is connected to a freshly bound socket, so the request cannot be queued.
+    let mut lonely = request::<IntoIter<&str>, &str>("inproc://timeouts_end_waits")?
+        .with_context(&context)
+        .bind()?;
+    lonely.set_send_timeout(50)?;
+    assert_eq!(lonely.get_send_timeout()?, 50);
+    let error = lonely.send(vec!["nobody"]).await.unwrap_err();
+    assert!(matches!(error, RequestReplyError::TimedOut));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_ref_retries_with_the_same_message() -> Result<()> {
+    let uri = "inproc://send_ref_retries_with_the_same_message";
+    let context = Context::new();
+    let mut client = request::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    client.set_send_timeout(50)?;
+
+    // No peer is connected yet, so the first attempt times out without taking any frame.
+    let mut msg = vec!["retried", "request"].into();
+    let error = client.send_ref(&mut msg).await.unwrap_err();
+    assert!(matches!(error, RequestReplyError::TimedOut));
+    assert_eq!(msg.0.as_slice(), ["retried", "request"]);
+
+    let server = reply::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+    client.send_ref(&mut msg).await?;
+    assert!(msg.0.next().is_none());
+
+    let request = server.recv().await?;
+    assert_eq!(request.len(), 2);
+    assert_eq!(request[0].as_str(), Some("retried"));
+    assert_eq!(request[1].as_str(), Some("request"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn monitor_reports_connection() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5583";
+    // ØMQ keeps trying to connect until the server is up, so no event is missed.
+    let client = request::<IntoIter<&str>, &str>(uri)?.connect()?;
+    let mut events = client.monitor(zmq::SocketEvent::ALL)?;
+    let _server = reply::<IntoIter<&str>, &str>(uri)?.bind()?;
+
+    let connected = async {
+        while let Some(event) = events.next().await {
+            let event = event?;
+            if event.event == zmq::SocketEvent::CONNECTED {
+                return Result::Ok(event);
+            }
+        }
+        panic!("the monitor stopped before the connection");
+    };
+    let timeout = Box::pin(async_std::task::sleep(Duration::from_secs(5)));
+    let event = match futures::future::select(Box::pin(connected), timeout).await {
+        futures::future::Either::Left((event, _)) => event?,
+        futures::future::Either::Right(_) => panic!("no connection was reported"),
+    };
+    assert_eq!(event.address, uri);
+
+    Ok(())
+}
+
+/// Future sending a request then receiving its reply through the poll API.
+struct RoundTrip<'a> {
+    client: &'a Request<IntoIter<&'static str>, &'static str>,
+    msg: MultipartIter<IntoIter<&'static str>, &'static str>,
+    sent: bool,
+}
+
+impl Future for RoundTrip<'_> {
+    type Output = std::result::Result<Multipart, RequestReplyError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if !this.sent {
+            futures::ready!(this.client.poll_send(cx, &mut this.msg))?;
+            this.sent = true;
+        }
+        this.client.poll_recv(cx)
+    }
+}
+
+#[async_std::test]
+async fn poll_api_drives_round_trip() -> Result<()> {
+    let context = Context::new();
+    let (client, server) = req_rep_pair::<IntoIter<&str>, &str>(&context)?;
+
+    let round_trip = RoundTrip {
+        client: &client,
+        msg: vec!["ping"].into(),
+        sent: false,
+    };
+    let serve = async {
+        let request = poll_fn(|cx| server.poll_recv(cx)).await?;
+        assert_eq!(request[0].as_str(), Some("ping"));
+        let mut reply = vec!["pong"].into();
+        poll_fn(|cx| server.poll_send(cx, &mut reply)).await
+    };
+
+    let (reply, served) = futures::join!(round_trip, serve);
+    served?;
+    assert_eq!(reply?[0].as_str(), Some("pong"));
+
+    // A reply socket without a pending request refuses to send.
+    let mut orphan = vec!["orphan"].into();
+    let error = poll_fn(|cx| server.poll_send(cx, &mut orphan))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, RequestReplyError::NoPeerCaptured));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn concurrent_recv_is_rejected() -> Result<()> {
+    let context = Context::new();
+    let (request, reply) = req_rep_pair::<IntoIter<&str>, &str>(&context)?;
+
+    let (first, second, sent) = futures::join!(reply.recv(), reply.recv(), async {
+        async_std::task::sleep(Duration::from_millis(50)).await;
+        request.send(vec!["ping"]).await
+    });
+    sent?;
+    let results = [first, second];
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    assert!(results
+        .iter()
+        .any(|result| matches!(result, Err(RequestReplyError::ConcurrentAccess))));
+
+    // The socket is usable again once the operations are over.
+    reply.send(vec!["pong"]).await?;
+    assert!(request.recv().await?.frame_eq_str(0, "pong"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn recv_reports_peer_disconnected() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5590";
+    let context = Context::new();
+    let server = reply::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut client = request::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+    // A timeout, so that a missed disconnection fails the test instead of hanging it.
+    client.detect_disconnects()?.set_receive_timeout(5000)?;
+
+    client.send(vec!["unanswered"]).await?;
+    assert!(server.recv().await?.frame_eq_str(0, "unanswered"));
+    server.close_now();
+
+    let error = client.recv().await.unwrap_err();
+    assert!(matches!(error, RequestReplyError::PeerDisconnected));
+    assert_eq!(zmq::Error::from(error), zmq::Error::ENOTCONN);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn detect_disconnects_survives_another_monitor() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5597";
+    let context = Context::new();
+    let server = reply::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut client = request::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+    client.detect_disconnects()?.set_receive_timeout(5000)?;
+    let _events = client.monitor(zmq::SocketEvent::ALL)?;
+
+    client.send(vec!["unanswered"]).await?;
+    assert!(server.recv().await?.frame_eq_str(0, "unanswered"));
+    server.close_now();
+
+    let error = client.recv().await.unwrap_err();
+    assert!(matches!(error, RequestReplyError::PeerDisconnected));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn recv_timeout_returns_none_without_request() -> Result<()> {
+    let context = Context::new();
+    let (request, reply) = req_rep_pair::<IntoIter<&str>, &str>(&context)?;
+
+    assert!(reply
+        .recv_timeout(Duration::from_millis(50))
+        .await?
+        .is_none());
+    request.send(vec!["ping"]).await?;
+    let msg = reply.recv_timeout(Duration::from_secs(5)).await?.unwrap();
+    assert!(msg.frame_eq_str(0, "ping"));
+
+    assert!(request
+        .recv_timeout(Duration::from_millis(50))
+        .await?
+        .is_none());
+    reply.send(vec!["pong"]).await?;
+    let msg = request.recv_timeout(Duration::from_secs(5)).await?.unwrap();
+    assert!(msg.frame_eq_str(0, "pong"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn try_recv_returns_pending_request() -> Result<()> {
+    let context = Context::new();
+    let (request, reply) = req_rep_pair::<IntoIter<&str>, &str>(&context)?;
+
+    assert!(reply.try_recv()?.is_none());
+    request.send(vec!["ping"]).await?;
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    let msg = loop {
+        if let Some(msg) = reply.try_recv()? {
+            break msg;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "no request within the deadline"
+        );
+        async_std::task::sleep(Duration::from_millis(10)).await;
+    };
+    assert!(msg.frame_eq_str(0, "ping"));
+
+    reply.send(vec!["pong"]).await?;
+    assert!(request.recv().await?.frame_eq_str(0, "pong"));
+
+    Ok(())
+}
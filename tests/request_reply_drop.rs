@@ -0,0 +1,42 @@
+#![cfg(all(feature = "tracing", debug_assertions))]
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use async_zmq::{reply, request, Message, Result};
+
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn warn_on_request_dropped_before_reply() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5575";
+    let _reply = reply::<std::vec::IntoIter<Message>, Message>(uri)?.bind()?;
+    let request = request(uri)?.connect()?;
+    request.send(Message::from("Hello")).await?;
+
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || drop(request));
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("WARN"));
+    assert!(output.contains("REQ socket dropped"));
+
+    Ok(())
+}
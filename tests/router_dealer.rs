@@ -0,0 +1,477 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+use std::vec::IntoIter;
+
+use async_zmq::{
+    context::Context, dealer, reply, router, Message, Multipart, Result, SendOverflow, SinkExt,
+    StreamExt, WorkerPool,
+};
+use futures::future::{join_all, select, Either};
+
+#[async_std::test]
+async fn router_broadcast_reaches_all_dealers() -> Result<()> {
+    let uri = "inproc://router_broadcast";
+    let context = Context::new();
+    let mut router = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+
+    let mut dealers = Vec::new();
+    for _ in 0..3 {
+        let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+            .with_context(&context)
+            .connect()?;
+        dealer.send(vec!["hello"].into()).await?;
+        dealers.push(dealer);
+    }
+    for _ in 0..3 {
+        let msg = router.next().await.unwrap()?;
+        assert_eq!(msg[1].as_str(), Some("hello"));
+    }
+    assert_eq!(router.peers().count(), 3);
+
+    let body: Multipart = vec![Message::from("shutdown")].into();
+    router.broadcast(body).await?;
+
+    for dealer in dealers.iter_mut() {
+        let msg = dealer.next().await.unwrap()?;
+        assert_eq!(msg.len(), 1);
+        assert_eq!(msg[0].as_str(), Some("shutdown"));
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn router_broadcast_after_queued_messages() -> Result<()> {
+    let uri = "inproc://router_broadcast_after_queued_messages";
+    let context = Context::new();
+    let mut router = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .with_send_queue(4, SendOverflow::Block)
+        .bind()?;
+    let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+    dealer.send(vec!["hello"].into()).await?;
+    let identity = router.next().await.unwrap()?.remove(0).unwrap();
+
+    // Queued without flushing, so it is still waiting when the broadcast starts.
+    let direct = vec![identity, Message::from("direct")];
+    router.feed(direct.into()).await?;
+    router
+        .broadcast(vec![Message::from("broadcast")].into())
+        .await?;
+
+    let msg = dealer.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("direct"));
+    let msg = dealer.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("broadcast"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn router_forgets_disconnected_peers() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5601";
+    let context = Context::new();
+    let mut router = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+
+    let mut dealers = Vec::new();
+    for _ in 0..2 {
+        let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+            .with_context(&context)
+            .connect()?;
+        dealer.send(vec!["hello"].into()).await?;
+        router.next().await.unwrap()?;
+        dealers.push(dealer);
+    }
+    assert_eq!(router.tracked_peers(), 2);
+
+    dealers.pop().unwrap().close_now();
+    for _ in 0..50 {
+        router.broadcast(vec![Message::from("ping")].into()).await?;
+        if router.tracked_peers() == 1 {
+            break;
+        }
+        async_std::task::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(router.tracked_peers(), 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn router_recv_survives_cancellation() -> Result<()> {
+    let uri = "inproc://router_recv_survives_cancellation";
+    let context = Context::new();
+    let mut router = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+
+    let mut received = 0;
+    for round in 0..50 {
+        if round % 5 == 0 {
+            dealer.send(vec!["one", "two", "three"].into()).await?;
+        }
+        let timeout = Box::pin(async_std::task::sleep(Duration::from_micros(50)));
+        if let Either::Left((msg, _)) = select(router.next(), timeout).await {
+            let msg = msg.unwrap()?;
+            assert_eq!(msg.len(), 4);
+            assert_eq!(msg[1].as_str(), Some("one"));
+            assert_eq!(msg[3].as_str(), Some("three"));
+            received += 1;
+        }
+    }
+    while received < 10 {
+        let msg = router.next().await.unwrap()?;
+        assert_eq!(msg.len(), 4);
+        received += 1;
+    }
+
+    dealer.send(vec!["clean"].into()).await?;
+    let msg = router.next().await.unwrap()?;
+    assert_eq!(msg.len(), 2);
+    assert_eq!(msg[1].as_str(), Some("clean"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn worker_pool_distributes_requests() -> Result<()> {
+    let context = Context::new();
+    let pool = WorkerPool::bind(&context, "inproc://pool_frontend", "inproc://pool_backend")?;
+    let running = async_std::task::spawn(pool.run());
+
+    // Reply sockets aren't Sync, so workers and clients are driven from this task.
+    let mut workers = Vec::new();
+    for name in ["worker-a", "worker-b"] {
+        let worker = reply::<IntoIter<&str>, &str>("inproc://pool_backend")?
+            .with_context(&context)
+            .connect()?;
+        workers.push(async move {
+            while worker.recv().await.is_ok() {
+                if worker.send(vec![name]).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let clients = async {
+        async_std::task::sleep(Duration::from_millis(100)).await;
+        let mut clients = Vec::new();
+        for _ in 0..3 {
+            let mut client = dealer::<IntoIter<&str>, &str>("inproc://pool_frontend")?
+                .with_context(&context)
+                .connect()?;
+            client.send(vec!["", "request"].into()).await?;
+            clients.push(client);
+        }
+
+        let mut served_by = BTreeSet::new();
+        for client in clients.iter_mut() {
+            let msg = client.next().await.unwrap()?;
+            assert!(msg.frame_is_empty(0));
+            served_by.insert(msg[1].as_str().unwrap().to_string());
+        }
+        Result::Ok(served_by)
+    };
+
+    match select(Box::pin(join_all(workers)), Box::pin(clients)).await {
+        Either::Left(_) => panic!("workers stopped before serving all the clients"),
+        Either::Right((served_by, _)) => assert_eq!(served_by?.len(), 2),
+    }
+
+    context.shutdown()?;
+    running.await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn worker_pool_closes_sockets_when_cancelled() -> Result<()> {
+    let context = Context::new();
+    let pool = WorkerPool::bind(
+        &context,
+        "inproc://cancel_frontend",
+        "inproc://cancel_backend",
+    )?;
+    let cancelled = async_std::future::timeout(Duration::from_millis(50), pool.run()).await;
+    assert!(cancelled.is_err());
+
+    // Dropping the sockets released their inproc names.
+    assert!(context.inproc_endpoints().is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn connect_routing_id_names_peers() -> Result<()> {
+    let context = Context::new();
+
+    // A ROUTER connecting to a DEALER names it, and can route to it straight away.
+    let mut worker = dealer::<IntoIter<&str>, &str>("inproc://connect_routing_id_worker")?
+        .with_context(&context)
+        .bind()?;
+    let mut router = router::<IntoIter<&str>, &str>("inproc://connect_routing_id_router")?
+        .with_context(&context)
+        .bind()?;
+    router.set_connect_routing_id(b"worker-1")?;
+    router
+        .as_raw_socket()
+        .connect("inproc://connect_routing_id_worker")?;
+    router.send(vec!["worker-1", "job"].into()).await?;
+    let msg = worker.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("job"));
+
+    worker.send(vec!["done"].into()).await?;
+    let msg = router.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("worker-1"));
+    assert_eq!(msg[1].as_str(), Some("done"));
+
+    // A bound DEALER presents the id it was given to the ROUTER it connects to next.
+    let mut client = dealer::<IntoIter<&str>, &str>("inproc://connect_routing_id_idle")?
+        .with_context(&context)
+        .bind()?;
+    client.set_identity(b"client-1")?;
+    client
+        .as_raw_socket()
+        .connect("inproc://connect_routing_id_router")?;
+    client.send(vec!["hello"].into()).await?;
+    let msg = router.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("client-1"));
+    assert_eq!(msg[1].as_str(), Some("hello"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn dealer_round_trips_with_reply() -> Result<()> {
+    let uri = "inproc://dealer_round_trips_with_reply";
+    let context = Context::new();
+    let reply = reply::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .connect()?;
+    dealer.set_send_hwm(100)?.set_receive_hwm(200)?;
+    assert_eq!(dealer.get_send_hwm()?, 100);
+    assert_eq!(dealer.get_receive_hwm()?, 200);
+
+    dealer.send(vec!["", "ping", "twice"].into()).await?;
+    let request = reply.recv().await?;
+    assert_eq!(request.len(), 2);
+    assert_eq!(request[0].as_str(), Some("ping"));
+    reply.send(request).await?;
+
+    let answer = dealer.recv().await?;
+    assert_eq!(answer.len(), 3);
+    assert!(answer.frame_is_empty(0));
+    assert_eq!(answer[1].as_str(), Some("ping"));
+    assert_eq!(answer[2].as_str(), Some("twice"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn peer_limit_bounds_tracked_peers() -> Result<()> {
+    let uri = "inproc://peer_limit_bounds_tracked_peers";
+    let context = Context::new();
+    let mut router = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    router.set_peer_limit(2);
+
+    let mut dealers = Vec::new();
+    for name in ["a", "b", "c", "d"] {
+        let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+            .with_context(&context)
+            .connect()?;
+        dealer.send(vec![name].into()).await?;
+        router.next().await.unwrap()?;
+        assert!(router.tracked_peers() <= 2);
+        dealers.push(dealer);
+    }
+    assert_eq!(router.tracked_peers(), 2);
+
+    // The first peer was evicted, and is tracked again once it speaks.
+    dealers[0].send(vec!["a"].into()).await?;
+    let msg = router.next().await.unwrap()?;
+    assert!(router.peers().any(|peer| peer == &msg[0][..]));
+    assert_eq!(router.tracked_peers(), 2);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn idle_peers_expire_while_receiving() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5582";
+    let mut router = router::<IntoIter<Message>, Message>(uri)?.bind()?;
+    router.set_peer_idle_timeout(Duration::from_millis(50));
+
+    let mut quiet = dealer::<IntoIter<&str>, &str>(uri)?.connect()?;
+    quiet.send(vec!["hello"].into()).await?;
+    router.next().await.unwrap()?;
+    assert_eq!(router.tracked_peers(), 1);
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    // The quiet peer is still connected, but forgotten once another one speaks.
+    let mut active = dealer::<IntoIter<&str>, &str>(uri)?.connect()?;
+    active.send(vec!["hello"].into()).await?;
+    let msg = router.next().await.unwrap()?;
+    assert_eq!(router.tracked_peers(), 1);
+    assert!(router.peers().all(|peer| peer == &msg[0][..]));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn router_routes_by_dealer_identity() -> Result<()> {
+    let uri = "inproc://router_routes_by_dealer_identity";
+    let context = Context::new();
+    let mut router = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    router.set_router_mandatory(true)?;
+
+    let mut dealers = Vec::new();
+    for name in ["alice", "bob"] {
+        let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+            .with_context(&context)
+            .configure(move |socket| socket.set_identity(name.as_bytes()))
+            .connect()?;
+        dealer.send(vec!["hello", name].into()).await?;
+        dealers.push(dealer);
+    }
+    for _ in 0..2 {
+        let msg = router.recv().await?;
+        assert_eq!(msg.len(), 3);
+        assert_eq!(msg[0].as_str(), msg[2].as_str());
+    }
+
+    for name in ["bob", "alice"] {
+        let reply = vec![Message::from(name), Message::from(&format!("for {}", name))];
+        router.send(reply.into()).await?;
+    }
+    for (dealer, name) in dealers.iter_mut().zip(["alice", "bob"]) {
+        let msg = dealer.recv().await?;
+        assert_eq!(msg.len(), 1);
+        assert_eq!(msg[0].as_str(), Some(format!("for {}", name).as_str()));
+    }
+
+    let unknown = vec![Message::from("carol"), Message::from("lost")];
+    let error = router.send(unknown.into()).await.unwrap_err();
+    assert_eq!(
+        async_zmq::Error::from(error),
+        async_zmq::Error::EHOSTUNREACH
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn proxy_forwards_requests_and_replies() -> Result<()> {
+    let context = Context::new();
+    let frontend = router::<IntoIter<Message>, Message>("inproc://proxy_frontend")?
+        .with_context(&context)
+        .bind()?;
+    let backend = dealer::<IntoIter<Message>, Message>("inproc://proxy_backend")?
+        .with_context(&context)
+        .bind()?;
+    let capture = async_zmq::push::<IntoIter<Message>, Message>("inproc://proxy_capture")?
+        .with_context(&context)
+        .bind()?;
+    let mut captured = async_zmq::pull("inproc://proxy_capture")?
+        .with_context(&context)
+        .connect()?;
+    async_std::task::spawn(async_zmq::proxy_with_capture(frontend, backend, capture));
+
+    let worker = reply::<IntoIter<&str>, &str>("inproc://proxy_backend")?
+        .with_context(&context)
+        .connect()?;
+    let client = async_zmq::request::<IntoIter<&str>, &str>("inproc://proxy_frontend")?
+        .with_context(&context)
+        .connect()?;
+
+    client.send(vec!["ping"]).await?;
+    let request = worker.recv().await?;
+    assert_eq!(request.len(), 1);
+    assert_eq!(request[0].as_str(), Some("ping"));
+    worker.send(vec!["pong"]).await?;
+    let reply = client.recv().await?;
+    assert_eq!(reply[0].as_str(), Some("pong"));
+
+    // The capture sees both directions, envelope included.
+    let request = captured.next().await.unwrap()?;
+    assert_eq!(
+        request.last().and_then(|frame| frame.as_str()),
+        Some("ping")
+    );
+    let reply = captured.next().await.unwrap()?;
+    assert_eq!(reply.last().and_then(|frame| frame.as_str()), Some("pong"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn router_sees_dealer_identity() -> Result<()> {
+    let uri = "inproc://router_sees_dealer_identity";
+    let context = Context::new();
+    let mut router = router::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut dealer = dealer::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .with_identity(b"worker-1")
+        .connect()?;
+    assert_eq!(dealer.get_identity()?, b"worker-1");
+
+    dealer.send(vec!["ready"].into()).await?;
+    let msg = router.recv().await?;
+    assert!(msg.frame_eq(0, b"worker-1"));
+
+    // The routing id of a connected socket was already exchanged.
+    let result = dealer.set_identity(b"worker-2").map(|_| ());
+    assert_eq!(result, Err(async_zmq::Error::EINVAL));
+    // A bound socket presents it to the peers connecting afterwards.
+    router.set_identity(b"frontend")?;
+    assert_eq!(router.get_identity()?, b"frontend");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn router_reads_dealer_metadata() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5587";
+    let context = Context::new();
+    let mut router = router::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let dealer = dealer::<IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .with_metadata("X-Client-Version", "1.2.0")
+        .connect();
+    let mut dealer = match dealer {
+        Ok(dealer) => dealer,
+        // `ZMQ_METADATA` is only available in libzmq builds with the draft API.
+        Err(async_zmq::SocketError::InvalidSocketType) => return Ok(()),
+        Err(error) => return Err(error.into()),
+    };
+
+    dealer.send(vec!["hello"].into()).await?;
+    let mut msg = router.recv().await?;
+    assert_eq!(msg[1].gets("X-Client-Version"), Some("1.2.0"));
+
+    // The metadata of a connected socket was already exchanged.
+    let result = dealer.set_metadata("X-Client-Name", "worker").map(|_| ());
+    assert_eq!(result, Err(async_zmq::Error::EINVAL));
+
+    Ok(())
+}
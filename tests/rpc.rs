@@ -0,0 +1,80 @@
+use std::time::Duration;
+use std::vec::IntoIter;
+
+use async_zmq::{
+    context::Context, router, rpc_client, Message, Multipart, Result, RpcError, StreamExt,
+};
+use futures::future::{join, join_all};
+
+#[async_std::test]
+async fn concurrent_calls_match_replies() -> Result<()> {
+    let uri = "inproc://concurrent_calls_match_replies";
+    let context = Context::new();
+    let mut server = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let client = rpc_client(uri)?.with_context(&context).connect()?;
+
+    let calls = join_all((0..3).map(|i| {
+        let request = format!("request-{}", i).into_bytes();
+        client.call(Multipart::of([request]))
+    }));
+    // Replies are sent in the reverse order of the requests.
+    let serve = async {
+        let mut requests = Vec::new();
+        for _ in 0..3 {
+            requests.push(server.next().await.unwrap()?);
+        }
+        for mut request in requests.into_iter().rev() {
+            let body = format!("reply-to-{}", request[2].as_str().unwrap());
+            request[2] = Message::from(body.as_str());
+            server.send(request.into()).await?;
+        }
+        Result::Ok(())
+    };
+
+    let (replies, served) = join(calls, serve).await;
+    served?;
+    for (i, reply) in replies.into_iter().enumerate() {
+        let reply = reply?;
+        assert_eq!(reply.len(), 1);
+        assert_eq!(
+            reply[0].as_str(),
+            Some(format!("reply-to-request-{}", i).as_str())
+        );
+    }
+    assert_eq!(client.in_flight(), 0);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn late_reply_is_orphaned() -> Result<()> {
+    let uri = "inproc://late_reply_is_orphaned";
+    let context = Context::new();
+    let mut server = router::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let client = rpc_client(uri)?.with_context(&context).connect()?;
+
+    let (reply, request) = join(
+        client.call_timeout(Multipart::of(["slow"]), Duration::from_millis(50)),
+        server.next(),
+    )
+    .await;
+    assert!(matches!(reply, Err(RpcError::Timeout)));
+    assert_eq!(client.in_flight(), 0);
+    server.send(request.unwrap()?.into()).await?;
+
+    let serve = async {
+        let request = server.next().await.unwrap()?;
+        server.send(request.into()).await?;
+        Result::Ok(())
+    };
+    let (reply, served) = join(client.call(Multipart::of(["fast"])), serve).await;
+    served?;
+    assert_eq!(reply?[0].as_str(), Some("fast"));
+    assert_eq!(client.orphaned_replies(), 1);
+
+    Ok(())
+}
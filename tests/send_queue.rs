@@ -0,0 +1,98 @@
+use std::time::Duration;
+use std::vec::IntoIter;
+
+use async_zmq::{
+    context::Context, pull, push, request, Push, Result, SendOverflow, SinkExt, SocketError,
+    StreamExt,
+};
+use futures::future::{join, select, Either};
+
+async fn queued_push(
+    context: &Context,
+    uri: &str,
+    overflow: SendOverflow,
+) -> Result<Push<IntoIter<&'static str>, &'static str>> {
+    // Without any peer, PUSH cannot take messages and they pile up in the send queue.
+    let mut push = push(uri)?
+        .with_context(context)
+        .with_send_queue(2, overflow)
+        .bind()?;
+    for msg in ["first", "second"] {
+        push.feed(vec![msg].into()).await?;
+    }
+    Ok(push)
+}
+
+async fn received(context: &Context, uri: &str, count: usize) -> Result<Vec<String>> {
+    let mut pull = pull(uri)?.with_context(context).connect()?;
+    let mut received = Vec::new();
+    for _ in 0..count {
+        let msg = pull.next().await.unwrap()?;
+        received.push(msg[0].as_str().unwrap().to_string());
+    }
+    Ok(received)
+}
+
+#[async_std::test]
+async fn block_when_queue_is_full() -> Result<()> {
+    let uri = "inproc://send_queue_block";
+    let context = Context::new();
+    let mut push = queued_push(&context, uri, SendOverflow::Block).await?;
+
+    let feed = push.feed(vec!["third"].into());
+    let timeout = async_std::task::sleep(Duration::from_millis(100));
+    assert!(matches!(
+        select(Box::pin(feed), Box::pin(timeout)).await,
+        Either::Right(_)
+    ));
+    assert_eq!(push.dropped_messages(), 0);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn drop_oldest_when_queue_is_full() -> Result<()> {
+    let uri = "inproc://send_queue_drop_oldest";
+    let context = Context::new();
+    let mut push = queued_push(&context, uri, SendOverflow::DropOldest).await?;
+
+    push.feed(vec!["third"].into()).await?;
+    assert_eq!(push.dropped_messages(), 1);
+
+    let (flushed, received) = join(push.flush(), received(&context, uri, 2)).await;
+    flushed?;
+    assert_eq!(received?, vec!["second", "third"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn drop_newest_when_queue_is_full() -> Result<()> {
+    let uri = "inproc://send_queue_drop_newest";
+    let context = Context::new();
+    let mut push = queued_push(&context, uri, SendOverflow::DropNewest).await?;
+
+    push.feed(vec!["third"].into()).await?;
+    assert_eq!(push.dropped_messages(), 1);
+
+    let (flushed, received) = join(push.flush(), received(&context, uri, 2)).await;
+    flushed?;
+    assert_eq!(received?, vec!["first", "second"]);
+
+    Ok(())
+}
+
+#[test]
+fn reject_send_queue_without_sink() -> Result<()> {
+    // Request sends in lockstep, without going through a send queue.
+    let built = request::<IntoIter<&str>, &str>("inproc://send_queue_request")?
+        .with_send_queue(2, SendOverflow::Block)
+        .bind();
+    assert!(matches!(built, Err(SocketError::InvalidSocketType)));
+    let built = pull("inproc://send_queue_pull")?
+        .with_send_queue(2, SendOverflow::Block)
+        .connect();
+    assert!(matches!(built, Err(SocketError::InvalidSocketType)));
+
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use std::time::{Duration, Instant};
+
+use async_zmq::{
+    context::Context, dealer, pull, push, router, AsRawSocket, Error, Result, SinkExt, SocketError,
+    StreamExt,
+};
+
+#[async_std::test]
+async fn bind_retry_after_unbind() -> Result<()> {
+    let uri = "tcp://127.0.0.1:5571";
+    let context = Context::new();
+
+    let first = pull(uri)?.with_context(&context).bind()?;
+    first.as_raw_socket().unbind(uri)?;
+
+    let second = pull(uri)?
+        .with_context(&context)
+        .bind_retry(20, Duration::from_millis(50))
+        .await?;
+    assert_eq!(second.as_raw_socket().get_last_endpoint()?.unwrap(), uri);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn connect_async_to_hostname() -> Result<()> {
+    let context = Context::new();
+    let mut pull = pull("tcp://127.0.0.1:*")?.with_context(&context).bind()?;
+    let endpoint = pull.as_raw_socket().get_last_endpoint()?.unwrap();
+    let hostname_endpoint = endpoint.replace("127.0.0.1", "localhost");
+
+    let mut push = push(&hostname_endpoint)?
+        .with_context(&context)
+        .connect_async()
+        .await?;
+    push.send(vec!["Hello"].into()).await?;
+
+    let msg = pull.next().await.unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("Hello"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn bind_async_to_wildcard() -> Result<()> {
+    let pull = pull("tcp://127.0.0.1:*")?.bind_async().await?;
+
+    assert!(pull.as_raw_socket().get_last_endpoint()?.is_ok());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn connect_async_to_unresolvable_hostname() -> Result<()> {
+    let started = Instant::now();
+    let result = pull("tcp://does-not-exist.invalid:5573")?
+        .resolve_timeout(Duration::from_millis(500))
+        .connect_async()
+        .await;
+
+    assert!(matches!(
+        result.err(),
+        Some(SocketError::Unexpected(Error::EHOSTUNREACH | Error::EAGAIN))
+    ));
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reconnect_jitter_within_band() -> Result<()> {
+    let context = Context::new();
+    let mut intervals = Vec::new();
+    for _ in 0..10 {
+        let socket = pull("tcp://127.0.0.1:5574")?
+            .with_context(&context)
+            .with_reconnect_jitter(Duration::from_millis(100), Duration::from_millis(1000))
+            .connect()?;
+        intervals.push(socket.as_raw_socket().get_reconnect_ivl()?);
+    }
+
+    assert!(intervals.iter().all(|ivl| (100..=1100).contains(ivl)));
+    intervals.sort_unstable();
+    intervals.dedup();
+    assert!(intervals.len() > 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn inproc_name_taken() -> Result<()> {
+    let context = Context::new();
+    let first = pull("inproc://events")?.with_context(&context).bind()?;
+    assert_eq!(context.inproc_endpoints(), vec!["events".to_string()]);
+
+    let second = pull("inproc://events")?.with_context(&context).bind();
+    assert!(matches!(second, Err(SocketError::InprocNameTaken(name)) if name == "events"));
+
+    drop(first);
+    assert!(context.inproc_endpoints().is_empty());
+
+    // ØMQ releases the name once the closed socket has been reaped in the background.
+    pull("inproc://events")?
+        .with_context(&context)
+        .bind_retry(20, Duration::from_millis(50))
+        .await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn configure_sets_raw_option() -> Result<()> {
+    let context = Context::new();
+    let pull = pull("inproc://configure_sets_raw_option")?
+        .with_context(&context)
+        .configure(|raw| raw.set_tos(0x10))
+        .bind()?;
+
+    assert_eq!(pull.as_raw_socket().get_tos()?, 0x10);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn set_raw_option_before_bind() -> Result<()> {
+    const ZMQ_ROUTING_ID: i32 = 5;
+    const ZMQ_SNDHWM: i32 = 23;
+
+    let context = Context::new();
+    let push = push::<std::vec::IntoIter<&str>, &str>("inproc://set_raw_option_before_bind")?
+        .with_context(&context)
+        .set_raw_option(ZMQ_ROUTING_ID, b"worker-1")
+        .set_raw_option(ZMQ_SNDHWM, &42i32.to_ne_bytes())
+        .bind()?;
+
+    assert_eq!(push.get_raw_option(ZMQ_ROUTING_ID, 255)?, b"worker-1");
+    assert_eq!(push.get_raw_option(ZMQ_SNDHWM, 4)?, 42i32.to_ne_bytes());
+    assert_eq!(push.as_raw_socket().get_sndhwm()?, 42);
+
+    let invalid = pull("inproc://set_raw_option_invalid")?
+        .with_context(&context)
+        .set_raw_option(ZMQ_SNDHWM, b"x")
+        .bind();
+    match invalid {
+        Err(error) => assert_eq!(Error::from(error), Error::EINVAL),
+        Ok(_) => panic!("invalid option value accepted"),
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn configure_sets_identity_before_connect() -> Result<()> {
+    let uri = "inproc://configure_sets_identity";
+    let context = Context::new();
+    let mut router = router::<std::vec::IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let mut dealer = dealer::<std::vec::IntoIter<&str>, &str>(uri)?
+        .with_context(&context)
+        .configure(|socket| {
+            socket.set_identity(b"worker-7")?;
+            socket.set_linger(0)
+        })
+        .connect()?;
+    assert_eq!(dealer.as_raw_socket().get_identity()?, b"worker-7");
+    assert_eq!(dealer.as_raw_socket().get_linger()?, 0);
+
+    dealer.send(vec!["ready"].into()).await?;
+    let msg = router.recv().await?;
+    assert!(msg.frame_eq(0, b"worker-7"));
+    assert!(msg.frame_eq_str(1, "ready"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn chained_builder_configures_socket() -> Result<()> {
+    let mut builder = router::<std::vec::IntoIter<&str>, &str>("tcp://127.0.0.1:*")?
+        .with_identity(b"frontend")
+        .with_zap_domain("global")
+        .with_send_hwm(1000)
+        .with_receive_hwm(500);
+    // CURVE needs libzmq built with libsodium or tweetnacl.
+    let curve = zmq::has("curve").unwrap_or(false);
+    if curve {
+        builder = builder.with_curve_server(&async_zmq::CurveKeyPair::new()?);
+    }
+    let router = builder.bind()?;
+
+    let socket = router.as_raw_socket();
+    assert_eq!(socket.get_identity()?, b"frontend");
+    assert_eq!(socket.get_zap_domain()?.unwrap(), "global");
+    assert_eq!(socket.get_sndhwm()?, 1000);
+    assert_eq!(socket.get_rcvhwm()?, 500);
+    if curve {
+        assert!(socket.is_curve_server()?);
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn bind_with_endpoint_resolves_wildcard_port() -> Result<()> {
+    let (mut push, endpoint) = push("tcp://127.0.0.1:*")?.bind_with_endpoint()?;
+    let port: u16 = endpoint
+        .strip_prefix("tcp://127.0.0.1:")
+        .and_then(|port| port.parse().ok())
+        .unwrap_or_else(|| panic!("unexpected endpoint {}", endpoint));
+    assert_ne!(port, 0);
+
+    let mut pull = pull(endpoint.as_str())?.connect()?;
+    push.send(vec!["hello"].into()).await?;
+    assert!(pull.next().await.unwrap()?.frame_eq_str(0, "hello"));
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use async_zmq::stream::PeerEvent;
+use async_zmq::{Result, StreamExt};
+use futures::{AsyncReadExt, AsyncWriteExt};
+
+#[async_std::test]
+async fn peer_events_detect_disconnect() -> Result<()> {
+    let mut server = async_zmq::stream("tcp://127.0.0.1:5581")?.bind()?;
+    server.set_heartbeat(Duration::from_secs(10), Duration::from_secs(1), 3)?;
+    assert_eq!(server.as_raw_socket().get_tcp_keepalive()?, 1);
+    assert_eq!(server.as_raw_socket().get_tcp_keepalive_idle()?, 10);
+    let mut events = server.peer_events();
+
+    let mut peer = TcpStream::connect("127.0.0.1:5581").unwrap();
+    let id = match events.next().await.unwrap()? {
+        PeerEvent::Connected(id) => id,
+        event => panic!("unexpected {:?}", event),
+    };
+    assert_eq!(events.peers().count(), 1);
+
+    peer.write_all(b"hello").unwrap();
+    match events.next().await.unwrap()? {
+        PeerEvent::Data(from, data) => {
+            assert_eq!(from, id);
+            assert_eq!(data.frame_bytes(0), Some(&b"hello"[..]));
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+
+    drop(peer);
+    let event = async_std::future::timeout(Duration::from_secs(5), events.next())
+        .await
+        .expect("disconnect not detected");
+    match event.unwrap()? {
+        PeerEvent::Disconnected(from) => assert_eq!(from, id),
+        event => panic!("unexpected {:?}", event),
+    }
+    assert_eq!(events.peers().count(), 0);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn io_adapter_talks_to_tcp_listener() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:5595")?;
+    let echo = std::thread::spawn(move || {
+        let (mut peer, _) = listener.accept().unwrap();
+        let mut buf = [0; 64];
+        let len = peer.read(&mut buf).unwrap();
+        peer.write_all(&buf[..len]).unwrap();
+    });
+
+    let mut io = async_zmq::stream("tcp://127.0.0.1:5595")?
+        .connect()?
+        .into_io();
+    io.write_all(b"hello").await?;
+    assert!(io.peer().is_some());
+
+    let mut reply = [0; 5];
+    io.read_exact(&mut reply).await?;
+    assert_eq!(&reply, b"hello");
+
+    // The listener thread closes its end once done.
+    echo.join().unwrap();
+    let mut rest = Vec::new();
+    io.read_to_end(&mut rest).await?;
+    assert!(rest.is_empty());
+    io.close().await?;
+
+    Ok(())
+}
@@ -1,18 +1,19 @@
-use async_zmq::{Result, StreamExt};
+use async_zmq::{context::Context, Message, Publish, Result, StreamExt, Subscribe};
+use std::time::Duration;
+use std::vec::IntoIter;
 
 #[async_std::test]
 async fn test_pub_sub_watermarks() -> Result<()> {
     // Create publisher with watermark
-    let publisher = async_zmq::publish("tcp://127.0.0.1:*")?
-        .bind()?
-        .set_send_hwm(100)?;
+    let mut publisher = async_zmq::publish::<IntoIter<Message>, Message>("tcp://127.0.0.1:*")?
+        .bind()?;
+    publisher.set_send_hwm(100)?;
     
     let endpoint = publisher.as_raw_socket().get_last_endpoint()?.unwrap();
 
     // Create subscriber with watermark
-    let subscriber = async_zmq::subscribe(&endpoint)?
-        .connect()?
-        .set_receive_hwm(100)?;
+    let mut subscriber = async_zmq::subscribe(&endpoint)?.connect()?;
+    subscriber.set_receive_hwm(100)?;
 
     // Verify watermark settings
     assert_eq!(publisher.get_send_hwm()?, 100);
@@ -24,18 +25,14 @@ async fn test_pub_sub_watermarks() -> Result<()> {
 #[async_std::test]
 async fn test_req_rep_watermarks() -> Result<()> {
     // Create reply socket with watermarks
-    let reply: &async_zmq::Reply<_, _> = async_zmq::reply("tcp://127.0.0.1:*")?
-        .bind()?
-        .set_receive_hwm(100)?
-        .set_send_hwm(200)?;
+    let mut reply = async_zmq::reply::<IntoIter<Message>, Message>("tcp://127.0.0.1:*")?.bind()?;
+    reply.set_receive_hwm(100)?.set_send_hwm(200)?;
     
     let endpoint = reply.as_raw_socket().get_last_endpoint()?.unwrap();
 
     // Create request socket with watermarks
-    let request = async_zmq::request(&endpoint)?
-        .connect()?
-        .set_receive_hwm(100)?
-        .set_send_hwm(200)?;
+    let mut request = async_zmq::request::<IntoIter<Message>, Message>(&endpoint)?.connect()?;
+    request.set_receive_hwm(100)?.set_send_hwm(200)?;
 
     // Verify watermark settings
     assert_eq!(reply.get_receive_hwm()?, 100);
@@ -44,4 +41,92 @@ async fn test_req_rep_watermarks() -> Result<()> {
     assert_eq!(request.get_send_hwm()?, 200);
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Publish `count` messages without reading them, then count those the subscriber receives.
+async fn flood(
+    publisher: &mut Publish<IntoIter<Message>, Message>,
+    subscriber: Subscribe,
+    count: usize,
+) -> Result<(usize, Subscribe)> {
+    for _ in 0..count {
+        publisher.send_msg(Message::from("tick")).await?;
+    }
+    let mut stream = subscriber.idle_timeout(Duration::from_millis(200));
+    let mut received = 0;
+    while let Some(msg) = stream.next().await {
+        msg?;
+        received += 1;
+    }
+    Ok((received, stream.into_inner()))
+}
+
+#[async_std::test]
+async fn reset_connections_applies_watermarks() -> Result<()> {
+    let context = Context::new();
+    let endpoint = "inproc://reset-connections";
+    let mut publisher = async_zmq::publish(endpoint)?
+        .with_context(&context)
+        .configure(|socket| socket.set_sndhwm(10))
+        .bind()?;
+    let mut subscriber = async_zmq::subscribe(endpoint)?
+        .with_context(&context)
+        .connect()?;
+    subscriber.set_subscribe("")?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    // The live connection keeps the default watermark of 1000 messages of the subscriber.
+    subscriber.set_receive_hwm(10)?;
+    let (received, mut subscriber) = flood(&mut publisher, subscriber, 5000).await?;
+    assert!(received > 1000, "{}", received);
+
+    subscriber.reset_connections().await?;
+    async_std::task::sleep(Duration::from_millis(100)).await;
+    let (received, _) = flood(&mut publisher, subscriber, 5000).await?;
+    assert!(received > 0 && received <= 100, "{}", received);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reset_connections_rebinds() -> Result<()> {
+    let mut publisher = async_zmq::publish::<IntoIter<Message>, Message>("tcp://127.0.0.1:*")?
+        .bind()?;
+    let endpoint = publisher.as_raw_socket().get_last_endpoint()?.unwrap();
+    let mut subscriber = async_zmq::subscribe(&endpoint)?.connect()?;
+    subscriber.set_subscribe("")?;
+
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    // The subscriber reconnects to the rebound address after its reconnect interval.
+    publisher.reset_connections().await?;
+    for _ in 0..20 {
+        publisher.send_msg(Message::from("after reset")).await?;
+        let next = async_std::future::timeout(Duration::from_millis(100), subscriber.next());
+        if let Ok(msg) = next.await {
+            assert_eq!(msg.unwrap()?[0].as_str(), Some("after reset"));
+            return Ok(());
+        }
+    }
+    panic!("subscriber did not reconnect");
+}
+
+#[async_std::test]
+async fn linger_settings() -> Result<()> {
+    let context = Context::new();
+    let (mut request, mut reply) = async_zmq::req_rep_pair::<IntoIter<Message>, Message>(&context)?;
+    let (mut publisher, mut subscriber) =
+        async_zmq::pub_sub_pair::<IntoIter<Message>, Message>(&context)?;
+
+    request.set_linger(0)?;
+    reply.set_linger(100)?;
+    publisher.set_linger(200)?;
+    subscriber.set_linger(-1)?;
+
+    assert_eq!(request.get_linger()?, 0);
+    assert_eq!(reply.get_linger()?, 100);
+    assert_eq!(publisher.get_linger()?, 200);
+    assert_eq!(subscriber.get_linger()?, -1);
+
+    Ok(())
+}
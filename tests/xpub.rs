@@ -1,4 +1,4 @@
-use async_zmq::{subscribe, xpublish, Context, Message, Result, StreamExt};
+use async_zmq::{context::Context, subscribe, xpublish, xsubscribe, Message, Result, StreamExt};
 use std::vec::IntoIter;
 
 #[async_std::test]
@@ -8,7 +8,7 @@ async fn xpublish_subscribe() -> Result<()> {
     let mut xpublish = xpublish::<IntoIter<Message>, Message>(uri)?
         .with_context(&context)
         .bind()?;
-    let subscribe = subscribe(uri)?.with_context(&context).connect()?;
+    let mut subscribe = subscribe(uri)?.with_context(&context).connect()?;
 
     let topic = "Topic";
     subscribe.set_subscribe(topic)?;
@@ -23,3 +23,40 @@ async fn xpublish_subscribe() -> Result<()> {
 
     Ok(())
 }
+
+#[async_std::test]
+async fn xsubscribe_forwards_subscription() -> Result<()> {
+    let uri = "inproc://xpub_xsub_forward";
+    let context = Context::new();
+    let mut xpublish = xpublish::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let xsubscribe = xsubscribe(uri)?.with_context(&context).connect()?;
+
+    xsubscribe.send_subscription("Topic").await?;
+    let event = xpublish.next().await.unwrap()?;
+    assert_eq!(&event[0][..], b"\x01Topic");
+
+    xsubscribe.send_unsubscription("Topic").await?;
+    let event = xpublish.next().await.unwrap()?;
+    assert_eq!(&event[0][..], b"\x00Topic");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn xsubscribe_option_reaches_xpublish() -> Result<()> {
+    let uri = "inproc://xpub_xsub_option";
+    let context = Context::new();
+    let mut xpublish = xpublish::<IntoIter<Message>, Message>(uri)?
+        .with_context(&context)
+        .bind()?;
+    let xsubscribe = xsubscribe(uri)?.with_context(&context).connect()?;
+
+    xsubscribe.set_subscribe("topic")?;
+    let event = xpublish.next().await.unwrap()?;
+    assert_eq!(event.len(), 1);
+    assert_eq!(&event[0][..], b"\x01topic");
+
+    Ok(())
+}